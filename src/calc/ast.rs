@@ -1,5 +1,1460 @@
+use crate::calc::cell::{CellProvider, CellRef, NoCells};
+use crate::calc::complex::Complex;
+use crate::calc::context::Context;
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::evaluator::Evaluator;
+use crate::calc::function;
+use crate::calc::locale::Locale;
+use crate::calc::matrix::Matrix;
+use crate::calc::numeric::Numeric;
+use crate::calc::span::Span;
+use crate::calc::token::OperatorPrecedence;
+use crate::calc::value::{Value, ValueType};
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
+/// A binary operator appearing in an expression tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    // +
+    Add,
+    // -
+    Sub,
+    // *
+    Mul,
+    // /
+    Div,
+    // ^
+    Caret,
+    // %，余数与除数同号
+    Rem,
+    // //，向下取整的除法
+    IntDiv,
+}
 
-pub enum Node{
-    
-}
\ No newline at end of file
+impl BinaryOp {
+    /// Returns the precedence the parser assigns to this operator.
+    pub fn get_precedence(&self) -> OperatorPrecedence {
+        match self {
+            Self::Add | Self::Sub => OperatorPrecedence::AddOrSubtract,
+            Self::Mul | Self::Div | Self::Rem | Self::IntDiv => {
+                OperatorPrecedence::MultiplyOrDivide
+            }
+            Self::Caret => OperatorPrecedence::Power,
+        }
+    }
+
+    /// Returns whether a chain of this operator groups from the right, so
+    /// that `2 ^ 3 ^ 2` means `2 ^ (3 ^ 2)`.
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, Self::Caret)
+    }
+
+    /// Applies the operator to two already evaluated operands.
+    ///
+    /// All arithmetic is checked by the numeric backend, so overflow and
+    /// division by zero are reported as errors instead of panicking.
+    pub fn apply<N: Numeric>(&self, lhs: N, rhs: N) -> CalcResult<N> {
+        match self {
+            Self::Add => lhs.add(&rhs),
+            Self::Sub => lhs.sub(&rhs),
+            Self::Mul => lhs.mul(&rhs),
+            Self::Div => lhs.div(&rhs),
+            Self::Caret => lhs.pow(&rhs),
+            Self::Rem => lhs.rem_floor(&rhs),
+            Self::IntDiv => lhs.div_floor(&rhs),
+        }
+    }
+
+    /// Applies the operator to complex operands.
+    ///
+    /// # Returns
+    ///
+    /// The result, or `CalcError::TypeMismatch` for `%` and `//`, which are
+    /// only defined on real numbers
+    pub fn apply_complex<N: Numeric>(
+        &self,
+        lhs: Complex<N>,
+        rhs: Complex<N>,
+    ) -> CalcResult<Complex<N>> {
+        match self {
+            Self::Add => lhs.add(&rhs),
+            Self::Sub => lhs.sub(&rhs),
+            Self::Mul => lhs.mul(&rhs),
+            Self::Div => lhs.div(&rhs),
+            Self::Caret => lhs.pow(&rhs),
+            Self::Rem | Self::IntDiv => Err(CalcError::TypeMismatch {
+                expected: ValueType::Number,
+                actual: ValueType::Complex,
+            }),
+        }
+    }
+
+    /// Applies the operator to operands of which at least one is a matrix.
+    ///
+    /// `+` and `-` of two matrices work element by element and `*` is the
+    /// matrix product. A number combines with every element of a matrix,
+    /// except that `matrix ^ n` raises a square matrix to the integer power
+    /// `n`.
+    ///
+    /// # Returns
+    ///
+    /// The result, `CalcError::DimensionMismatch` if the shapes do not fit,
+    /// or `CalcError::TypeMismatch` for any other combination of operands
+    pub fn apply_matrix<N: Numeric>(&self, lhs: Value<N>, rhs: Value<N>) -> CalcResult<Matrix<N>> {
+        match (&lhs, &rhs) {
+            (Value::Matrix(l), Value::Matrix(r)) => match self {
+                Self::Add | Self::Sub => l.zip_with(r, |l, r| self.apply(l.clone(), r.clone())),
+                Self::Mul => l.mul(r),
+                _ => Err(CalcError::TypeMismatch {
+                    expected: ValueType::Number,
+                    actual: rhs.value_type(),
+                }),
+            },
+            (Value::Matrix(m), Value::Number(n)) if *self == Self::Caret => {
+                let exponent = n.to_i64().ok_or_else(|| CalcError::InvalidArgument {
+                    function: self.to_string(),
+                    value: n.to_string(),
+                })?;
+                m.pow(exponent)
+            }
+            (Value::Matrix(m), Value::Number(n)) => m.map(|e| self.apply(e.clone(), n.clone())),
+            (Value::Number(n), Value::Matrix(m)) if *self != Self::Caret => {
+                m.map(|e| self.apply(n.clone(), e.clone()))
+            }
+            // 复数不能与矩阵运算，数字也不能以矩阵为指数
+            _ => {
+                let other = match &lhs {
+                    Value::Number(_) | Value::Matrix(_) => &rhs,
+                    _ => &lhs,
+                };
+                Err(CalcError::TypeMismatch {
+                    expected: ValueType::Number,
+                    actual: other.value_type(),
+                })
+            }
+        }
+    }
+
+    /// Returns the sign the exact result of the operation would have, used
+    /// to saturate a result the backend cannot represent.
+    ///
+    /// A division by zero takes the sign of the dividend, so `0 / 0`
+    /// saturates to zero.
+    pub(crate) fn result_sign<N: Numeric>(&self, lhs: &N, rhs: &N) -> Ordering {
+        let sign = |n: &N| n.partial_cmp(&N::zero()).unwrap_or(Ordering::Equal);
+        let product = |l: Ordering, r: Ordering| match (l, r) {
+            (Ordering::Equal, _) | (_, Ordering::Equal) => Ordering::Equal,
+            (l, r) if l == r => Ordering::Greater,
+            _ => Ordering::Less,
+        };
+        match self {
+            Self::Add => sign(lhs).then(sign(rhs)),
+            Self::Sub => sign(lhs).then(sign(rhs).reverse()),
+            Self::Mul => product(sign(lhs), sign(rhs)),
+            Self::Div | Self::Rem | Self::IntDiv if rhs.is_zero() => sign(lhs),
+            Self::Div | Self::IntDiv => product(sign(lhs), sign(rhs)),
+            Self::Rem => sign(rhs),
+            Self::Caret => {
+                let odd = rhs.to_i64().is_some_and(|exponent| exponent % 2 != 0);
+                if sign(lhs) == Ordering::Less && odd {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            }
+        }
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Add => f.write_str("+"),
+            Self::Sub => f.write_str("-"),
+            Self::Mul => f.write_str("*"),
+            Self::Div => f.write_str("/"),
+            Self::Caret => f.write_str("^"),
+            Self::Rem => f.write_str("%"),
+            Self::IntDiv => f.write_str("//"),
+        }
+    }
+}
+
+/// A postfix operator appearing in an expression tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostfixOp {
+    // !
+    Factorial,
+    // %，除以 100
+    Percent,
+}
+
+impl PostfixOp {
+    /// Applies the operator to an already evaluated operand.
+    ///
+    /// # Returns
+    ///
+    /// The result, or `CalcError::InvalidArgument` for the factorial of a
+    /// negative or non-integer number
+    pub fn apply<N: Numeric>(&self, value: N) -> CalcResult<N> {
+        match self {
+            Self::Factorial => function::factorial("factorial", &value),
+            Self::Percent => value.div(&N::from_usize(100)),
+        }
+    }
+}
+
+impl Display for PostfixOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Factorial => f.write_str("!"),
+            Self::Percent => f.write_str("%"),
+        }
+    }
+}
+
+/// An expression tree produced by the parser.
+///
+/// Operators and function calls remember the span of their operator or
+/// function name so evaluation errors can point back into the source.
+/// Spans are ignored when comparing nodes, so trees parsed from differently
+/// formatted sources are equal when they have the same shape.
+#[derive(Debug)]
+pub enum Node {
+    // 数字
+    Number(Decimal),
+    // 超出 Decimal 精度的数字字面量，保留原文，求值时由数值后端转换
+    BigNumber(String),
+    // 虚数字面量，例如 4i
+    Imaginary(Decimal),
+    // 变量
+    Variable(String, Span),
+    // 单元格引用
+    Cell(CellRef, Span),
+    // 单元格区域
+    Range(CellRef, CellRef, Span),
+    // 字符串
+    Text(String),
+    // 取负
+    Negative {
+        expr: Box<Node>,
+        span: Span,
+    },
+    // 后缀运算，跨度为运算符
+    Postfix {
+        op: PostfixOp,
+        expr: Box<Node>,
+        span: Span,
+    },
+    // 二元运算
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+        span: Span,
+    },
+    // 函数调用，跨度为函数名
+    Function {
+        function_prefix: String,
+        args: Vec<Node>,
+        span: Span,
+    },
+    // 矩阵字面量，按行保存元素，跨度为左中括号
+    Matrix {
+        rows: Vec<Vec<Node>>,
+        span: Span,
+    },
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(l), Self::Number(r)) => l == r,
+            (Self::BigNumber(l), Self::BigNumber(r)) => l == r,
+            (Self::Imaginary(l), Self::Imaginary(r)) => l == r,
+            (Self::Variable(l, _), Self::Variable(r, _)) => l == r,
+            (Self::Cell(l, _), Self::Cell(r, _)) => l == r,
+            (Self::Range(l_from, l_to, _), Self::Range(r_from, r_to, _)) => {
+                l_from == r_from && l_to == r_to
+            }
+            (Self::Text(l), Self::Text(r)) => l == r,
+            (Self::Negative { expr: l, .. }, Self::Negative { expr: r, .. }) => l == r,
+            (
+                Self::Postfix {
+                    op: l_op, expr: l, ..
+                },
+                Self::Postfix {
+                    op: r_op, expr: r, ..
+                },
+            ) => l_op == r_op && l == r,
+            (
+                Self::Binary {
+                    op: l_op,
+                    lhs: l_lhs,
+                    rhs: l_rhs,
+                    ..
+                },
+                Self::Binary {
+                    op: r_op,
+                    lhs: r_lhs,
+                    rhs: r_rhs,
+                    ..
+                },
+            ) => l_op == r_op && l_lhs == r_lhs && l_rhs == r_rhs,
+            (
+                Self::Function {
+                    function_prefix: l_name,
+                    args: l_args,
+                    ..
+                },
+                Self::Function {
+                    function_prefix: r_name,
+                    args: r_args,
+                    ..
+                },
+            ) => l_name == r_name && l_args == r_args,
+            (Self::Matrix { rows: l, .. }, Self::Matrix { rows: r, .. }) => l == r,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Node {}
+
+impl Clone for Node {
+    /// Clones the tree, following the left operands of an operator chain
+    /// with a loop.
+    fn clone(&self) -> Self {
+        let mut chain = Vec::new();
+        let mut node = self;
+        while let Some(operand) = node.chain_operand() {
+            chain.push(node);
+            node = operand;
+        }
+        let mut clone = match node {
+            Self::Number(n) => Self::Number(*n),
+            Self::BigNumber(literal) => Self::BigNumber(literal.clone()),
+            Self::Imaginary(n) => Self::Imaginary(*n),
+            Self::Variable(name, span) => Self::Variable(name.clone(), *span),
+            Self::Cell(cell, span) => Self::Cell(*cell, *span),
+            Self::Range(from, to, span) => Self::Range(*from, *to, *span),
+            Self::Text(text) => Self::Text(text.clone()),
+            Self::Negative { expr, span } => Self::Negative {
+                expr: expr.clone(),
+                span: *span,
+            },
+            Self::Function {
+                function_prefix,
+                args,
+                span,
+            } => Self::Function {
+                function_prefix: function_prefix.clone(),
+                args: args.clone(),
+                span: *span,
+            },
+            Self::Matrix { rows, span } => Self::Matrix {
+                rows: rows.clone(),
+                span: *span,
+            },
+            Self::Postfix { .. } | Self::Binary { .. } => unreachable!("chains are unwound above"),
+        };
+        // 由内向外重建运算符链
+        for link in chain.into_iter().rev() {
+            clone = match link {
+                Self::Postfix { op, span, .. } => Self::Postfix {
+                    op: *op,
+                    expr: Box::new(clone),
+                    span: *span,
+                },
+                Self::Binary { op, rhs, span, .. } => Self::Binary {
+                    op: *op,
+                    lhs: Box::new(clone),
+                    rhs: rhs.clone(),
+                    span: *span,
+                },
+                _ => unreachable!("only operators are collected"),
+            };
+        }
+        clone
+    }
+}
+
+impl Node {
+    /// Evaluates the expression tree.
+    ///
+    /// The numeric backend is chosen by the type of the variable values:
+    /// `Decimal` by default, or `f64` and `Rational` from
+    /// [`numeric`](crate::calc::numeric). Literals are converted into the
+    /// backend before any arithmetic happens.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - Values for the variables referenced by the expression
+    ///
+    /// # Returns
+    ///
+    /// The value of the expression, or an error if a variable is missing,
+    /// a function is unknown, the expression references cells, or the
+    /// arithmetic fails
+    pub fn eval<N: Numeric>(&self, variables: &HashMap<String, N>) -> CalcResult<N> {
+        self.eval_with_cells(variables, &NoCells)
+    }
+
+    /// Evaluates the expression tree, resolving cell references and ranges
+    /// through `cells`. The parser only reads references such as `A1` when
+    /// `ParseOptions::cell_references` is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - Values for the variables referenced by the expression
+    /// * `cells` - The provider of cell values
+    ///
+    /// # Returns
+    ///
+    /// The value of the expression; a bare range is a type error
+    pub fn eval_with_cells<N: Numeric>(
+        &self,
+        variables: &HashMap<String, N>,
+        cells: &dyn CellProvider,
+    ) -> CalcResult<N> {
+        self.eval_with_context(variables, cells, &Context::default())
+    }
+
+    /// Evaluates the expression tree with an evaluation context controlling
+    /// the scale and rounding of results.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - Values for the variables referenced by the expression
+    /// * `cells` - The provider of cell values
+    /// * `context` - The evaluation settings
+    ///
+    /// # Returns
+    ///
+    /// The value of the expression, rounded as the context requires
+    pub fn eval_with_context<N: Numeric>(
+        &self,
+        variables: &HashMap<String, N>,
+        cells: &dyn CellProvider,
+        context: &Context,
+    ) -> CalcResult<N> {
+        Evaluator::new(variables, cells, context).eval_number(self)
+    }
+
+    /// Evaluates the expression tree to a value instead of a number.
+    ///
+    /// With the `Null` and `ErrorValue` arithmetic policies a failed
+    /// operation produces `Value::Null` or `Value::Error` rather than an
+    /// error; this method returns those values as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - Values for the variables referenced by the expression
+    /// * `cells` - The provider of cell values
+    /// * `context` - The evaluation settings
+    ///
+    /// # Returns
+    ///
+    /// The value of the expression, rounded as the context requires
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::cell::CellRef;
+    /// use rust_calculate::calc::context::{ArithmeticPolicy, Context};
+    /// use rust_calculate::calc::parser::Parser;
+    /// use rust_calculate::calc::value::{ErrorKind, Value};
+    /// use rust_decimal::Decimal;
+    /// use std::collections::HashMap;
+    ///
+    /// let context = Context {
+    ///     divide_by_zero_policy: ArithmeticPolicy::ErrorValue,
+    ///     ..Context::default()
+    /// };
+    /// let node = Parser::new("1 + 1 / 0").unwrap().parse().unwrap();
+    /// let cells: HashMap<CellRef, Decimal> = HashMap::new();
+    /// let value = node.eval_value(&HashMap::<String, Decimal>::new(), &cells, &context);
+    /// assert!(matches!(value, Ok(Value::Error(ErrorKind::Div0, span)) if span.start == 6));
+    /// ```
+    pub fn eval_value<N: Numeric>(
+        &self,
+        variables: &HashMap<String, N>,
+        cells: &dyn CellProvider,
+        context: &Context,
+    ) -> CalcResult<Value<N>> {
+        Evaluator::new(variables, cells, context).eval_value(self)
+    }
+
+    /// Evaluates the expression once for every row of variables.
+    ///
+    /// Each row is evaluated on its own, so one failing row does not stop the
+    /// others. With `Context::error_values` set, failing rows produce error
+    /// values such as `#N/A` pointing at the part of the expression that
+    /// failed, just like a spreadsheet column.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - The variables of each row
+    /// * `cells` - The provider of cell values
+    /// * `context` - The evaluation settings
+    ///
+    /// # Returns
+    ///
+    /// The value or error of each row, in the order of `rows`
+    pub fn eval_batch<N: Numeric>(
+        &self,
+        rows: &[HashMap<String, N>],
+        cells: &dyn CellProvider,
+        context: &Context,
+    ) -> Vec<CalcResult<Value<N>>> {
+        rows.iter()
+            .map(|variables| self.eval_value(variables, cells, context))
+            .collect()
+    }
+
+    /// Returns the span of the reference, operator or function name the node
+    /// was parsed from; literals have none.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Number(_) | Self::BigNumber(_) | Self::Imaginary(_) | Self::Text(_) => None,
+            Self::Variable(_, span)
+            | Self::Cell(_, span)
+            | Self::Range(_, _, span)
+            | Self::Negative { span, .. }
+            | Self::Postfix { span, .. }
+            | Self::Binary { span, .. }
+            | Self::Function { span, .. }
+            | Self::Matrix { span, .. } => Some(*span),
+        }
+    }
+
+    /// Returns the direct sub-expressions of the node, in source order.
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Self::Number(_)
+            | Self::BigNumber(_)
+            | Self::Imaginary(_)
+            | Self::Text(_)
+            | Self::Variable(..)
+            | Self::Cell(..)
+            | Self::Range(..) => Vec::new(),
+            Self::Negative { expr, .. } | Self::Postfix { expr, .. } => vec![expr],
+            Self::Binary { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::Function { args, .. } => args.iter().collect(),
+            Self::Matrix { rows, .. } => rows.iter().flatten().collect(),
+        }
+    }
+
+    /// Returns the left operand of a binary or postfix operator.
+    ///
+    /// A chain such as `1 + 2 + ... + n` is parsed with a loop and grows
+    /// along these operands, so walks over the tree follow them with a loop
+    /// as well instead of recursing once per operator.
+    pub(crate) fn chain_operand(&self) -> Option<&Node> {
+        match self {
+            Self::Binary { lhs: operand, .. } | Self::Postfix { expr: operand, .. } => {
+                Some(operand)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the precedence of the node when it is printed as a sub-expression.
+    ///
+    /// Atoms (numbers, variables, function calls and matrices) bind tighter
+    /// than any operator, while negative numbers print with a leading `-` and
+    /// therefore behave like `Negative`.
+    pub fn get_precedence(&self) -> OperatorPrecedence {
+        match self {
+            Self::Number(n) | Self::Imaginary(n) if n.is_sign_negative() => {
+                OperatorPrecedence::Negative
+            }
+            Self::Negative { .. } => OperatorPrecedence::Negative,
+            Self::Postfix { .. } => OperatorPrecedence::Postfix,
+            Self::Binary { op, .. } => op.get_precedence(),
+            _ => OperatorPrecedence::Function,
+        }
+    }
+
+    /// Prints the expression with some sub-expressions replaced by text,
+    /// such as the values they evaluated to.
+    ///
+    /// # Arguments
+    ///
+    /// * `substitute` - Returns the replacement of a node, or `None` to print
+    ///   the node as usual
+    pub(crate) fn render(&self, substitute: &dyn Fn(&Node) -> Option<String>) -> String {
+        Substituted {
+            node: self,
+            substitute,
+            locale: Locale::EnUs,
+            implicit: false,
+        }
+        .to_string()
+    }
+
+    /// Pretty-prints the expression with the function names of a locale,
+    /// e.g. `求和<a, b>` instead of `sum<a, b>` in Chinese.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::locale::Locale;
+    /// use rust_calculate::calc::parser::Parser;
+    ///
+    /// let node = Parser::new("绝对值<x> + sum<1, 2>").unwrap().parse().unwrap();
+    /// assert_eq!(node.to_string(), "abs<x> + sum<1, 2>");
+    /// assert_eq!(node.to_localized_string(Locale::ZhCn), "绝对值<x> + 求和<1, 2>");
+    /// ```
+    pub fn to_localized_string(&self, locale: Locale) -> String {
+        Substituted {
+            node: self,
+            substitute: &|_| None,
+            locale,
+            implicit: false,
+        }
+        .to_string()
+    }
+
+    /// Pretty-prints the expression with multiplications written
+    /// implicitly where the parser's implicit multiplication reads them
+    /// back the same, e.g. `2x` or `3(a + b)`.
+    ///
+    /// A product stays explicit when its right operand starts with a
+    /// number, a sign, a cell or a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::parser::{ParseOptions, Parser};
+    ///
+    /// let options = ParseOptions {
+    ///     implicit_multiplication: true,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let node = Parser::with_options("2x(a + b)", &options).unwrap().parse().unwrap();
+    /// assert_eq!(node.to_string(), "2 * x * (a + b)");
+    /// assert_eq!(node.to_implicit_string(), "2x(a + b)");
+    /// ```
+    pub fn to_implicit_string(&self) -> String {
+        Substituted {
+            node: self,
+            substitute: &|_| None,
+            locale: Locale::EnUs,
+            implicit: true,
+        }
+        .to_string()
+    }
+}
+
+impl Display for Node {
+    /// Pretty-prints the expression in a form the parser accepts again.
+    ///
+    /// Parentheses are only emitted where precedence or associativity
+    /// requires them, so `(1 + 2) * x` prints as `(1 + 2) * x` while
+    /// `1 + (2 * x)` prints as `1 + 2 * x`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Substituted {
+            node: self,
+            substitute: &|_| None,
+            locale: Locale::EnUs,
+            implicit: false,
+        }
+        .fmt(f)
+    }
+}
+
+/// A node being printed with some of its sub-expressions replaced and its
+/// function names in a given locale.
+struct Substituted<'a> {
+    node: &'a Node,
+    substitute: &'a dyn Fn(&Node) -> Option<String>,
+    locale: Locale,
+    // 是否将乘法写成隐式形式
+    implicit: bool,
+}
+
+impl Substituted<'_> {
+    fn child<'b>(&'b self, node: &'b Node) -> Substituted<'b> {
+        Substituted {
+            node,
+            substitute: self.substitute,
+            locale: self.locale,
+            implicit: self.implicit,
+        }
+    }
+
+    /// 替换后的负数与负号的优先级相同，其余替换文本视为原子
+    fn get_precedence(&self) -> OperatorPrecedence {
+        match (self.substitute)(self.node) {
+            Some(text) if text.starts_with('-') => OperatorPrecedence::Negative,
+            Some(_) => OperatorPrecedence::Function,
+            None => self.node.get_precedence(),
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut Formatter<'_>, needs_paren: bool) -> std::fmt::Result {
+        if needs_paren {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+
+    /// Writes a product without `*`, or returns `None` if the parser would
+    /// not read the juxtaposed operands back as the same product.
+    ///
+    /// `lhs_text` is the left operand already printed, without brackets.
+    fn implicit_product(&self, lhs: &Self, lhs_text: &str, rhs: &Self) -> Option<String> {
+        let precedence = BinaryOp::Mul.get_precedence();
+        let lhs_text = parenthesize(lhs_text.to_string(), lhs.get_precedence() < precedence);
+        let rhs_text = parenthesize(rhs.to_string(), rhs.get_precedence() <= precedence);
+        let bracket = rhs_text.starts_with('(');
+        if !bracket
+            && !matches!(
+                leftmost(rhs.node),
+                Node::Variable(..) | Node::Function { .. }
+            )
+        {
+            return None;
+        }
+        // 数字与后面的名称或括号之间无需空格，例如 2x、3(a + b)
+        let tight = bracket
+            || match rightmost(lhs.node) {
+                Node::Number(n) => lhs_text.ends_with(&n.to_string()),
+                Node::BigNumber(literal) => lhs_text.ends_with(literal.as_str()),
+                _ => false,
+            };
+        let separator = if tight { "" } else { " " };
+        Some(format!("{lhs_text}{separator}{rhs_text}"))
+    }
+
+    /// Prints a chain of binary and postfix operators such as
+    /// `1 + 2 + ... + n`, following its left operands with a loop.
+    fn chain_to_string(&self) -> String {
+        let mut chain = Vec::new();
+        let mut node = self.node;
+        // 被替换为文本的节点整体作为操作数
+        while let Some(operand) = node.chain_operand()
+            && (chain.is_empty() || (self.substitute)(node).is_none())
+        {
+            chain.push(node);
+            node = operand;
+        }
+        let mut text = self.child(node).to_string();
+        for link in chain.into_iter().rev() {
+            let lhs = self.child(node);
+            text = match link {
+                Node::Postfix { op, .. } => {
+                    let needs_paren = lhs.get_precedence() < OperatorPrecedence::Postfix;
+                    format!("{}{}", parenthesize(text, needs_paren), op)
+                }
+                Node::Binary { op, rhs, .. } => {
+                    let rhs = self.child(rhs);
+                    if self.implicit
+                        && *op == BinaryOp::Mul
+                        && let Some(product) = self.implicit_product(&lhs, &text, &rhs)
+                    {
+                        product
+                    } else {
+                        let precedence = op.get_precedence();
+                        // 同级运算只有在结合方向的另一侧才需要括号
+                        let right = op.is_right_associative();
+                        let (lhs_precedence, rhs_precedence) =
+                            (lhs.get_precedence(), rhs.get_precedence());
+                        let lhs_paren =
+                            lhs_precedence < precedence || (right && lhs_precedence == precedence);
+                        let rhs_paren =
+                            rhs_precedence < precedence || (!right && rhs_precedence == precedence);
+                        format!(
+                            "{} {} {}",
+                            parenthesize(text, lhs_paren),
+                            op,
+                            parenthesize(rhs.to_string(), rhs_paren)
+                        )
+                    }
+                }
+                _ => unreachable!("only operators are collected"),
+            };
+            node = link;
+        }
+        text
+    }
+}
+
+/// Wraps printed text in brackets if it needs them.
+fn parenthesize(text: String, needs_paren: bool) -> String {
+    if needs_paren {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+/// Returns the operand printed first in a node without brackets.
+fn leftmost(mut node: &Node) -> &Node {
+    while let Node::Binary { lhs: expr, .. } | Node::Postfix { expr, .. } = node {
+        node = expr;
+    }
+    node
+}
+
+/// Returns the operand printed last in a node without brackets.
+fn rightmost(mut node: &Node) -> &Node {
+    loop {
+        match node {
+            Node::Binary { rhs, .. } => node = rhs,
+            Node::Negative { expr, .. } => node = expr,
+            _ => return node,
+        }
+    }
+}
+
+impl Display for Substituted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(text) = (self.substitute)(self.node) {
+            return f.write_str(&text);
+        }
+        match self.node {
+            Node::Number(n) => write!(f, "{}", n),
+            Node::BigNumber(literal) => f.write_str(literal),
+            Node::Imaginary(n) => write!(f, "{}i", n),
+            Node::Variable(name, _) => f.write_str(name),
+            Node::Cell(cell, _) => write!(f, "{}", cell),
+            Node::Range(from, to, _) => write!(f, "{}:{}", from, to),
+            Node::Text(text) => write!(f, "\"{}\"", text),
+            Node::Negative { expr, .. } => {
+                let expr = self.child(expr);
+                f.write_str("-")?;
+                expr.fmt_operand(f, expr.get_precedence() < OperatorPrecedence::Negative)
+            }
+            Node::Postfix { .. } | Node::Binary { .. } => f.write_str(&self.chain_to_string()),
+            Node::Function {
+                function_prefix,
+                args,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{}<",
+                    function::localized_name(function_prefix, self.locale)
+                )?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", self.child(arg))?;
+                }
+                f.write_str(">")
+            }
+            Node::Matrix { rows, .. } => {
+                f.write_str("[")?;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    for (j, value) in row.iter().enumerate() {
+                        if j > 0 {
+                            f.write_str(", ")?;
+                        }
+                        write!(f, "{}", self.child(value))?;
+                    }
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::context::{ArithmeticPolicy, RoundingMode, RoundingScope};
+    use crate::calc::error::CalcError;
+    use crate::calc::limits::Limits;
+    use crate::calc::numeric::Rational;
+    use crate::calc::parser::{ParseOptions, Parser};
+    use crate::calc::value::ErrorKind;
+    use rust_decimal::dec;
+    use std::time::Duration;
+
+    fn parse(expression: &str) -> Node {
+        let options = ParseOptions {
+            cell_references: true,
+            ..ParseOptions::default()
+        };
+        Parser::with_options(expression, &options)
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    /// Tests evaluation of arithmetic with the usual operator precedence.
+    #[test]
+    fn test_eval_precedence() {
+        let variables = HashMap::new();
+        assert_eq!(parse("1 + 2 * 3").eval(&variables), Ok(dec!(7)));
+        assert_eq!(parse("(1 + 2) * 3").eval(&variables), Ok(dec!(9)));
+        assert_eq!(parse("2 ^ 3 - -1").eval(&variables), Ok(dec!(9)));
+        assert_eq!(parse("7 / 2").eval(&variables), Ok(dec!(3.5)));
+        assert_eq!(parse("1 + 7 // 2 * 3").eval(&variables), Ok(dec!(10)));
+        assert_eq!(parse("-7 % 3 + 10 % 4 ^ 2").eval(&variables), Ok(dec!(12)));
+        assert_eq!(parse("2 ^ 3! - -3!").eval(&variables), Ok(dec!(70)));
+    }
+
+    /// Tests that `^` groups from the right and reports overflow, division
+    /// by zero and complex results as errors.
+    #[test]
+    fn test_eval_power() {
+        let variables = HashMap::<String, Decimal>::new();
+        assert_eq!(parse("2 ^ 3 ^ 2").eval(&variables), Ok(dec!(512)));
+        assert_eq!(parse("(2 ^ 3) ^ 2").eval(&variables), Ok(dec!(64)));
+        assert_eq!(parse("2 ^ -2 ^ 2").eval(&variables), Ok(dec!(16)));
+        assert_eq!(parse("4 ^ 0.5 * 3").eval(&variables), Ok(dec!(6)));
+        assert_eq!(
+            parse("(-8) ^ 0.5").eval(&variables),
+            Err(CalcError::ComplexResult("-8 ^ 0.5".to_string()).at(Span::new(5, 6)))
+        );
+        assert_eq!(
+            parse("0 ^ -1").eval(&variables),
+            Err(CalcError::DivideByZero.at(Span::new(2, 3)))
+        );
+        assert_eq!(
+            parse("10 ^ 10 ^ 2").eval(&variables),
+            Err(CalcError::Overflow.at(Span::new(3, 4)))
+        );
+    }
+
+    /// Tests the errors of factorial and of a remainder by zero.
+    #[test]
+    fn test_eval_postfix_errors() {
+        let variables = HashMap::<String, Decimal>::new();
+        for (expression, value, span) in [
+            ("(-1)!", "-1", Span::new(4, 5)),
+            ("2.5!", "2.5", Span::new(3, 4)),
+        ] {
+            assert_eq!(
+                parse(expression).eval(&variables),
+                Err(CalcError::InvalidArgument {
+                    function: "factorial".to_string(),
+                    value: value.to_string()
+                }
+                .at(span))
+            );
+        }
+        assert_eq!(
+            parse("30!").eval(&variables),
+            Err(CalcError::Overflow.at(Span::new(2, 3)))
+        );
+        assert_eq!(
+            parse("5 % (2 - 2)").eval(&variables),
+            Err(CalcError::DivideByZero.at(Span::new(2, 3)))
+        );
+        assert_eq!(
+            parse("5 // 0").eval(&variables),
+            Err(CalcError::DivideByZero.at(Span::new(2, 4)))
+        );
+    }
+
+    /// Tests complex literals, arithmetic and functions, and promotion of
+    /// real operations to complex results in complex mode only.
+    #[test]
+    fn test_eval_complex() {
+        let options = ParseOptions {
+            complex_numbers: true,
+            ..ParseOptions::default()
+        };
+        let eval = |expression: &str, complex_numbers| {
+            let context = Context {
+                complex_numbers,
+                ..Context::default()
+            };
+            Parser::with_options(expression, &options)
+                .unwrap()
+                .parse()
+                .unwrap()
+                .eval_value(&HashMap::<String, Decimal>::new(), &NoCells, &context)
+        };
+        let c = |re, im| Ok(Value::Complex(Complex::new(re, im)));
+        let n = |n| Ok(Value::Number(n));
+        assert_eq!(eval("(3 + 4i) * (1 - 2i)", false), c(dec!(11), dec!(-2)));
+        assert_eq!(eval("(3 + 4i) / (1 - 2i) - 1", false), c(dec!(-2), dec!(2)));
+        assert_eq!(eval("-(2 - 3i)", false), c(dec!(-2), dec!(3)));
+        assert_eq!(eval("2i * 2i", false), n(dec!(-4)));
+        assert_eq!(eval("(1 + 1i) ^ 2", false), c(dec!(0), dec!(2)));
+        assert_eq!(eval("abs<3 + 4i>", false), n(dec!(5)));
+        assert_eq!(eval("re<3 - 4i> + im<3 - 4i>", false), n(dec!(-1)));
+        assert_eq!(eval("conj<1 + 2i>", false), c(dec!(1), dec!(-2)));
+        assert_eq!(
+            eval("sqrt<-4>", false),
+            Err(CalcError::ComplexResult("sqrt<-4>".to_string()).at(Span::new(0, 4)))
+        );
+        assert_eq!(eval("sqrt<-4>", true), c(dec!(0), dec!(2)));
+        assert_eq!(eval("(-4) ^ 0.5 + 1", true), c(dec!(1), dec!(2)));
+        assert_eq!(eval("sqrt<-4> ^ 2", true), n(dec!(-4)));
+        let mismatch = Err(CalcError::TypeMismatch {
+            expected: ValueType::Number,
+            actual: ValueType::Complex,
+        });
+        assert_eq!(eval("(1 + 2i) % 2", false), mismatch);
+        assert_eq!(eval("(2i)!", false), mismatch);
+        assert_eq!(eval("sum<1, 2i>", false), mismatch);
+    }
+
+    /// Tests matrix arithmetic and functions against the literals of their
+    /// results, and dimension errors pointing at the operator or function
+    /// that failed.
+    #[test]
+    fn test_eval_matrix() {
+        let eval = |expression: &str| {
+            parse(expression).eval_value(
+                &HashMap::from([("x".to_string(), dec!(2))]),
+                &NoCells,
+                &Context::default(),
+            )
+        };
+        assert_eq!(eval("[1, 2; 3, 4] + [x, 0; 0, x]"), eval("[3, 2; 3, 6]"));
+        assert_eq!(eval("[1, 2; 3, 4] * [1; 1]"), eval("[3; 7]"));
+        assert_eq!(eval("-[1, 2] * 3 - 1"), eval("[-4, -7]"));
+        assert_eq!(eval("12 / [1, 2, 3]"), eval("[12, 6, 4]"));
+        assert_eq!(eval("[1, 1; 1, 0] ^ 10"), eval("[89, 55; 55, 34]"));
+        assert_eq!(eval("[4, 0; 0, 8] ^ -1"), eval("[0.25, 0; 0, 0.125]"));
+        assert_eq!(eval("transpose<[1, 2, 3]>"), eval("[1; 2; 3]"));
+        assert_eq!(eval("solve<[2, 1; 1, 3], [3; 5]>"), eval("[0.8; 1.4]"));
+        assert_eq!(
+            eval("det<[1, 2; 3, 4]> + dot<[1, 2], [3; 4]>"),
+            Ok(Value::Number(dec!(9)))
+        );
+        assert_eq!(
+            eval("sum<[1, 2; 3, 4]> / count<[1, 2; 3, 4]>"),
+            Ok(Value::Number(dec!(2.5)))
+        );
+
+        let error = eval("[1, 2] + [1, 2, 3]").unwrap_err();
+        assert_eq!(error.span(), Some(Span::new(7, 8)));
+        assert_eq!(
+            error.without_span(),
+            &CalcError::DimensionMismatch {
+                expected: "1×2".to_string(),
+                actual: "1×3".to_string()
+            }
+        );
+        let error = eval("1 + inv<[1, 2; 2, 4]>").unwrap_err();
+        assert_eq!(
+            (error.without_span(), error.span()),
+            (&CalcError::SingularMatrix, Some(Span::new(4, 7)))
+        );
+        let mismatch = Err(CalcError::TypeMismatch {
+            expected: ValueType::Number,
+            actual: ValueType::Matrix,
+        });
+        assert_eq!(eval("[1, 2] / [1, 2]"), mismatch);
+        assert_eq!(eval("2 ^ [1, 2]"), mismatch);
+        assert_eq!(eval("[[1, 2], 3]"), mismatch);
+        assert_eq!(
+            parse("[1, 2] * 2").eval(&HashMap::new()),
+            mismatch.map(|_| dec!(0))
+        );
+    }
+
+    /// Tests evaluation of variables, `nvl` fallbacks and nested functions.
+    #[test]
+    fn test_eval_variables_and_functions() {
+        let variables = HashMap::from([("x".to_string(), dec!(4))]);
+        assert_eq!(parse("x * 2.5").eval(&variables), Ok(dec!(10.0)));
+        assert_eq!(parse("nvl<y, 3> + x").eval(&variables), Ok(dec!(7)));
+        let prices = HashMap::from([
+            ("单价".to_string(), dec!(2.5)),
+            ("数量".to_string(), dec!(4)),
+        ]);
+        assert_eq!(parse("单价 * 数量").eval(&prices), Ok(dec!(10.0)));
+        assert_eq!(parse("求和<x, 绝对值<-1>>").eval(&variables), Ok(dec!(5)));
+        assert_eq!(
+            parse("1 + 2 * nvl < abs < 1 + 2 * 3 + [ ( 1+ 3 ) / 2 ] > , 0 >").eval(&variables),
+            Ok(dec!(19))
+        );
+        assert_eq!(
+            parse("y + 1").eval(&variables),
+            Err(CalcError::UndefinedVariable("y".to_string()))
+        );
+        assert_eq!(
+            parse("1 / 0").eval(&variables),
+            Err(CalcError::DivideByZero.at(Span::new(2, 3)))
+        );
+    }
+
+    /// Tests that the step budget and timeout stop an evaluation, even inside
+    /// `iferror` or with error values enabled.
+    #[test]
+    fn test_eval_limits() {
+        let variables = HashMap::new();
+        let eval = |expression, limits| {
+            let context = Context {
+                error_values: true,
+                limits,
+                ..Context::default()
+            };
+            parse(expression).eval_with_context(&variables, &NoCells, &context)
+        };
+        let steps = |max_steps| Limits {
+            max_steps: Some(max_steps),
+            ..Limits::default()
+        };
+        assert_eq!(eval("1 + 2 * 3", steps(5)), Ok(dec!(7)));
+        assert_eq!(
+            eval("1 + 2 * 3", steps(4)),
+            Err(CalcError::StepLimitExceeded(4))
+        );
+        assert_eq!(
+            eval("iferror<1 + 2 * 3, 0>", steps(4)),
+            Err(CalcError::StepLimitExceeded(4))
+        );
+        let timeout = Limits {
+            timeout: Some(Duration::ZERO),
+            ..Limits::default()
+        };
+        assert_eq!(
+            eval("iferror<1, 0>", timeout),
+            Err(CalcError::Timeout(Duration::ZERO))
+        );
+
+        // 区域的每个单元格都计入步数，展开过程中同样检查超时
+        let cells: HashMap<CellRef, Decimal> = HashMap::new();
+        let eval_cells = |expression, limits| {
+            let context = Context {
+                limits,
+                ..Context::default()
+            };
+            parse(expression).eval_with_context(&variables, &cells, &context)
+        };
+        assert_eq!(eval_cells("sum<A1:B2>", steps(6)), Ok(dec!(0)));
+        assert_eq!(
+            eval_cells("sum<A1:B2>", steps(5)),
+            Err(CalcError::StepLimitExceeded(5))
+        );
+        assert_eq!(
+            eval_cells("sum<A1:XFD100000>", steps(1000)),
+            Err(CalcError::StepLimitExceeded(1000))
+        );
+        let timeout = Duration::from_millis(50);
+        let started = std::time::Instant::now();
+        assert_eq!(
+            eval_cells(
+                "sum<A1:XFD100000>",
+                Limits {
+                    timeout: Some(timeout),
+                    ..Limits::default()
+                }
+            ),
+            Err(CalcError::Timeout(timeout))
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    /// Tests the overflow, underflow and division-by-zero policies.
+    #[test]
+    fn test_eval_arithmetic_policies() {
+        let variables = HashMap::from([("big".to_string(), Decimal::MAX)]);
+        let eval = |expression, policy| {
+            let context = Context {
+                overflow_policy: policy,
+                underflow_policy: policy,
+                divide_by_zero_policy: policy,
+                ..Context::default()
+            };
+            parse(expression).eval_value(&variables, &NoCells, &context)
+        };
+        let n = |n| Ok(Value::Number(n));
+        assert_eq!(
+            eval("1 + big * 2", ArithmeticPolicy::Error),
+            Err(CalcError::Overflow.at(Span::new(8, 9)))
+        );
+        assert_eq!(
+            eval(
+                "0.0000000000001 * 0.00000000000001 * 0.01",
+                ArithmeticPolicy::Error
+            ),
+            Err(CalcError::Underflow.at(Span::new(35, 36)))
+        );
+        assert_eq!(
+            eval("-big * 2", ArithmeticPolicy::Saturate),
+            n(-Decimal::MAX)
+        );
+        assert_eq!(
+            eval("-1 / 0 + 1", ArithmeticPolicy::Saturate),
+            n(-Decimal::MAX + dec!(1))
+        );
+        assert_eq!(eval("0 / 0", ArithmeticPolicy::Saturate), n(dec!(0)));
+        for (expression, span) in [("10 ^ -30", Span::new(3, 4)), ("2 ^ -100", Span::new(2, 3))] {
+            assert_eq!(
+                eval(expression, ArithmeticPolicy::Error),
+                Err(CalcError::Underflow.at(span))
+            );
+            assert_eq!(eval(expression, ArithmeticPolicy::Saturate), n(dec!(0)));
+        }
+        assert_eq!(eval("2 * (1 / 0)", ArithmeticPolicy::Null), Ok(Value::Null));
+        assert_eq!(eval("nvl<1 / 0, 5>", ArithmeticPolicy::Null), n(dec!(5)));
+        assert_eq!(
+            eval("abs<1 / 0> - 1", ArithmeticPolicy::ErrorValue),
+            Ok(Value::Error(ErrorKind::Div0, Span::new(6, 7)))
+        );
+        assert_eq!(
+            eval("iferror<big + big, 0> + 1", ArithmeticPolicy::ErrorValue),
+            n(dec!(1))
+        );
+        // iferror 也能捕获默认策略下的求值错误
+        assert_eq!(
+            eval("iferror<1 / 0, 2>", ArithmeticPolicy::Error),
+            n(dec!(2))
+        );
+        assert_eq!(
+            eval("iferror<foo<1>, 2>", ArithmeticPolicy::Error),
+            Err(CalcError::UnknownFunction("foo".to_string()))
+        );
+        assert_eq!(
+            parse("1 / 0 + 1").eval_with_context(
+                &variables,
+                &NoCells,
+                &Context {
+                    divide_by_zero_policy: ArithmeticPolicy::ErrorValue,
+                    ..Context::default()
+                }
+            ),
+            Err(CalcError::ErrorValue {
+                kind: ErrorKind::Div0,
+                span: Span::new(2, 3)
+            })
+        );
+    }
+
+    /// Tests that the numeric backend follows the type of the variables.
+    #[test]
+    fn test_eval_backends() {
+        fn x<N>(value: N) -> HashMap<String, N> {
+            HashMap::from([("x".to_string(), value)])
+        }
+        let node = parse("1 / 3 * x");
+        assert_eq!(
+            node.eval(&x(Rational::from_usize(3))),
+            Ok(Rational::from_usize(1))
+        );
+        assert_eq!(node.eval(&x(3.0)), Ok(1.0));
+        assert_ne!(node.eval(&x(dec!(3))), Ok(dec!(1)));
+        assert_eq!(
+            parse("sqrt<x> + 1").eval(&x(Rational::new(4, 9).unwrap())),
+            Ok(Rational::new(5, 3).unwrap())
+        );
+    }
+
+    /// Tests rounding through the evaluation context and `round`.
+    #[test]
+    fn test_eval_rounding() {
+        let variables = HashMap::from([("x".to_string(), dec!(2.345))]);
+        let eval = |expression, context: &Context| {
+            parse(expression).eval_with_context(&variables, &NoCells, context)
+        };
+        let context = Context::default();
+        assert_eq!(eval("round<x, 2>", &context), Ok(dec!(2.35)));
+        assert_eq!(
+            eval(r#"round<x, 2, "HALF_EVEN">"#, &context),
+            Ok(dec!(2.34))
+        );
+        assert_eq!(eval("round<x>", &context), Ok(dec!(2)));
+        assert_eq!(
+            eval(r#"round<x, 2, "NEAREST">"#, &context),
+            Err(CalcError::InvalidArgument {
+                function: "round".to_string(),
+                value: "NEAREST".to_string()
+            }
+            .at(Span::new(0, 5)))
+        );
+
+        let final_result = Context {
+            scale: Some(1),
+            rounding_mode: RoundingMode::Floor,
+            rounding_scope: RoundingScope::FinalResult,
+            ..Context::default()
+        };
+        assert_eq!(eval("x * 3 + x * 3", &final_result), Ok(dec!(14.0)));
+        assert_eq!(eval("round<x, 2, \"UP\">", &final_result), Ok(dec!(2.3)));
+        let each_operation = Context {
+            rounding_scope: RoundingScope::EachOperation,
+            ..final_result.clone()
+        };
+        assert_eq!(eval("x * 3 + x * 3", &each_operation), Ok(dec!(14.0)));
+        assert_eq!(eval("x / 3 * 3", &final_result), Ok(dec!(2.3)));
+        assert_eq!(eval("x / 3 * 3", &each_operation), Ok(dec!(2.1)));
+    }
+
+    /// Tests error values, their propagation and the functions that inspect them.
+    #[test]
+    fn test_eval_error_values() {
+        let cells = HashMap::from([(CellRef::new(1, 1), dec!(4))]);
+        let variables = HashMap::from([("x".to_string(), dec!(-1))]);
+        let context = Context {
+            error_values: true,
+            ..Context::default()
+        };
+        let eval = |expression| parse(expression).eval_value(&variables, &cells, &context);
+        let n = |n| Ok(Value::Number(n));
+        assert_eq!(
+            eval("1 + y * 2"),
+            Ok(Value::Error(ErrorKind::Na, Span::new(4, 5)))
+        );
+        assert_eq!(
+            parse("1 + B2").eval_value(&variables, &NoCells, &context),
+            Ok(Value::Error(ErrorKind::Ref, Span::new(4, 6)))
+        );
+        assert_eq!(
+            eval("A1:A2 * 2"),
+            Ok(Value::Error(ErrorKind::Value, Span::new(6, 7)))
+        );
+        assert_eq!(
+            eval("abs<factorial<x>>"),
+            Ok(Value::Error(ErrorKind::Num, Span::new(4, 13)))
+        );
+        assert_eq!(eval("iferror<sqrt<x>, 0> + nvl<y, 1>"), n(dec!(1)));
+        assert_eq!(eval("iserror<y> + iserror<A1 + A2>"), n(dec!(1)));
+        assert_eq!(
+            eval("errortype<A1:A2 * 2> * 10 + errortype<1 / 0>"),
+            n(dec!(32))
+        );
+        assert_eq!(
+            eval("errortype<1>"),
+            Ok(Value::Error(ErrorKind::Na, Span::new(0, 9)))
+        );
+        // 除零仍遵循算术策略
+        assert_eq!(
+            eval("1 / 0"),
+            Err(CalcError::DivideByZero.at(Span::new(2, 3)))
+        );
+        assert_eq!(
+            eval("foo<1>"),
+            Err(CalcError::UnknownFunction("foo".to_string()))
+        );
+
+        // 没有开启错误值时，iserror 也能检查求值错误
+        assert_eq!(
+            parse("iserror<y>").eval_with_cells(&variables, &cells),
+            Ok(dec!(1))
+        );
+
+        let rows = [
+            HashMap::from([("x".to_string(), dec!(2))]),
+            HashMap::new(),
+            HashMap::from([("x".to_string(), dec!(0))]),
+        ];
+        let results = parse("10 / x").eval_batch(&rows, &cells, &context);
+        assert_eq!(
+            results,
+            vec![
+                n(dec!(5)),
+                Ok(Value::Error(ErrorKind::Na, Span::new(5, 6))),
+                Err(CalcError::DivideByZero.at(Span::new(3, 4))),
+            ]
+        );
+    }
+
+    /// Tests evaluation of cell references and aggregates over ranges.
+    #[test]
+    fn test_eval_cells() {
+        let cells = HashMap::from([
+            (CellRef::new(1, 1), dec!(1)),
+            (CellRef::new(1, 2), dec!(2)),
+            (CellRef::new(1, 4), dec!(6)),
+            (CellRef::new(2, 1), dec!(10)),
+        ]);
+        let variables = HashMap::new();
+        let eval = |expression| parse(expression).eval_with_cells(&variables, &cells);
+        assert_eq!(eval("sum<A1:A4> + B1"), Ok(dec!(19)));
+        assert_eq!(eval("avg<A1:A4>"), Ok(dec!(3)));
+        assert_eq!(eval("count<A1:B4>"), Ok(dec!(4)));
+        assert_eq!(eval("max<A1:A4, 7> - min<A1:B1>"), Ok(dec!(6)));
+        assert_eq!(eval("A3 + 1"), Ok(dec!(1)));
+        assert_eq!(
+            eval("A1:A4 + 1"),
+            Err(CalcError::TypeMismatch {
+                expected: ValueType::Number,
+                actual: ValueType::Range
+            })
+        );
+        assert_eq!(
+            parse("A1 + 1").eval(&variables),
+            Err(CalcError::UndefinedCell("A1".to_string()))
+        );
+
+        // 未开启单元格引用时，VAT1 这样的名称是普通变量
+        let variables =
+            HashMap::from([("VAT1".to_string(), dec!(2)), ("ID2".to_string(), dec!(5))]);
+        let eval = |expression| {
+            Parser::new(expression)
+                .unwrap()
+                .parse()
+                .unwrap()
+                .eval_with_cells(&variables, &cells)
+        };
+        assert_eq!(eval("VAT1 * 2"), Ok(dec!(4)));
+        assert_eq!(eval("ID2 + 1"), Ok(dec!(6)));
+    }
+
+    /// Tests that pretty-printed expressions only keep the parentheses they need
+    /// and parse back to the same tree.
+    #[test]
+    fn test_display_round_trip() {
+        for (expression, expected) in [
+            ("1 + 2 * 3", "1 + 2 * 3"),
+            ("(1 + 2) * 3", "(1 + 2) * 3"),
+            ("a - (b - c)", "a - (b - c)"),
+            ("(a - b) - c", "a - b - c"),
+            ("-(a + b) ^ 2", "-(a + b) ^ 2"),
+            ("nvl<abs<x>, 0> / { y }", "nvl<abs<x>, 0> / y"),
+            ("sum<A1:B10> - C2", "sum<A1:B10> - C2"),
+            (r#"round<x, 2, "HALF_EVEN">"#, r#"round<x, 2, "HALF_EVEN">"#),
+            ("-(3!) + (a + 1)! // (-2)!", "-3! + (a + 1)! // (-2)!"),
+            ("(a % b) % (c % d)", "a % b % (c % d)"),
+            ("a ^ (b ^ c)", "a ^ b ^ c"),
+            ("(a ^ b) ^ c", "(a ^ b) ^ c"),
+        ] {
+            let node = parse(expression);
+            assert_eq!(node.to_string(), expected);
+            assert_eq!(parse(&node.to_string()), node);
+        }
+    }
+
+    /// Tests that implicit printing only drops `*` where implicit
+    /// multiplication parses the text back to the same tree.
+    #[test]
+    fn test_implicit_round_trip() {
+        let options = ParseOptions {
+            implicit_multiplication: true,
+            cell_references: true,
+            ..ParseOptions::default()
+        };
+        let parse = |expression: &str| {
+            Parser::with_options(expression, &options)
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+        for (expression, expected) in [
+            ("2 * x", "2x"),
+            ("3 * (a + b)", "3(a + b)"),
+            ("(a + 1) * (b - 1)", "(a + 1)(b - 1)"),
+            ("a * b * abs<c>", "a b abs<c>"),
+            ("-2 * x ^ 2", "-2x ^ 2"),
+            ("a * (b * c)", "a(b c)"),
+            ("a / 2 * x", "a / 2x"),
+            ("x * 2", "x * 2"),
+            ("a * -b", "a * -b"),
+            ("2 * A1", "2 * A1"),
+            ("x2 * y", "x2 y"),
+        ] {
+            let node = parse(expression);
+            assert_eq!(node.to_implicit_string(), expected);
+            assert_eq!(parse(&node.to_implicit_string()), node);
+        }
+    }
+}
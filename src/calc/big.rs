@@ -0,0 +1,758 @@
+use crate::calc::context::RoundingMode;
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::numeric::Numeric;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+/// 每个分段保存 9 位十进制数
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+/// An arbitrary-precision signed integer.
+///
+/// The magnitude is stored little-endian in base 10^9, which keeps decimal
+/// parsing and printing straightforward.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        let mut limbs = Vec::new();
+        let mut value = value;
+        while value > 0 {
+            limbs.push((value % BASE) as u32);
+            value /= BASE;
+        }
+        Self {
+            negative: false,
+            limbs,
+        }
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        let mut result = Self::from_u64(value.unsigned_abs());
+        result.negative = value < 0;
+        result
+    }
+
+    /// Parses an optionally signed string of decimal digits.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let limbs = digits
+            .as_bytes()
+            .rchunks(BASE_DIGITS)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold(0u32, |acc, b| acc * 10 + (b - b'0') as u32)
+            })
+            .collect();
+        Some(Self::from_parts(negative, limbs))
+    }
+
+    /// 10^exponent
+    pub fn pow10(exponent: usize) -> Self {
+        let mut limbs = vec![0; exponent / BASE_DIGITS];
+        limbs.push(10u32.pow((exponent % BASE_DIGITS) as u32));
+        Self::from_parts(false, limbs)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            negative: false,
+            limbs: self.limbs.clone(),
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::from_parts(!self.negative, self.limbs.clone())
+    }
+
+    /// Returns the number of decimal digits of the magnitude; zero has one digit.
+    pub fn digits(&self) -> usize {
+        match self.limbs.last() {
+            None => 1,
+            Some(top) => (self.limbs.len() - 1) * BASE_DIGITS + top.to_string().len(),
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.limbs.len() > 3 {
+            return None;
+        }
+        let magnitude = self.limbs.iter().rev().try_fold(0i128, |acc, limb| {
+            acc.checked_mul(BASE as i128)?.checked_add(*limb as i128)
+        })?;
+        i64::try_from(if self.negative { -magnitude } else { magnitude }).ok()
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        if self.negative == rhs.negative {
+            return Self::from_parts(self.negative, add_magnitude(&self.limbs, &rhs.limbs));
+        }
+        match cmp_magnitude(&self.limbs, &rhs.limbs) {
+            Ordering::Less => {
+                Self::from_parts(rhs.negative, sub_magnitude(&rhs.limbs, &self.limbs))
+            }
+            _ => Self::from_parts(self.negative, sub_magnitude(&self.limbs, &rhs.limbs)),
+        }
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Self {
+        self.add(&rhs.neg())
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Self {
+        if self.is_zero() || rhs.is_zero() {
+            return Self::zero();
+        }
+        let mut result = vec![0u64; self.limbs.len() + rhs.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in rhs.limbs.iter().enumerate() {
+                let current = result[i + j] + (*a as u64) * (*b as u64) + carry;
+                result[i + j] = current % BASE;
+                carry = current / BASE;
+            }
+            result[i + rhs.limbs.len()] += carry;
+        }
+        Self::from_parts(
+            self.negative != rhs.negative,
+            result.into_iter().map(|limb| limb as u32).collect(),
+        )
+    }
+
+    /// Divides, truncating toward zero.
+    ///
+    /// # Returns
+    ///
+    /// The quotient and remainder, or `None` if `rhs` is zero
+    pub fn div_rem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let divisor = rhs.abs();
+        let mut quotient = vec![0u32; self.limbs.len()];
+        let mut remainder = Self::zero();
+        for i in (0..self.limbs.len()).rev() {
+            // remainder = remainder * BASE + limb
+            let mut limbs = Vec::with_capacity(remainder.limbs.len() + 1);
+            limbs.push(self.limbs[i]);
+            limbs.extend_from_slice(&remainder.limbs);
+            remainder = Self::from_parts(false, limbs);
+            // 二分查找当前位的商
+            let (mut low, mut high) = (0u64, BASE - 1);
+            while low < high {
+                let mid = (low + high).div_ceil(2);
+                if divisor.mul(&Self::from_u64(mid)).cmp(&remainder) == Ordering::Greater {
+                    high = mid - 1;
+                } else {
+                    low = mid;
+                }
+            }
+            quotient[i] = low as u32;
+            remainder = remainder.sub(&divisor.mul(&Self::from_u64(low)));
+        }
+        Some((
+            Self::from_parts(self.negative != rhs.negative, quotient),
+            Self::from_parts(self.negative, remainder.limbs),
+        ))
+    }
+
+    /// Returns the integer square root of a non-negative value.
+    pub fn isqrt(&self) -> Option<Self> {
+        if self.negative {
+            return None;
+        }
+        if self.is_zero() {
+            return Some(Self::zero());
+        }
+        // 牛顿迭代，初值不小于真实平方根
+        let two = Self::from_u64(2);
+        let mut x = Self::pow10(self.digits().div_ceil(2));
+        loop {
+            let (quotient, _) = self.div_rem(&x)?;
+            let (next, _) = x.add(&quotient).div_rem(&two)?;
+            if next >= x {
+                return Some(x);
+            }
+            x = next;
+        }
+    }
+
+    fn from_parts(negative: bool, mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        Self {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+}
+
+fn add_magnitude(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(lhs.len().max(rhs.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..lhs.len().max(rhs.len()) {
+        let current = *lhs.get(i).unwrap_or(&0) as u64 + *rhs.get(i).unwrap_or(&0) as u64 + carry;
+        result.push((current % BASE) as u32);
+        carry = current / BASE;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+/// lhs - rhs，要求 |lhs| >= |rhs|
+fn sub_magnitude(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(lhs.len());
+    let mut borrow = 0i64;
+    for (i, limb) in lhs.iter().enumerate() {
+        let mut current = *limb as i64 - *rhs.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if current < 0 {
+            current += BASE as i64;
+            borrow = 1;
+        }
+        result.push(current as u32);
+    }
+    result
+}
+
+fn cmp_magnitude(lhs: &[u32], rhs: &[u32]) -> Ordering {
+    lhs.len()
+        .cmp(&rhs.len())
+        .then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.limbs, &other.limbs),
+            (true, true) => cmp_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+        match self.limbs.split_last() {
+            None => f.write_str("0"),
+            Some((top, rest)) => {
+                write!(f, "{}", top)?;
+                rest.iter()
+                    .rev()
+                    .try_for_each(|limb| write!(f, "{:09}", limb))
+            }
+        }
+    }
+}
+
+/// An arbitrary-precision decimal number for the big-number mode.
+///
+/// The value is `mantissa * 10^-scale`. `PRECISION` is the maximum number of
+/// significant digits: addition, subtraction, multiplication and positive
+/// integer powers are exact and report `CalcError::Overflow` once a result
+/// needs more digits than that, while division, negative integer powers and
+/// square roots are rounded half-even to `PRECISION` digits. The default of 100 digits keeps `2^200` and
+/// `factorial<60>` exact.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::big::BigDecimal;
+/// use rust_calculate::calc::parser::Parser;
+/// use std::collections::HashMap;
+///
+/// let node = Parser::new("2 ^ 200").unwrap().parse().unwrap();
+/// let result = node.eval(&HashMap::<String, BigDecimal>::new()).unwrap();
+/// assert_eq!(result.to_string(), "1606938044258990275541962092341162602522202993782792835301376");
+///
+/// // 精度不足时报告溢出
+/// assert!(node.eval(&HashMap::<String, BigDecimal<50>>::new()).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigDecimal<const PRECISION: usize = 100> {
+    mantissa: BigInt,
+    scale: usize,
+}
+
+impl<const PRECISION: usize> BigDecimal<PRECISION> {
+    /// Creates `mantissa * 10^-scale`, removing trailing zeros and checking
+    /// the precision limit.
+    pub fn new(mantissa: BigInt, scale: usize) -> CalcResult<Self> {
+        let value = Self::normalize(mantissa, scale);
+        if value.mantissa.digits() > PRECISION {
+            return Err(CalcError::Overflow);
+        }
+        Ok(value)
+    }
+
+    pub fn mantissa(&self) -> &BigInt {
+        &self.mantissa
+    }
+
+    pub fn scale(&self) -> usize {
+        self.scale
+    }
+
+    fn normalize(mut mantissa: BigInt, mut scale: usize) -> Self {
+        let ten = BigInt::from_u64(10);
+        while scale > 0 {
+            let (quotient, remainder) = mantissa.div_rem(&ten).unwrap();
+            if !remainder.is_zero() {
+                break;
+            }
+            mantissa = quotient;
+            scale -= 1;
+        }
+        Self { mantissa, scale }
+    }
+
+    /// Returns both mantissas scaled to the larger of the two scales.
+    fn align(&self, rhs: &Self) -> (BigInt, BigInt, usize) {
+        let scale = self.scale.max(rhs.scale);
+        (
+            self.mantissa.mul(&BigInt::pow10(scale - self.scale)),
+            rhs.mantissa.mul(&BigInt::pow10(scale - rhs.scale)),
+            scale,
+        )
+    }
+
+    /// Rounds `mantissa * 10^-scale` half-even to `PRECISION` significant digits.
+    ///
+    /// `sticky` marks that the mantissa was already truncated, i.e. the exact
+    /// value is slightly larger in magnitude.
+    fn round_to_precision(mantissa: BigInt, scale: isize, sticky: bool) -> CalcResult<Self> {
+        let excess = mantissa.digits().saturating_sub(PRECISION);
+        let (mut mantissa, mut scale) = (mantissa, scale);
+        if excess > 0 {
+            let divisor = BigInt::pow10(excess);
+            let (quotient, remainder) = mantissa.div_rem(&divisor).unwrap();
+            let half = BigInt::pow10(excess - 1).mul(&BigInt::from_u64(5));
+            let remainder = remainder.abs();
+            let odd = quotient.div_rem(&BigInt::from_u64(2)).unwrap().1 != BigInt::zero();
+            let round_up = match remainder.cmp(&half) {
+                Ordering::Greater => true,
+                Ordering::Equal => sticky || odd,
+                Ordering::Less => false,
+            };
+            let one = BigInt::from_i64(if mantissa.is_negative() { -1 } else { 1 });
+            mantissa = if round_up {
+                quotient.add(&one)
+            } else {
+                quotient
+            };
+            scale -= excess as isize;
+        }
+        if scale < 0 {
+            mantissa = mantissa.mul(&BigInt::pow10(scale.unsigned_abs()));
+            scale = 0;
+        }
+        Self::new(mantissa, scale as usize)
+    }
+
+    fn is_integer(&self) -> bool {
+        self.scale == 0
+    }
+
+    /// Truncates `mantissa * 10^-scale` to `digits` significant digits,
+    /// setting `sticky` when nonzero digits are dropped.
+    fn truncate(
+        mantissa: BigInt,
+        scale: isize,
+        digits: usize,
+        sticky: &mut bool,
+    ) -> (BigInt, isize) {
+        let excess = mantissa.digits().saturating_sub(digits);
+        if excess == 0 {
+            return (mantissa, scale);
+        }
+        let (quotient, remainder) = mantissa.div_rem(&BigInt::pow10(excess)).unwrap();
+        *sticky |= !remainder.is_zero();
+        (quotient, scale - excess as isize)
+    }
+
+    /// Computes `self^-exponent` rounded half-even to `PRECISION` digits.
+    ///
+    /// `base^exponent` itself may need far more digits than the result, e.g.
+    /// `2^-400`, so the reciprocal is raised to the power instead, keeping
+    /// `GUARD_DIGITS` extra digits in every intermediate product.
+    fn pow_negative(&self, exponent: u64) -> CalcResult<Self> {
+        const GUARD_DIGITS: usize = 10;
+        if self.mantissa.is_zero() {
+            return Err(CalcError::DivideByZero);
+        }
+        let digits = PRECISION + GUARD_DIGITS;
+        let mut sticky = false;
+        // 1 / (m * 10^-s) = (10^shift / m) * 10^-(shift - s)
+        let shift = digits + 1 + self.mantissa.digits();
+        let (quotient, remainder) = BigInt::pow10(shift).div_rem(&self.mantissa).unwrap();
+        sticky |= !remainder.is_zero();
+        let mut base = Self::truncate(
+            quotient,
+            shift as isize - self.scale as isize,
+            digits,
+            &mut sticky,
+        );
+        let mut result = (BigInt::from_u64(1), 0);
+        let mut remaining = exponent;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = Self::truncate(
+                    result.0.mul(&base.0),
+                    result.1 + base.1,
+                    digits,
+                    &mut sticky,
+                );
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = Self::truncate(base.0.mul(&base.0), 2 * base.1, digits, &mut sticky);
+            }
+        }
+        Self::round_to_precision(result.0, result.1, sticky)
+    }
+}
+
+impl<const PRECISION: usize> PartialOrd for BigDecimal<PRECISION> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let (lhs, rhs, _) = self.align(other);
+        Some(lhs.cmp(&rhs))
+    }
+}
+
+impl<const PRECISION: usize> Display for BigDecimal<PRECISION> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.abs().to_string();
+        if self.mantissa.is_negative() {
+            f.write_str("-")?;
+        }
+        if self.scale == 0 {
+            return f.write_str(&digits);
+        }
+        let padded = format!("{:0>width$}", digits, width = self.scale + 1);
+        let (integer, fraction) = padded.split_at(padded.len() - self.scale);
+        write!(f, "{}.{}", integer, fraction)
+    }
+}
+
+impl<const PRECISION: usize> Numeric for BigDecimal<PRECISION> {
+    fn from_literal(literal: &str) -> CalcResult<Self> {
+        let (integer, fraction) = literal.split_once('.').unwrap_or((literal, ""));
+        if fraction.starts_with('-') {
+            return Err(CalcError::InvalidNumber(literal.to_string()));
+        }
+        let mantissa = BigInt::parse(&format!("{integer}{fraction}"))
+            .ok_or_else(|| CalcError::InvalidNumber(literal.to_string()))?;
+        Self::new(mantissa, fraction.len())
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Self::normalize(BigInt::from_u64(value as u64), 0)
+    }
+
+    fn max_value() -> Self {
+        // PRECISION 个 9
+        Self::normalize(BigInt::pow10(PRECISION).sub(&BigInt::from_u64(1)), 0)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.is_integer() {
+            self.mantissa.to_i64()
+        } else {
+            None
+        }
+    }
+
+    fn add(&self, rhs: &Self) -> CalcResult<Self> {
+        let (lhs, rhs, scale) = self.align(rhs);
+        Self::new(lhs.add(&rhs), scale)
+    }
+
+    fn sub(&self, rhs: &Self) -> CalcResult<Self> {
+        let (lhs, rhs, scale) = self.align(rhs);
+        Self::new(lhs.sub(&rhs), scale)
+    }
+
+    fn mul(&self, rhs: &Self) -> CalcResult<Self> {
+        Self::new(self.mantissa.mul(&rhs.mantissa), self.scale + rhs.scale)
+    }
+
+    fn div(&self, rhs: &Self) -> CalcResult<Self> {
+        if rhs.mantissa.is_zero() {
+            return Err(CalcError::DivideByZero);
+        }
+        // 放大被除数，使商至少有 PRECISION + 1 位有效数字
+        let shift = (PRECISION + 1 + rhs.mantissa.digits()).saturating_sub(self.mantissa.digits());
+        let dividend = self.mantissa.mul(&BigInt::pow10(shift));
+        let (quotient, remainder) = dividend.div_rem(&rhs.mantissa).unwrap();
+        let scale = self.scale as isize - rhs.scale as isize + shift as isize;
+        Self::round_to_precision(quotient, scale, !remainder.is_zero())
+    }
+
+    fn pow(&self, exponent: &Self) -> CalcResult<Self> {
+        let Some(n) = exponent.to_i64() else {
+            let expression = format!("{} ^ {}", self, exponent);
+            return Err(if self.mantissa.is_negative() {
+                CalcError::ComplexResult(expression)
+            } else {
+                CalcError::InexactResult(expression)
+            });
+        };
+        if n < 0 {
+            return self.pow_negative(n.unsigned_abs());
+        }
+        // 快速幂，每次乘法都会检查精度上限
+        let mut result = Self::from_usize(1);
+        let mut base = self.clone();
+        let mut remaining = n.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn neg(&self) -> CalcResult<Self> {
+        Ok(Self {
+            mantissa: self.mantissa.neg(),
+            scale: self.scale,
+        })
+    }
+
+    fn abs(&self) -> CalcResult<Self> {
+        Ok(Self {
+            mantissa: self.mantissa.abs(),
+            scale: self.scale,
+        })
+    }
+
+    fn sqrt(&self) -> CalcResult<Self> {
+        if self.mantissa.is_negative() {
+            return Err(CalcError::ComplexResult(format!("sqrt<{}>", self)));
+        }
+        // sqrt(m * 10^-s) = isqrt(m * 10^(2k + s % 2)) * 10^-(k + (s + s % 2) / 2)
+        let odd = self.scale % 2;
+        let k = PRECISION + 1;
+        let radicand = self.mantissa.mul(&BigInt::pow10(2 * k + odd));
+        let root = radicand.isqrt().unwrap();
+        let exact = root.mul(&root) == radicand;
+        Self::round_to_precision(root, (k + (self.scale + odd) / 2) as isize, !exact)
+    }
+
+    fn round(&self, scale: u32, mode: RoundingMode) -> CalcResult<Self> {
+        let scale = scale as usize;
+        if self.scale <= scale {
+            return Ok(self.clone());
+        }
+        let divisor = BigInt::pow10(self.scale - scale);
+        let (quotient, remainder) = self.mantissa.div_rem(&divisor).unwrap();
+        let half = remainder.abs().mul(&BigInt::from_u64(2)).cmp(&divisor);
+        let odd = !quotient.div_rem(&BigInt::from_u64(2)).unwrap().1.is_zero();
+        let negative = self.mantissa.is_negative();
+        let quotient = if mode.rounds_away(negative, half, remainder.is_zero(), odd) {
+            quotient.add(&BigInt::from_i64(if negative { -1 } else { 1 }))
+        } else {
+            quotient
+        };
+        Self::new(quotient, scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(literal: &str) -> BigDecimal {
+        BigDecimal::from_literal(literal).unwrap()
+    }
+
+    /// Tests parsing, printing and arithmetic of big integers.
+    #[test]
+    fn test_big_int() {
+        let a = BigInt::parse("123456789012345678901234567890").unwrap();
+        let b = BigInt::parse("-987654321").unwrap();
+        assert_eq!(a.to_string(), "123456789012345678901234567890");
+        assert_eq!(a.add(&b).to_string(), "123456789012345678900246913569");
+        assert_eq!(b.sub(&a).to_string(), "-123456789012345678902222222211");
+        assert_eq!(
+            a.mul(&b).to_string(),
+            "-121932631124828532112482853211126352690"
+        );
+        let (quotient, remainder) = a.div_rem(&b).unwrap();
+        assert_eq!(quotient.to_string(), "-124999998873437499901");
+        assert_eq!(remainder.to_string(), "574845669");
+        assert_eq!(quotient.mul(&b).add(&remainder), a);
+        assert_eq!(
+            BigInt::parse("1000000").unwrap().isqrt(),
+            Some(BigInt::from_u64(1000))
+        );
+        assert_eq!(BigInt::from_u64(99).isqrt(), Some(BigInt::from_u64(9)));
+        assert_eq!(BigInt::pow10(10).digits(), 11);
+        assert_eq!(BigInt::from_i64(-42).to_i64(), Some(-42));
+    }
+
+    /// Tests exact decimal arithmetic and the precision limit.
+    #[test]
+    fn test_big_decimal_arithmetic() {
+        assert_eq!(big("0.1").add(&big("0.2")), Ok(big("0.3")));
+        assert_eq!(big("1.50").to_string(), "1.5");
+        assert_eq!(big("-0.05").mul(&big("3")).unwrap().to_string(), "-0.15");
+        assert!(big("0.5") < big("0.75"));
+        assert_eq!(
+            big("99999999999999999999999999999")
+                .add(&big("1"))
+                .unwrap()
+                .to_string(),
+            "100000000000000000000000000000"
+        );
+        assert_eq!(big("2").pow(&big("-2")), Ok(big("0.25")));
+        assert_eq!(
+            BigDecimal::<5>::from_literal("99999")
+                .unwrap()
+                .add(&BigDecimal::from_usize(1)),
+            Err(CalcError::Overflow)
+        );
+    }
+
+    /// Tests that division and square roots are rounded to the precision.
+    #[test]
+    fn test_big_decimal_rounding() {
+        let third = BigDecimal::<10>::from_usize(1)
+            .div(&BigDecimal::from_usize(3))
+            .unwrap();
+        assert_eq!(third.to_string(), "0.3333333333");
+        let two_thirds = BigDecimal::<10>::from_usize(2)
+            .div(&BigDecimal::from_usize(3))
+            .unwrap();
+        assert_eq!(two_thirds.to_string(), "0.6666666667");
+        assert_eq!(big("10").div(&big("4")), Ok(big("2.5")));
+        assert_eq!(big("2.25").sqrt(), Ok(big("1.5")));
+        assert_eq!(
+            BigDecimal::<20>::from_usize(2).sqrt().unwrap().to_string(),
+            "1.4142135623730950488"
+        );
+        assert_eq!(big("1").div(&big("0")), Err(CalcError::DivideByZero));
+    }
+
+    /// Tests that a negative power is rounded to the precision even when the
+    /// positive power would need more digits.
+    #[test]
+    fn test_big_decimal_negative_pow() {
+        let pow = |base: &str, exponent: &str| {
+            BigDecimal::<10>::from_literal(base)
+                .unwrap()
+                .pow(&BigDecimal::from_literal(exponent).unwrap())
+                .map(|value| value.to_string())
+        };
+        assert_eq!(pow("2", "-3"), Ok("0.125".to_string()));
+        assert_eq!(pow("-0.7", "-5"), Ok("-5.949901827".to_string()));
+        assert_eq!(pow("3", "-7"), Ok("0.0004572473708".to_string()));
+        assert_eq!(pow("2", "-40"), Ok("0.0000000000009094947018".to_string()));
+        assert_eq!(
+            pow("2", "-400"),
+            Ok(format!("0.{}3872591915", "0".repeat(120)))
+        );
+        assert_eq!(pow("0.5", "-40"), Err(CalcError::Overflow));
+        assert_eq!(pow("0", "-1"), Err(CalcError::DivideByZero));
+        assert_eq!(
+            big("2").pow(&big("-400")).unwrap().to_string(),
+            format!(
+                "0.{}{}",
+                "0".repeat(120),
+                "3872591914849318272818030633286351847570219192048790865487762941344416348097685964862682234277014597"
+            )
+        );
+    }
+
+    /// Tests that the big-number mode evaluates results that overflow `Decimal`.
+    #[test]
+    fn test_eval_big_mode() {
+        use crate::calc::parser::Parser;
+        use rust_decimal::Decimal;
+        use std::collections::HashMap;
+
+        let node = Parser::new("factorial<60> / 2 ^ 56")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            node.eval(&HashMap::<String, BigDecimal>::new())
+                .unwrap()
+                .to_string(),
+            "115476893502183682653166335352659171719555028600718376458740234375"
+        );
+        assert_eq!(
+            node.eval(&HashMap::<String, Decimal>::new())
+                .map_err(|error| error.without_span().clone()),
+            Err(CalcError::Overflow)
+        );
+    }
+
+    /// Tests that a literal too long for `Decimal` is read exactly in the
+    /// big-number mode and rejected in the default mode.
+    #[test]
+    fn test_eval_big_literal() {
+        use crate::calc::parser::Parser;
+        use rust_decimal::Decimal;
+        use std::collections::HashMap;
+
+        let literal = "1234567890123456789012345678901234567890";
+        let node = Parser::new(&format!("{} + 1", literal))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(node.to_string(), format!("{} + 1", literal));
+        assert_eq!(
+            node.eval(&HashMap::<String, BigDecimal>::new())
+                .unwrap()
+                .to_string(),
+            "1234567890123456789012345678901234567891"
+        );
+        assert_eq!(
+            node.eval(&HashMap::<String, Decimal>::new())
+                .map_err(|error| error.without_span().clone()),
+            Err(CalcError::InvalidNumber(literal.to_string()))
+        );
+    }
+}
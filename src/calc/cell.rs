@@ -0,0 +1,184 @@
+use crate::calc::error::{CalcError, CalcResult};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// An A1-style reference to a single cell.
+///
+/// Columns and rows are 1-based, so `A1` is column 1, row 1 and `AB12` is
+/// column 28, row 12.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct CellRef {
+    pub column: u32,
+    pub row: u32,
+}
+
+impl CellRef {
+    /// The last column of a spreadsheet, `XFD`.
+    pub const MAX_COLUMN: u32 = 16_384;
+
+    pub fn new(column: u32, row: u32) -> Self {
+        Self { column, row }
+    }
+
+    /// Parses an A1-style reference such as `B12`.
+    ///
+    /// # Returns
+    ///
+    /// The cell reference, or `None` if the text is not uppercase column
+    /// letters up to `XFD` followed by a row number of at least 1
+    pub fn parse(text: &str) -> Option<Self> {
+        let split = text.find(|c: char| !c.is_ascii_uppercase())?;
+        let (letters, digits) = text.split_at(split);
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let column = Self::parse_column(letters)?;
+        let row = digits.parse().ok().filter(|row| *row > 0)?;
+        Some(Self::new(column, row))
+    }
+
+    /// Parses column letters such as `AB`.
+    ///
+    /// # Returns
+    ///
+    /// The 1-based column number, or `None` if the text is not uppercase
+    /// letters or names a column past `XFD`
+    pub fn parse_column(letters: &str) -> Option<u32> {
+        if letters.is_empty() || letters.len() > 3 {
+            return None;
+        }
+        letters
+            .chars()
+            .try_fold(0u32, |acc, c| {
+                c.is_ascii_uppercase()
+                    .then(|| acc * 26 + (c as u32 - 'A' as u32 + 1))
+            })
+            .filter(|column| *column <= Self::MAX_COLUMN)
+    }
+}
+
+impl Display for CellRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // 列号为 26 进制且没有 0，例如 26 -> Z, 27 -> AA
+        let mut letters = Vec::new();
+        let mut column = self.column;
+        while column > 0 {
+            column -= 1;
+            letters.push((b'A' + (column % 26) as u8) as char);
+            column /= 26;
+        }
+        letters.iter().rev().try_for_each(|c| write!(f, "{}", c))?;
+        write!(f, "{}", self.row)
+    }
+}
+
+/// Supplies cell values to the evaluator.
+///
+/// Implement this trait to resolve cell references and ranges against a grid.
+/// Blank cells are reported as `None`; they count as zero in arithmetic and
+/// are skipped by aggregates.
+pub trait CellProvider {
+    /// Returns the value of a single cell, or `None` if the cell is blank.
+    fn get_cell(&self, cell: &CellRef) -> CalcResult<Option<Decimal>>;
+
+    /// Returns the values of the non-blank cells in a rectangular range, row by row.
+    ///
+    /// The default implementation calls `get_cell` for every cell; providers
+    /// backed by a sparse or remote store should override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - One corner of the range
+    /// * `to` - The opposite corner of the range
+    /// * `interrupt` - Checks the evaluation's deadline; an implementation
+    ///   that visits cells one by one should call it regularly and stop with
+    ///   its error
+    fn get_range(
+        &self,
+        from: &CellRef,
+        to: &CellRef,
+        interrupt: &dyn Fn() -> CalcResult<()>,
+    ) -> CalcResult<Vec<Decimal>> {
+        let mut values = Vec::new();
+        for row in from.row.min(to.row)..=from.row.max(to.row) {
+            for column in from.column.min(to.column)..=from.column.max(to.column) {
+                interrupt()?;
+                if let Some(value) = self.get_cell(&CellRef::new(column, row))? {
+                    values.push(value);
+                }
+            }
+        }
+        Ok(values)
+    }
+}
+
+impl CellProvider for HashMap<CellRef, Decimal> {
+    fn get_cell(&self, cell: &CellRef) -> CalcResult<Option<Decimal>> {
+        Ok(self.get(cell).copied())
+    }
+}
+
+/// Provider used when the caller does not supply one: every reference is an error.
+pub(crate) struct NoCells;
+
+impl CellProvider for NoCells {
+    fn get_cell(&self, cell: &CellRef) -> CalcResult<Option<Decimal>> {
+        Err(CalcError::UndefinedCell(cell.to_string()))
+    }
+
+    fn get_range(
+        &self,
+        from: &CellRef,
+        to: &CellRef,
+        _interrupt: &dyn Fn() -> CalcResult<()>,
+    ) -> CalcResult<Vec<Decimal>> {
+        Err(CalcError::UndefinedCell(format!("{}:{}", from, to)))
+    }
+}
+
+/// Returns the number of cells in the range between two corners.
+pub(crate) fn range_size(from: &CellRef, to: &CellRef) -> u64 {
+    let rows = from.row.abs_diff(to.row) as u64 + 1;
+    let columns = from.column.abs_diff(to.column) as u64 + 1;
+    rows * columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    /// Tests parsing and printing A1-style references.
+    #[test]
+    fn test_cell_ref() {
+        assert_eq!(CellRef::parse("A1"), Some(CellRef::new(1, 1)));
+        assert_eq!(CellRef::parse("AB12"), Some(CellRef::new(28, 12)));
+        assert_eq!(CellRef::parse("A0"), None);
+        assert_eq!(CellRef::parse("a1"), None);
+        assert_eq!(CellRef::parse("A"), None);
+        assert_eq!(CellRef::parse("XFE1"), None);
+        assert_eq!(CellRef::parse("AAAA1"), None);
+        for text in ["A1", "Z9", "AA10", "XFD1048576"] {
+            assert_eq!(CellRef::parse(text).unwrap().to_string(), text);
+        }
+    }
+
+    /// Tests that the default range lookup skips blank cells.
+    #[test]
+    fn test_get_range() {
+        let cells = HashMap::from([(CellRef::new(1, 1), dec!(1)), (CellRef::new(2, 2), dec!(4))]);
+        assert_eq!(
+            cells.get_range(&CellRef::new(2, 2), &CellRef::new(1, 1), &|| Ok(())),
+            Ok(vec![dec!(1), dec!(4)])
+        );
+        assert_eq!(
+            cells.get_range(&CellRef::new(1, 1), &CellRef::new(2, 2), &|| {
+                Err(CalcError::Overflow)
+            }),
+            Err(CalcError::Overflow)
+        );
+        assert_eq!(range_size(&CellRef::new(3, 1), &CellRef::new(1, 4)), 12);
+    }
+}
@@ -0,0 +1,341 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Digits used for uppercase amounts (大写金额), from 零 to 玖.
+const UPPER_DIGITS: [char; 10] = ['零', '壹', '贰', '叁', '肆', '伍', '陆', '柒', '捌', '玖'];
+
+/// Digits used for ordinary Chinese numerals, from 零 to 九.
+const LOWER_DIGITS: [char; 10] = ['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+/// Units within a group of four digits, from the ones up to the thousands.
+const UPPER_UNITS: [&str; 4] = ["", "拾", "佰", "仟"];
+const LOWER_UNITS: [&str; 4] = ["", "十", "百", "千"];
+
+/// Formats an amount as uppercase Chinese currency (人民币大写), rounded
+/// half up to the fen.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::chinese::to_rmb;
+/// use rust_decimal::dec;
+///
+/// assert_eq!(to_rmb(dec!(1234.50)), "壹仟贰佰叁拾肆元伍角整");
+/// assert_eq!(to_rmb(dec!(100000.07)), "壹拾万元零柒分");
+/// assert_eq!(to_rmb(dec!(-0.3)), "负叁角整");
+/// ```
+pub fn to_rmb(amount: Decimal) -> String {
+    let amount = amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    let cents = amount.abs().mantissa().unsigned_abs() * 10u128.pow(2 - amount.scale());
+    let (yuan, jiao, fen) = (
+        cents / 100,
+        (cents / 10 % 10) as usize,
+        (cents % 10) as usize,
+    );
+    let mut text = String::new();
+    if amount.is_sign_negative() && cents > 0 {
+        text.push('负');
+    }
+    if yuan > 0 {
+        text.push_str(&integer_to_chinese(yuan, &UPPER_DIGITS, &UPPER_UNITS));
+        text.push('元');
+    }
+    match (jiao, fen) {
+        (0, 0) if yuan == 0 => text.push_str("零元整"),
+        (0, 0) => text.push('整'),
+        (jiao, 0) => {
+            text.push(UPPER_DIGITS[jiao]);
+            text.push_str("角整");
+        }
+        (jiao, fen) => {
+            if jiao > 0 {
+                text.push(UPPER_DIGITS[jiao]);
+                text.push('角');
+            } else if yuan > 0 {
+                text.push('零');
+            }
+            text.push(UPPER_DIGITS[fen]);
+            text.push('分');
+        }
+    }
+    text
+}
+
+/// Formats a number as an ordinary Chinese numeral, such as `一百二十` or
+/// `三点一四`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::chinese::to_chinese;
+/// use rust_decimal::dec;
+///
+/// assert_eq!(to_chinese(dec!(120)), "一百二十");
+/// assert_eq!(to_chinese(dec!(15)), "十五");
+/// assert_eq!(to_chinese(dec!(-3.14)), "负三点一四");
+/// ```
+pub fn to_chinese(value: Decimal) -> String {
+    let value = value.normalize();
+    let mut text = String::new();
+    if value.is_sign_negative() && !value.is_zero() {
+        text.push('负');
+    }
+    let digits = value.abs().to_string();
+    let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+    let integer: u128 = integer.parse().unwrap_or_default();
+    if integer == 0 {
+        text.push('零');
+    } else {
+        let words = integer_to_chinese(integer, &LOWER_DIGITS, &LOWER_UNITS);
+        // 10 到 19 习惯上省略开头的“一”
+        match words.strip_prefix("一十") {
+            Some(rest) => {
+                text.push('十');
+                text.push_str(rest);
+            }
+            None => text.push_str(&words),
+        }
+    }
+    if !fraction.is_empty() {
+        text.push('点');
+        text.extend(
+            fraction
+                .bytes()
+                .map(|digit| LOWER_DIGITS[(digit - b'0') as usize]),
+        );
+    }
+    text
+}
+
+/// Writes a positive integer with the given digits and units.
+///
+/// The digits are grouped by four under 万 and by eight under 亿, so
+/// 3200000000000 is 三万二千亿. Runs of zeros collapse into a single 零.
+fn integer_to_chinese(value: u128, digits: &[char; 10], units: &[&str; 4]) -> String {
+    const YI: u128 = 100_000_000;
+    if value < YI {
+        return below_yi(value, digits, units);
+    }
+    let (high, low) = (value / YI, value % YI);
+    let mut text = integer_to_chinese(high, digits, units);
+    text.push('亿');
+    if low > 0 {
+        // 亿以下的最高位为零时需要补“零”
+        if low < YI / 10 {
+            text.push(digits[0]);
+        }
+        text.push_str(&below_yi(low, digits, units));
+    }
+    text
+}
+
+/// Writes a positive integer below 亿 as up to two groups of four digits.
+fn below_yi(value: u128, digits: &[char; 10], units: &[&str; 4]) -> String {
+    let (high, low) = (value / 10_000, value % 10_000);
+    if high == 0 {
+        return below_wan(low, digits, units);
+    }
+    let mut text = below_wan(high, digits, units);
+    text.push('万');
+    if low > 0 {
+        if low < 1_000 {
+            text.push(digits[0]);
+        }
+        text.push_str(&below_wan(low, digits, units));
+    }
+    text
+}
+
+/// Writes a positive integer below 万.
+fn below_wan(value: u128, digits: &[char; 10], units: &[&str; 4]) -> String {
+    let mut text = String::new();
+    let mut pending_zero = false;
+    for position in (0..4).rev() {
+        let digit = (value / 10u128.pow(position) % 10) as usize;
+        if digit == 0 {
+            pending_zero = !text.is_empty();
+            continue;
+        }
+        if pending_zero {
+            text.push(digits[0]);
+            pending_zero = false;
+        }
+        text.push(digits[digit]);
+        text.push_str(units[position as usize]);
+    }
+    text
+}
+
+/// Returns true if the character can appear in a Chinese numeral accepted
+/// by [`parse_chinese`].
+pub fn is_chinese_numeral_char(c: char) -> bool {
+    chinese_digit(c).is_some() || chinese_unit(c).is_some() || c == '点'
+}
+
+fn chinese_digit(c: char) -> Option<u32> {
+    match c {
+        '零' | '〇' => Some(0),
+        '一' | '壹' => Some(1),
+        '二' | '两' | '贰' => Some(2),
+        '三' | '叁' => Some(3),
+        '四' | '肆' => Some(4),
+        '五' | '伍' => Some(5),
+        '六' | '陆' => Some(6),
+        '七' | '柒' => Some(7),
+        '八' | '捌' => Some(8),
+        '九' | '玖' => Some(9),
+        _ => None,
+    }
+}
+
+fn chinese_unit(c: char) -> Option<u64> {
+    match c {
+        '十' | '拾' => Some(10),
+        '百' | '佰' => Some(100),
+        '千' | '仟' => Some(1_000),
+        '万' => Some(10_000),
+        '亿' => Some(100_000_000),
+        _ => None,
+    }
+}
+
+/// Parses a Chinese numeral such as `一百二十`, `两万三千`, `二〇二四` or
+/// `三点一四`, in ordinary or uppercase digits.
+///
+/// # Returns
+///
+/// The value, or `None` if the text is not a well-formed numeral or the
+/// value does not fit into a `Decimal`
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::chinese::parse_chinese;
+/// use rust_decimal::dec;
+///
+/// assert_eq!(parse_chinese("一百二十"), Some(dec!(120)));
+/// assert_eq!(parse_chinese("壹万零伍"), Some(dec!(10005)));
+/// assert_eq!(parse_chinese("三点一四"), Some(dec!(3.14)));
+/// assert_eq!(parse_chinese("万"), None);
+/// ```
+pub fn parse_chinese(text: &str) -> Option<Decimal> {
+    let (integer, fraction) = match text.split_once('点') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (text, None),
+    };
+    let mut value = parse_chinese_integer(integer)?;
+    if let Some(fraction) = fraction {
+        if fraction.is_empty() {
+            return None;
+        }
+        let mut scale = Decimal::ONE;
+        for c in fraction.chars() {
+            scale /= Decimal::TEN;
+            value = value.checked_add(Decimal::from(chinese_digit(c)?) * scale)?;
+        }
+    }
+    Some(value)
+}
+
+fn parse_chinese_integer(text: &str) -> Option<Decimal> {
+    // total 为已完成的万、亿段，section 为当前段，number 为最近的数字
+    let (mut total, mut section, mut number) = (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+    let mut previous_digit = false;
+    let mut has_digit = false;
+    for c in text.chars() {
+        if let Some(digit) = chinese_digit(c) {
+            // 连续的数字按位读，例如 二〇二四
+            number = if previous_digit {
+                number
+                    .checked_mul(Decimal::TEN)?
+                    .checked_add(digit.into())?
+            } else {
+                digit.into()
+            };
+            previous_digit = true;
+            has_digit = true;
+            continue;
+        }
+        let unit = Decimal::from(chinese_unit(c)?);
+        previous_digit = false;
+        match c {
+            '万' | '亿' => {
+                if !has_digit {
+                    return None;
+                }
+                let group = section.checked_add(number)?;
+                if c == '亿' {
+                    total = total.checked_add(group)?.checked_mul(unit)?;
+                } else {
+                    total = total.checked_add(group.checked_mul(unit)?)?;
+                }
+                section = Decimal::ZERO;
+            }
+            _ => {
+                // 开头的“十”表示一十
+                let count = if number.is_zero() {
+                    Decimal::ONE
+                } else {
+                    number
+                };
+                section = section.checked_add(count.checked_mul(unit)?)?;
+                has_digit = true;
+            }
+        }
+        number = Decimal::ZERO;
+    }
+    if !has_digit {
+        return None;
+    }
+    total.checked_add(section)?.checked_add(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    /// Tests uppercase amounts with zeros inside and between groups, and
+    /// with and without jiao and fen.
+    #[test]
+    fn test_to_rmb() {
+        assert_eq!(to_rmb(dec!(0)), "零元整");
+        assert_eq!(to_rmb(dec!(0.05)), "伍分");
+        assert_eq!(to_rmb(dec!(10)), "壹拾元整");
+        assert_eq!(to_rmb(dec!(1005.60)), "壹仟零伍元陆角整");
+        assert_eq!(to_rmb(dec!(100010000)), "壹亿零壹万元整");
+        assert_eq!(to_rmb(dec!(10000000000)), "壹佰亿元整");
+        assert_eq!(to_rmb(dec!(1000000000000)), "壹万亿元整");
+        assert_eq!(to_rmb(dec!(3.456)), "叁元肆角陆分");
+        assert_eq!(to_rmb(dec!(-0.001)), "零元整");
+    }
+
+    /// Tests ordinary numerals and that parsing them gives the value back.
+    #[test]
+    fn test_chinese_round_trip() {
+        for value in [
+            dec!(0),
+            dec!(7),
+            dec!(10),
+            dec!(19),
+            dec!(105),
+            dec!(120),
+            dec!(1010),
+            dec!(23000),
+            dec!(100000001),
+            dec!(3200000000000),
+            dec!(0.5),
+            dec!(12.75),
+        ] {
+            let text = to_chinese(value);
+            assert_eq!(parse_chinese(&text), Some(value), "{text}");
+        }
+        assert_eq!(to_chinese(dec!(100000001)), "一亿零一");
+        assert_eq!(to_chinese(dec!(3200000000000)), "三万二千亿");
+        assert_eq!(parse_chinese("二〇二四"), Some(dec!(2024)));
+        assert_eq!(parse_chinese("两千零二十"), Some(dec!(2020)));
+        assert_eq!(parse_chinese("一万亿"), Some(dec!(1000000000000)));
+        assert_eq!(parse_chinese("三点"), None);
+        assert_eq!(parse_chinese("三点十"), None);
+        assert_eq!(parse_chinese("三个"), None);
+    }
+}
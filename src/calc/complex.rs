@@ -0,0 +1,388 @@
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::numeric::Numeric;
+use rust_decimal::{Decimal, MathematicalOps};
+use std::fmt::{Display, Formatter};
+
+/// A complex number `re + im·i` over a numeric backend.
+///
+/// Complex values appear when an expression contains an imaginary literal
+/// such as `4i`, or when `Context::complex_numbers` is set and a real
+/// operation such as `sqrt<-4>` has no real result. Arithmetic is done on
+/// the backend; the argument, polar form and non-integer powers go through
+/// `Decimal`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::complex::Complex;
+/// use rust_decimal::dec;
+///
+/// let z = Complex::new(dec!(3), dec!(4));
+/// assert_eq!(z.abs(), Ok(dec!(5)));
+/// assert_eq!(z.mul(&z.conj().unwrap()), Ok(Complex::new(dec!(25), dec!(0))));
+/// assert_eq!(z.to_string(), "3 + 4i");
+/// assert_eq!(Complex::new(dec!(-4), dec!(0)).sqrt(), Ok(Complex::new(dec!(0), dec!(2))));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Complex<N = Decimal> {
+    /// The real part
+    pub re: N,
+    /// The imaginary part
+    pub im: N,
+}
+
+impl<N: Numeric> Complex<N> {
+    /// Creates the complex number `re + im·i`.
+    pub fn new(re: N, im: N) -> Self {
+        Self { re, im }
+    }
+
+    /// Creates a complex number with no imaginary part.
+    pub fn from_real(re: N) -> Self {
+        Self::new(re, N::zero())
+    }
+
+    /// Returns whether the imaginary part is zero.
+    pub fn is_real(&self) -> bool {
+        self.im.is_zero()
+    }
+
+    /// Applies a fallible function, such as rounding, to both parts.
+    pub fn try_map(self, mut f: impl FnMut(N) -> CalcResult<N>) -> CalcResult<Self> {
+        Ok(Self::new(f(self.re)?, f(self.im)?))
+    }
+
+    pub fn add(&self, other: &Self) -> CalcResult<Self> {
+        Ok(Self::new(self.re.add(&other.re)?, self.im.add(&other.im)?))
+    }
+
+    pub fn sub(&self, other: &Self) -> CalcResult<Self> {
+        Ok(Self::new(self.re.sub(&other.re)?, self.im.sub(&other.im)?))
+    }
+
+    pub fn mul(&self, other: &Self) -> CalcResult<Self> {
+        let re = self.re.mul(&other.re)?.sub(&self.im.mul(&other.im)?)?;
+        let im = self.re.mul(&other.im)?.add(&self.im.mul(&other.re)?)?;
+        Ok(Self::new(re, im))
+    }
+
+    /// Divides by another complex number.
+    ///
+    /// # Returns
+    ///
+    /// The quotient, or `CalcError::DivideByZero` if `other` is zero
+    pub fn div(&self, other: &Self) -> CalcResult<Self> {
+        if other.is_real() {
+            return Ok(Self::new(self.re.div(&other.re)?, self.im.div(&other.re)?));
+        }
+        let denominator = other.re.mul(&other.re)?.add(&other.im.mul(&other.im)?)?;
+        let re = self.re.mul(&other.re)?.add(&self.im.mul(&other.im)?)?;
+        let im = self.im.mul(&other.re)?.sub(&self.re.mul(&other.im)?)?;
+        Ok(Self::new(re.div(&denominator)?, im.div(&denominator)?))
+    }
+
+    pub fn neg(&self) -> CalcResult<Self> {
+        Ok(Self::new(self.re.neg()?, self.im.neg()?))
+    }
+
+    /// Returns the complex conjugate `re - im·i`.
+    pub fn conj(&self) -> CalcResult<Self> {
+        Ok(Self::new(self.re.clone(), self.im.neg()?))
+    }
+
+    /// Returns the modulus `sqrt(re² + im²)`.
+    pub fn abs(&self) -> CalcResult<N> {
+        if self.is_real() {
+            return self.re.abs();
+        }
+        if self.re.is_zero() {
+            return self.im.abs();
+        }
+        self.re.mul(&self.re)?.add(&self.im.mul(&self.im)?)?.sqrt()
+    }
+
+    /// Returns the argument in radians, between `-π` (exclusive) and `π`.
+    /// The argument of zero is 0.
+    pub fn arg(&self) -> CalcResult<N> {
+        N::from_decimal(atan2(self.im.to_decimal()?, self.re.to_decimal()?)?)
+    }
+
+    /// Returns the principal square root, whose real part is not negative.
+    pub fn sqrt(&self) -> CalcResult<Self> {
+        if self.re.is_zero() && self.is_real() {
+            return Ok(self.clone());
+        }
+        // t = sqrt((|z| + |re|) / 2)，避免 |z| 与 re 相减时损失精度
+        let two = N::from_usize(2);
+        let t = self.abs()?.add(&self.re.abs()?)?.div(&two)?.sqrt()?;
+        let other = self.im.div(&t.mul(&two)?)?;
+        if self.re >= N::zero() {
+            Ok(Self::new(t, other))
+        } else if self.im < N::zero() {
+            Ok(Self::new(other.neg()?, t.neg()?))
+        } else {
+            Ok(Self::new(other, t))
+        }
+    }
+
+    /// Raises to a complex power.
+    ///
+    /// Integer exponents are computed by repeated multiplication and
+    /// half-integer ones through the square root, so `(-4) ^ 0.5` is exactly
+    /// `2i`. Any other exponent uses the polar form
+    /// `exp(exponent · ln(self))` in `Decimal`.
+    ///
+    /// # Returns
+    ///
+    /// The power, `CalcError::DivideByZero` for zero raised to an exponent
+    /// whose real part is not positive, or `CalcError::Overflow` and
+    /// `CalcError::Underflow` when the modulus leaves the backend's range
+    pub fn pow(&self, exponent: &Self) -> CalcResult<Self> {
+        if exponent.is_real()
+            && let Some(n) = exponent.re.to_i64()
+        {
+            return self.powi(n);
+        }
+        if self.re.is_zero() && self.is_real() {
+            return if exponent.re > N::zero() {
+                Ok(self.clone())
+            } else {
+                Err(CalcError::DivideByZero)
+            };
+        }
+        if exponent.is_real()
+            && let Some(n) = exponent.re.mul(&N::from_usize(2))?.to_i64()
+        {
+            return self.sqrt()?.powi(n);
+        }
+        let (re, im) = (self.re.to_decimal()?, self.im.to_decimal()?);
+        let (c, d) = (exponent.re.to_decimal()?, exponent.im.to_decimal()?);
+        let ln_modulus = self
+            .abs()?
+            .to_decimal()?
+            .checked_ln()
+            .ok_or(CalcError::Overflow)?;
+        let angle = atan2(im, re)?;
+        // z^w = exp(c·ln|z| - d·θ) · (cos(d·ln|z| + c·θ) + i·sin(d·ln|z| + c·θ))
+        let exponent = c
+            .checked_mul(ln_modulus)
+            .zip(d.checked_mul(angle))
+            .and_then(|(a, b)| a.checked_sub(b))
+            .ok_or(CalcError::Overflow)?;
+        let modulus = exponent
+            .checked_exp_with_tolerance(Decimal::new(1, 28))
+            .ok_or(if exponent > Decimal::ZERO {
+                CalcError::Overflow
+            } else {
+                CalcError::Underflow
+            })?;
+        let phase = d
+            .checked_mul(ln_modulus)
+            .zip(c.checked_mul(angle))
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or(CalcError::Overflow)?;
+        let (sin, cos) = sin_cos(phase)?;
+        let part = |trig: Decimal| {
+            modulus
+                .checked_mul(trig)
+                .ok_or(CalcError::Overflow)
+                .and_then(N::from_decimal)
+        };
+        Ok(Self::new(part(cos)?, part(sin)?))
+    }
+
+    /// Raises to an integer power by repeated squaring.
+    fn powi(&self, n: i64) -> CalcResult<Self> {
+        let one = Self::from_real(N::from_usize(1));
+        let mut result = one.clone();
+        let mut base = self.clone();
+        let mut remaining = n.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        if n < 0 { one.div(&result) } else { Ok(result) }
+    }
+}
+
+impl<N: Numeric> Display for Complex<N> {
+    /// Writes the number in rectangular form, such as `3 + 4i`, `3 - 4i` or
+    /// `-4i`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.re.is_zero() && !self.is_real() {
+            return write!(f, "{}i", self.im);
+        }
+        let im = self.im.to_string();
+        match im.strip_prefix('-') {
+            Some(magnitude) => write!(f, "{} - {}i", self.re, magnitude),
+            None => write!(f, "{} + {}i", self.re, im),
+        }
+    }
+}
+
+/// Computes `atan2(y, x)` in radians.
+///
+/// `rust_decimal`'s trigonometric functions are only accurate to about 12
+/// digits, so the angle is computed with a series in full precision.
+fn atan2(y: Decimal, x: Decimal) -> CalcResult<Decimal> {
+    if x.is_zero() && y.is_zero() {
+        return Ok(Decimal::ZERO);
+    }
+    // 先取 |比值| <= 1 的一侧，再按象限调整
+    if y.abs() <= x.abs() {
+        let angle = atan(y.checked_div(x).ok_or(CalcError::Underflow)?)?;
+        Ok(match (x < Decimal::ZERO, y < Decimal::ZERO) {
+            (false, _) => angle,
+            (true, false) => angle + Decimal::PI,
+            (true, true) => angle - Decimal::PI,
+        })
+    } else {
+        let angle = atan(x.checked_div(y).ok_or(CalcError::Underflow)?)?;
+        Ok(if y > Decimal::ZERO {
+            Decimal::HALF_PI - angle
+        } else {
+            -Decimal::HALF_PI - angle
+        })
+    }
+}
+
+/// Computes `atan(x)` for `|x| <= 1`.
+fn atan(x: Decimal) -> CalcResult<Decimal> {
+    // atan(x) = 2·atan(x / (1 + sqrt(1 + x²)))，两次减半后 |x| < 0.2，级数收敛较快
+    let mut x = x;
+    for _ in 0..2 {
+        let root = MathematicalOps::sqrt(&(Decimal::ONE + x * x)).ok_or(CalcError::Overflow)?;
+        x /= Decimal::ONE + root;
+    }
+    let square = x * x;
+    let mut power = x;
+    let mut sum = Decimal::ZERO;
+    let mut k = 1u32;
+    while !power.is_zero() {
+        let term = power / Decimal::from(k);
+        if term.is_zero() {
+            break;
+        }
+        sum = if k % 4 == 1 { sum + term } else { sum - term };
+        power *= square;
+        k += 2;
+    }
+    Ok(sum * Decimal::from(4))
+}
+
+/// Computes `(sin(x), cos(x))` with Taylor series after reducing `x` to
+/// `[-π, π]`.
+fn sin_cos(x: Decimal) -> CalcResult<(Decimal, Decimal)> {
+    let turns = (x / Decimal::TWO_PI).round();
+    let x = turns
+        .checked_mul(Decimal::TWO_PI)
+        .and_then(|whole| x.checked_sub(whole))
+        .ok_or(CalcError::Overflow)?;
+    // 正弦取奇数项，余弦取偶数项
+    let (mut sin, mut cos) = (Decimal::ZERO, Decimal::ZERO);
+    let mut term = Decimal::ONE;
+    let mut n = 0u32;
+    while !term.is_zero() {
+        match n % 4 {
+            0 => cos += term,
+            1 => sin += term,
+            2 => cos -= term,
+            _ => sin -= term,
+        }
+        n += 1;
+        term = term * x / Decimal::from(n);
+    }
+    Ok((sin, cos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn c(re: Decimal, im: Decimal) -> Complex {
+        Complex::new(re, im)
+    }
+
+    /// Tests the arithmetic, conjugate, modulus and square root.
+    #[test]
+    fn test_complex_arithmetic() {
+        let z = c(dec!(3), dec!(4));
+        let w = c(dec!(1), dec!(-2));
+        assert_eq!(z.add(&w), Ok(c(dec!(4), dec!(2))));
+        assert_eq!(z.sub(&w), Ok(c(dec!(2), dec!(6))));
+        assert_eq!(z.mul(&w), Ok(c(dec!(11), dec!(-2))));
+        assert_eq!(z.div(&w), Ok(c(dec!(-1), dec!(2))));
+        assert_eq!(z.conj(), Ok(c(dec!(3), dec!(-4))));
+        assert_eq!(z.abs(), Ok(dec!(5)));
+        assert_eq!(c(dec!(-3), dec!(-4)).sqrt(), Ok(c(dec!(1), dec!(-2))));
+        assert_eq!(c(dec!(0), dec!(2)).sqrt(), Ok(c(dec!(1), dec!(1))));
+        assert_eq!(c(dec!(-9), dec!(0)).sqrt(), Ok(c(dec!(0), dec!(3))));
+        assert_eq!(z.div(&c(dec!(0), dec!(0))), Err(CalcError::DivideByZero));
+    }
+
+    /// Tests the argument in every quadrant and on the axes.
+    #[test]
+    fn test_complex_arg() {
+        let quarter = Decimal::QUARTER_PI;
+        for (z, expected) in [
+            (c(dec!(1), dec!(1)), quarter),
+            (c(dec!(-1), dec!(1)), quarter * dec!(3)),
+            (c(dec!(-1), dec!(-1)), -quarter * dec!(3)),
+            (c(dec!(1), dec!(-1)), -quarter),
+            (c(dec!(0), dec!(2)), Decimal::HALF_PI),
+            (c(dec!(-2), dec!(0)), Decimal::PI),
+            (c(dec!(0), dec!(0)), Decimal::ZERO),
+        ] {
+            assert_eq!(z.arg().unwrap().round_dp(26), expected.round_dp(26), "{z}");
+        }
+        let angle = c(dec!(1), MathematicalOps::sqrt(&dec!(3)).unwrap())
+            .arg()
+            .unwrap();
+        assert_eq!(angle.round_dp(26), (Decimal::PI / dec!(3)).round_dp(26));
+    }
+
+    /// Tests integer, half-integer and general complex powers.
+    #[test]
+    fn test_complex_pow() {
+        let i = c(dec!(0), dec!(1));
+        assert_eq!(i.pow(&c(dec!(2), dec!(0))), Ok(c(dec!(-1), dec!(0))));
+        assert_eq!(
+            c(dec!(1), dec!(1)).pow(&c(dec!(-2), dec!(0))),
+            Ok(c(dec!(0), dec!(-0.5)))
+        );
+        assert_eq!(
+            c(dec!(-8), dec!(0)).pow(&c(dec!(0.5), dec!(0))).unwrap().re,
+            dec!(0)
+        );
+        // i^i = e^(-π/2)
+        let power = i.pow(&i).unwrap();
+        assert_eq!(power.re.round_dp(20), dec!(0.20787957635076190855));
+        assert_eq!(power.im.round_dp(20), dec!(0));
+        // (-8)^(1/3) = 1 + √3·i
+        let root = c(dec!(-8), dec!(0))
+            .pow(&c(dec!(1) / dec!(3), dec!(0)))
+            .unwrap();
+        assert_eq!(root.re.round_dp(20), dec!(1));
+        let sqrt_3 = MathematicalOps::sqrt(&dec!(3)).unwrap();
+        assert_eq!(root.im.round_dp(20), sqrt_3.round_dp(20));
+        assert_eq!(
+            c(dec!(0), dec!(0)).pow(&c(dec!(-1), dec!(0))),
+            Err(CalcError::DivideByZero)
+        );
+    }
+
+    /// Tests the rectangular display of complex numbers.
+    #[test]
+    fn test_complex_display() {
+        assert_eq!(c(dec!(3), dec!(4)).to_string(), "3 + 4i");
+        assert_eq!(c(dec!(3), dec!(-4.5)).to_string(), "3 - 4.5i");
+        assert_eq!(c(dec!(0), dec!(-4)).to_string(), "-4i");
+        assert_eq!(c(dec!(2), dec!(0)).to_string(), "2 + 0i");
+    }
+}
@@ -0,0 +1,288 @@
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::limits::Limits;
+use crate::calc::numeric::Numeric;
+use crate::calc::sandbox::Sandbox;
+use rust_decimal::RoundingStrategy;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How a value is rounded to a given scale.
+///
+/// The names follow `java.math.RoundingMode`, which is how they are written
+/// in `round<x, 2, "HALF_EVEN">`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    // 四舍五入
+    #[default]
+    HalfUp,
+    // 银行家舍入
+    HalfEven,
+    // 向零舍入
+    Down,
+    // 远离零舍入
+    Up,
+    // 向正无穷舍入
+    Ceiling,
+    // 向负无穷舍入
+    Floor,
+}
+
+impl RoundingMode {
+    /// Returns the matching `rust_decimal` strategy.
+    pub fn to_strategy(&self) -> RoundingStrategy {
+        match self {
+            Self::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Self::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Self::Down => RoundingStrategy::ToZero,
+            Self::Up => RoundingStrategy::AwayFromZero,
+            Self::Ceiling => RoundingStrategy::ToPositiveInfinity,
+            Self::Floor => RoundingStrategy::ToNegativeInfinity,
+        }
+    }
+
+    /// Decides whether a quotient truncated toward zero has to be moved one
+    /// unit away from zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `negative` - Whether the exact value is negative
+    /// * `remainder` - How twice the dropped remainder compares to the divisor,
+    ///   i.e. whether the dropped part is below, at or above one half
+    /// * `exact` - Whether the dropped remainder is zero
+    /// * `odd` - Whether the truncated quotient is odd
+    pub(crate) fn rounds_away(
+        &self,
+        negative: bool,
+        remainder: Ordering,
+        exact: bool,
+        odd: bool,
+    ) -> bool {
+        if exact {
+            return false;
+        }
+        match self {
+            Self::Down => false,
+            Self::Up => true,
+            Self::Ceiling => !negative,
+            Self::Floor => negative,
+            Self::HalfUp => remainder != Ordering::Less,
+            Self::HalfEven => {
+                remainder == Ordering::Greater || (remainder == Ordering::Equal && odd)
+            }
+        }
+    }
+}
+
+impl FromStr for RoundingMode {
+    type Err = CalcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "HALF_UP" => Ok(Self::HalfUp),
+            "HALF_EVEN" => Ok(Self::HalfEven),
+            "DOWN" => Ok(Self::Down),
+            "UP" => Ok(Self::Up),
+            "CEILING" => Ok(Self::Ceiling),
+            "FLOOR" => Ok(Self::Floor),
+            _ => Err(CalcError::InvalidArgument {
+                function: "round".to_string(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Display for RoundingMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::HalfUp => "HALF_UP",
+            Self::HalfEven => "HALF_EVEN",
+            Self::Down => "DOWN",
+            Self::Up => "UP",
+            Self::Ceiling => "CEILING",
+            Self::Floor => "FLOOR",
+        })
+    }
+}
+
+/// When the context's rounding is applied during evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingScope {
+    // 每一步运算后都舍入
+    EachOperation,
+    // 只对最终结果舍入
+    #[default]
+    FinalResult,
+}
+
+/// What happens when an operation overflows, underflows or divides by zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArithmeticPolicy {
+    // 报错，错误中带有运算符的位置
+    #[default]
+    Error,
+    // 取最接近的可表示值：溢出取最大值，下溢取 0
+    Saturate,
+    // 结果为空值
+    Null,
+    // 结果为错误值，例如 #DIV/0!，可被 iferror 捕获
+    ErrorValue,
+}
+
+/// Settings that control how an expression is evaluated.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::context::{Context, RoundingMode};
+/// use rust_decimal::dec;
+///
+/// let context = Context {
+///     scale: Some(2),
+///     rounding_mode: RoundingMode::HalfEven,
+///     ..Context::default()
+/// };
+/// assert_eq!(context.round(dec!(2.345)), Ok(dec!(2.34)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Context {
+    /// The number of decimal places results are rounded to; `None` disables rounding
+    pub scale: Option<u32>,
+    /// The rounding used for the context scale and as the default of `round`
+    pub rounding_mode: RoundingMode,
+    /// Whether every operation or only the final result is rounded
+    pub rounding_scope: RoundingScope,
+    /// The policy for results too large for the numeric backend
+    pub overflow_policy: ArithmeticPolicy,
+    /// The policy for non-zero results too small for the numeric backend
+    pub underflow_policy: ArithmeticPolicy,
+    /// The policy for divisions by zero
+    pub divide_by_zero_policy: ArithmeticPolicy,
+    /// Whether other evaluation errors, such as a missing variable or a type
+    /// mismatch, become error values like `#N/A` instead of failing the
+    /// evaluation
+    pub error_values: bool,
+    /// Whether operations without a real result, such as `sqrt<-4>` or
+    /// `(-8) ^ 0.5`, return complex numbers instead of failing with
+    /// `CalcError::ComplexResult`
+    pub complex_numbers: bool,
+    /// The step budget and deadline of an evaluation
+    pub limits: Limits,
+    /// The functions and variables the expression may use
+    pub sandbox: Sandbox,
+}
+
+impl Context {
+    /// Returns the policy that applies to an evaluation error, or `None` if
+    /// the error is not an overflow, underflow or division by zero.
+    pub fn policy_for(&self, error: &CalcError) -> Option<ArithmeticPolicy> {
+        match error.without_span() {
+            CalcError::Overflow => Some(self.overflow_policy),
+            CalcError::Underflow => Some(self.underflow_policy),
+            CalcError::DivideByZero => Some(self.divide_by_zero_policy),
+            _ => None,
+        }
+    }
+
+    /// Rounds a value to the context scale, if one is set.
+    pub fn round<N: Numeric>(&self, value: N) -> CalcResult<N> {
+        match self.scale {
+            Some(scale) => value.round(scale, self.rounding_mode),
+            None => Ok(value),
+        }
+    }
+
+    /// Rounds the result of a single operation when the scope is
+    /// `RoundingScope::EachOperation`.
+    pub fn round_operation<N: Numeric>(&self, value: N) -> CalcResult<N> {
+        match self.rounding_scope {
+            RoundingScope::EachOperation => self.round(value),
+            RoundingScope::FinalResult => Ok(value),
+        }
+    }
+
+    /// Rounds the final result when the scope is `RoundingScope::FinalResult`.
+    pub fn round_result<N: Numeric>(&self, value: N) -> CalcResult<N> {
+        match self.rounding_scope {
+            RoundingScope::EachOperation => Ok(value),
+            RoundingScope::FinalResult => self.round(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::big::BigDecimal;
+    use crate::calc::numeric::Rational;
+    use rust_decimal::dec;
+
+    /// Tests every rounding mode on positive and negative midpoints and
+    /// checks that all backends agree with `rust_decimal`.
+    #[test]
+    fn test_rounding_modes() {
+        let cases = [
+            (RoundingMode::HalfUp, [dec!(2.35), dec!(-2.35), dec!(2.34)]),
+            (
+                RoundingMode::HalfEven,
+                [dec!(2.34), dec!(-2.34), dec!(2.34)],
+            ),
+            (RoundingMode::Down, [dec!(2.34), dec!(-2.34), dec!(2.34)]),
+            (RoundingMode::Up, [dec!(2.35), dec!(-2.35), dec!(2.35)]),
+            (RoundingMode::Ceiling, [dec!(2.35), dec!(-2.34), dec!(2.35)]),
+            (RoundingMode::Floor, [dec!(2.34), dec!(-2.35), dec!(2.34)]),
+        ];
+        for (mode, expected) in cases {
+            for (input, expected) in [dec!(2.345), dec!(-2.345), dec!(2.3401)]
+                .into_iter()
+                .zip(expected)
+            {
+                assert_eq!(
+                    Numeric::round(&input, 2, mode),
+                    Ok(expected),
+                    "{mode} {input}"
+                );
+                let rational = Rational::from_decimal(input).unwrap();
+                assert_eq!(
+                    rational.round(2, mode),
+                    Rational::from_decimal(expected),
+                    "{mode} {input}"
+                );
+                let big = BigDecimal::<100>::from_decimal(input).unwrap();
+                assert_eq!(
+                    big.round(2, mode),
+                    BigDecimal::from_decimal(expected),
+                    "{mode} {input}"
+                );
+            }
+        }
+        assert_eq!(Numeric::round(&1.25f64, 1, RoundingMode::HalfEven), Ok(1.2));
+    }
+
+    /// Tests parsing rounding mode names.
+    #[test]
+    fn test_rounding_mode_from_str() {
+        assert_eq!("half_even".parse(), Ok(RoundingMode::HalfEven));
+        assert_eq!(
+            RoundingMode::Ceiling.to_string().parse(),
+            Ok(RoundingMode::Ceiling)
+        );
+        assert!("NEAREST".parse::<RoundingMode>().is_err());
+    }
+
+    /// Tests that the context only rounds when a scale is set.
+    #[test]
+    fn test_context_round() {
+        let context = Context::default();
+        assert_eq!(context.round(dec!(1.23456)), Ok(dec!(1.23456)));
+        let context = Context {
+            scale: Some(0),
+            rounding_mode: RoundingMode::Floor,
+            rounding_scope: RoundingScope::EachOperation,
+            ..Context::default()
+        };
+        assert_eq!(context.round_operation(dec!(-0.5)), Ok(dec!(-1)));
+        assert_eq!(context.round_result(dec!(-0.5)), Ok(dec!(-0.5)));
+    }
+}
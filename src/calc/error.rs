@@ -1,10 +1,117 @@
+use crate::calc::message::message_locale;
+use crate::calc::span::Span;
+use crate::calc::value::{ErrorKind, ValueType};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
 pub type CalcResult<T> = Result<T, CalcError>;
 
+/// An error from parsing or evaluating an expression.
+///
+/// Every error has a stable code, see [`code`](CalcError::code). `Display`
+/// writes the message in the language chosen with
+/// [`set_message_locale`](crate::calc::message::set_message_locale), Chinese
+/// by default.
 #[allow(unused)]
-#[derive(Debug, PartialEq, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CalcError {
-    #[error("非法字符: {0}")]
     UnexpectedChar(char),
-    #[error("无效运算符: {0}")]
-    InvalidOperator(String)
-}
\ No newline at end of file
+    InvalidOperator(String),
+    UnexpectedToken(String),
+    MissingOperator {
+        token: String,
+        span: Span,
+    },
+    UnclosedFunction(String),
+    UndefinedVariable(String),
+    UnknownFunction(String),
+    ArgumentCount {
+        function: String,
+        expected: String,
+        actual: usize,
+    },
+    InvalidNumber(String),
+    InexactResult(String),
+    InvalidArgument {
+        function: String,
+        value: String,
+    },
+    DivideByZero,
+    Overflow,
+    Underflow,
+    UndefinedCell(String),
+    TypeMismatch {
+        expected: ValueType,
+        actual: ValueType,
+    },
+    CircularReference(Vec<String>),
+    At {
+        error: Box<CalcError>,
+        span: Span,
+    },
+    ErrorValue {
+        kind: ErrorKind,
+        span: Span,
+    },
+    InputTooLong {
+        length: usize,
+        limit: usize,
+    },
+    TooManyTokens {
+        count: usize,
+        limit: usize,
+    },
+    NestingTooDeep(usize),
+    StepLimitExceeded(usize),
+    Timeout(Duration),
+    Forbidden {
+        name: String,
+        span: Span,
+    },
+    ComplexResult(String),
+    DimensionMismatch {
+        expected: String,
+        actual: String,
+    },
+    SingularMatrix,
+}
+
+impl CalcError {
+    /// Attaches the span of the operator or function that failed.
+    pub fn at(self, span: Span) -> Self {
+        match self {
+            Self::At { .. } => self,
+            error => Self::At {
+                error: Box::new(error),
+                span,
+            },
+        }
+    }
+
+    /// Returns the span the error points at, if it has one.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::At { span, .. }
+            | Self::ErrorValue { span, .. }
+            | Self::Forbidden { span, .. }
+            | Self::MissingOperator { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its span.
+    pub fn without_span(&self) -> &CalcError {
+        match self {
+            Self::At { error, .. } => error,
+            error => error,
+        }
+    }
+}
+
+impl Display for CalcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message(message_locale()))
+    }
+}
+
+impl std::error::Error for CalcError {}
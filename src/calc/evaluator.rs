@@ -0,0 +1,504 @@
+use crate::calc::ast::{Node, PostfixOp};
+use crate::calc::cell::{self, CellProvider, CellRef};
+use crate::calc::complex::Complex;
+use crate::calc::context::{ArithmeticPolicy, Context};
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::function;
+use crate::calc::matrix::Matrix;
+use crate::calc::numeric::Numeric;
+use crate::calc::span::Span;
+use crate::calc::value::{ErrorKind, Value, ValueType};
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+/// The nodes evaluated so far, identified by their address in the tree,
+/// with their values.
+pub(crate) type Steps<N> = Vec<(*const Node, Value<N>)>;
+
+/// Walks an expression tree and computes its value.
+///
+/// The evaluator holds everything that stays the same during one evaluation:
+/// the variables, the cell provider and the evaluation context. It also
+/// counts the evaluated nodes against the context's step budget and deadline.
+pub(crate) struct Evaluator<'a, N> {
+    variables: &'a HashMap<String, N>,
+    cells: &'a dyn CellProvider,
+    context: &'a Context,
+    // 记录求值步骤时，按求值顺序保存每个节点及其值
+    steps: Option<RefCell<Steps<N>>>,
+    // 已求值的节点数
+    evaluated: Cell<usize>,
+    deadline: Option<Instant>,
+}
+
+impl<'a, N: Numeric> Evaluator<'a, N> {
+    pub(crate) fn new(
+        variables: &'a HashMap<String, N>,
+        cells: &'a dyn CellProvider,
+        context: &'a Context,
+    ) -> Self {
+        Self {
+            variables,
+            cells,
+            context,
+            steps: None,
+            evaluated: Cell::new(0),
+            // 超时时间过大无法表示时视为不限时
+            deadline: context
+                .limits
+                .timeout
+                .and_then(|timeout| Instant::now().checked_add(timeout)),
+        }
+    }
+
+    /// Makes the evaluator record the value of every reference, operator and
+    /// function call it evaluates.
+    pub(crate) fn with_steps(mut self) -> Self {
+        self.steps = Some(RefCell::new(Vec::new()));
+        self
+    }
+
+    /// Returns the recorded steps in evaluation order. Nodes are identified
+    /// by their address in the evaluated tree.
+    pub(crate) fn into_steps(self) -> Steps<N> {
+        self.steps.map(RefCell::into_inner).unwrap_or_default()
+    }
+
+    /// Evaluates a whole expression to a number, applying the context's
+    /// final rounding.
+    pub(crate) fn eval_number(&self, node: &Node) -> CalcResult<N> {
+        self.eval_value(node)?.into_number()
+    }
+
+    /// Evaluates a whole expression, applying the context's final rounding
+    /// to a numeric result.
+    pub(crate) fn eval_value(&self, node: &Node) -> CalcResult<Value<N>> {
+        match self.eval(node)? {
+            Value::Number(n) => Ok(Value::Number(self.context.round_result(n)?)),
+            Value::Complex(z) => Ok(Value::from_complex(
+                z.try_map(|n| self.context.round_result(n))?,
+            )),
+            Value::Matrix(m) => Ok(Value::Matrix(
+                m.map(|n| self.context.round_result(n.clone()))?,
+            )),
+            other => Ok(other),
+        }
+    }
+
+    /// Evaluates a node, recording its value as a step unless it is a literal.
+    ///
+    /// An operator chain such as `1 + 2 + ... + n` is evaluated with a loop
+    /// over its left operands, so the recursion only grows with the nesting
+    /// of brackets, function calls and right operands.
+    pub(crate) fn eval(&self, node: &Node) -> CalcResult<Value<N>> {
+        let mut chain = Vec::new();
+        let mut operand = node;
+        // 由外向内计数并检查沙箱，未经检查的表达式同样受沙箱限制
+        let mut result = loop {
+            self.check_limits()?;
+            if let Err(error) = self.context.sandbox.check_node(operand) {
+                break Err(error);
+            }
+            match operand.chain_operand() {
+                Some(next) => {
+                    chain.push(operand);
+                    operand = next;
+                }
+                None => break self.eval_node(operand),
+            }
+        };
+        result = self.finish(operand, result);
+        for link in chain.into_iter().rev() {
+            result = result.and_then(|lhs| self.eval_operator(link, lhs));
+            result = self.finish(link, result);
+        }
+        result
+    }
+
+    /// Finishes the evaluation of a node: turns an error into an error value
+    /// at the node when the context asks for error values, and records the
+    /// value as a step unless the node is a literal.
+    ///
+    /// Overflow, underflow and division by zero are left alone, since they
+    /// follow their own arithmetic policies.
+    fn finish(&self, node: &Node, result: CalcResult<Value<N>>) -> CalcResult<Value<N>> {
+        let result = match result {
+            Err(error)
+                if self.context.error_values && self.context.policy_for(&error).is_none() =>
+            {
+                match (ErrorKind::from_error(&error), error.span().or(node.span())) {
+                    (Some(kind), Some(span)) => Ok(Value::Error(kind, span)),
+                    _ => Err(error),
+                }
+            }
+            result => result,
+        };
+        if let (Some(steps), Ok(value), Some(_)) = (&self.steps, &result, node.span()) {
+            steps.borrow_mut().push((node, value.clone()));
+        }
+        result
+    }
+
+    /// Evaluates a node that has already been counted and checked against
+    /// the sandbox.
+    fn eval_node(&self, node: &Node) -> CalcResult<Value<N>> {
+        match node {
+            Node::Number(n) => Ok(Value::Number(N::from_decimal(*n)?)),
+            Node::BigNumber(literal) => Ok(Value::Number(N::from_literal(literal)?)),
+            Node::Imaginary(n) => Ok(Value::from_complex(Complex::new(
+                N::zero(),
+                N::from_decimal(*n)?,
+            ))),
+            Node::Text(text) => Ok(Value::Text(text.clone())),
+            Node::Variable(name, _) => self
+                .variables
+                .get(name)
+                .map(|value| Value::Number(value.clone()))
+                .ok_or_else(|| CalcError::UndefinedVariable(name.clone())),
+            // 空单元格按 0 计算
+            Node::Cell(cell, _) => match self.cells.get_cell(cell)? {
+                Some(value) => Ok(Value::Number(N::from_decimal(value)?)),
+                None => Ok(Value::Number(N::zero())),
+            },
+            Node::Range(from, to, _) => {
+                self.charge_range(from, to)?;
+                self.cells
+                    .get_range(from, to, &|| self.check_deadline())?
+                    .into_iter()
+                    .map(N::from_decimal)
+                    .collect::<CalcResult<Vec<_>>>()
+                    .map(Value::Range)
+            }
+            Node::Matrix { rows, span } => {
+                let mut values = Vec::with_capacity(rows.len());
+                for row in rows {
+                    let mut elements = Vec::with_capacity(row.len());
+                    for element in row {
+                        match operand(self.eval(element)?)? {
+                            ControlFlow::Continue(n) => elements.push(n),
+                            ControlFlow::Break(value) => return Ok(value),
+                        }
+                    }
+                    values.push(elements);
+                }
+                Matrix::from_rows(values)
+                    .map(Value::Matrix)
+                    .map_err(|error| error.at(*span))
+            }
+            Node::Negative { expr, span } => {
+                let value = match classify(self.eval(expr)?)? {
+                    ControlFlow::Continue(Operand::Real(value)) => value,
+                    ControlFlow::Continue(Operand::Complex(z)) => {
+                        return self.resolve(
+                            z.neg().map(Value::from_complex),
+                            Ordering::Equal,
+                            *span,
+                        );
+                    }
+                    ControlFlow::Continue(Operand::Matrix(m)) => {
+                        return self.resolve(
+                            m.map(N::neg).map(Value::Matrix),
+                            Ordering::Equal,
+                            *span,
+                        );
+                    }
+                    ControlFlow::Break(value) => return Ok(value),
+                };
+                let sign = value
+                    .partial_cmp(&N::zero())
+                    .unwrap_or(Ordering::Equal)
+                    .reverse();
+                self.resolve(value.neg().map(Value::Number), sign, *span)
+            }
+            Node::Postfix { .. } | Node::Binary { .. } => {
+                unreachable!("operator chains are unwound by eval")
+            }
+            Node::Function {
+                function_prefix,
+                args,
+                span,
+            } => {
+                // nvl 需要惰性求值：第一个参数缺失或为空值时才使用默认值
+                if function_prefix == function::NVL {
+                    function::check_arg_count(function_prefix, args.len())?;
+                    return match self.eval(&args[0]) {
+                        Err(CalcError::UndefinedVariable(_))
+                        | Ok(Value::Null)
+                        | Ok(Value::Error(ErrorKind::Na, _)) => self.eval(&args[1]),
+                        result => result,
+                    };
+                }
+                // iferror、iserror 和 errortype 同样惰性求值，出错时不中断求值
+                if function_prefix == function::IFERROR {
+                    function::check_arg_count(function_prefix, args.len())?;
+                    return match self.eval_catching(&args[0], *span)? {
+                        Value::Error(..) => self.eval(&args[1]),
+                        value => Ok(value),
+                    };
+                }
+                if function_prefix == function::ISERROR {
+                    function::check_arg_count(function_prefix, args.len())?;
+                    let value = self.eval_catching(&args[0], *span)?;
+                    return function::call(function_prefix, &[value], self.context);
+                }
+                if function_prefix == function::ERRORTYPE {
+                    function::check_arg_count(function_prefix, args.len())?;
+                    return match self.eval_catching(&args[0], *span)? {
+                        value @ Value::Error(..) => {
+                            function::call(function_prefix, &[value], self.context)
+                        }
+                        // 与 Excel 的 ERROR.TYPE 一致，参数不是错误值时结果为 #N/A
+                        _ => Ok(Value::Error(ErrorKind::Na, *span)),
+                    };
+                }
+                let values = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<CalcResult<Vec<_>>>()?;
+                // 空值和错误值沿表达式向外传递
+                if let Some(value) = values
+                    .iter()
+                    .find(|value| matches!(value, Value::Null | Value::Error(..)))
+                {
+                    return Ok(value.clone());
+                }
+                // 只有参数全为负数时，溢出才饱和到负的最大值
+                let negative = values.iter().all(|value| match value {
+                    Value::Number(n) => *n < N::zero(),
+                    _ => true,
+                }) && values.iter().any(|value| matches!(value, Value::Number(_)));
+                let sign = if negative {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                self.resolve(
+                    function::call(function_prefix, &values, self.context),
+                    sign,
+                    *span,
+                )
+            }
+        }
+    }
+
+    /// Applies a binary or postfix operator to its evaluated left operand,
+    /// evaluating the right operand of a binary operator.
+    fn eval_operator(&self, node: &Node, lhs: Value<N>) -> CalcResult<Value<N>> {
+        match node {
+            Node::Postfix { op, span, .. } => {
+                let value = match operand(lhs)? {
+                    ControlFlow::Continue(value) => value,
+                    ControlFlow::Break(value) => return Ok(value),
+                };
+                let sign = match op {
+                    PostfixOp::Factorial => Ordering::Greater,
+                    PostfixOp::Percent => value.partial_cmp(&N::zero()).unwrap_or(Ordering::Equal),
+                };
+                self.resolve(op.apply(value).map(Value::Number), sign, *span)
+            }
+            Node::Binary { op, rhs, span, .. } => {
+                let lhs = match classify(lhs)? {
+                    ControlFlow::Continue(lhs) => lhs,
+                    ControlFlow::Break(value) => return Ok(value),
+                };
+                let rhs = match classify(self.eval(rhs)?)? {
+                    ControlFlow::Continue(rhs) => rhs,
+                    ControlFlow::Break(value) => return Ok(value),
+                };
+                let (lhs, rhs) = match (lhs, rhs) {
+                    (Operand::Real(lhs), Operand::Real(rhs)) => (lhs, rhs),
+                    (lhs @ Operand::Matrix(_), rhs) | (lhs, rhs @ Operand::Matrix(_)) => {
+                        let result = op.apply_matrix(lhs.into_value(), rhs.into_value());
+                        return self.resolve(result.map(Value::Matrix), Ordering::Greater, *span);
+                    }
+                    (lhs, rhs) => {
+                        let result = op.apply_complex(lhs.into_complex()?, rhs.into_complex()?);
+                        return self.resolve(
+                            result.map(Value::from_complex),
+                            Ordering::Greater,
+                            *span,
+                        );
+                    }
+                };
+                let sign = op.result_sign(&lhs, &rhs);
+                // 开启复数时，没有实数结果的运算（例如负数的分数次幂）改用复数计算
+                let fallback = self
+                    .context
+                    .complex_numbers
+                    .then(|| (lhs.clone(), rhs.clone()));
+                let result = match (op.apply(lhs, rhs), fallback) {
+                    (Err(CalcError::ComplexResult(_)), Some((lhs, rhs))) => op
+                        .apply_complex(Complex::from_real(lhs), Complex::from_real(rhs))
+                        .map(Value::from_complex),
+                    (result, _) => result.map(Value::Number),
+                };
+                self.resolve(result, sign, *span)
+            }
+            _ => unreachable!("only operators are collected"),
+        }
+    }
+
+    /// Counts one more evaluated node and checks the step budget and deadline.
+    ///
+    /// These errors have no error value kind, so neither `iferror` nor the
+    /// context's error values can hide them.
+    fn check_limits(&self) -> CalcResult<()> {
+        let evaluated = self.evaluated.get() + 1;
+        self.evaluated.set(evaluated);
+        let limits = &self.context.limits;
+        if let Some(limit) = limits.max_steps.filter(|limit| evaluated > *limit) {
+            return Err(CalcError::StepLimitExceeded(limit));
+        }
+        self.check_deadline()
+    }
+
+    fn check_deadline(&self) -> CalcResult<()> {
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.context.limits.timeout)
+            && Instant::now() >= deadline
+        {
+            return Err(CalcError::Timeout(timeout));
+        }
+        Ok(())
+    }
+
+    /// Counts every cell of a range as an evaluation step before the range
+    /// is expanded, so a huge range fails at once instead of being read.
+    fn charge_range(&self, from: &CellRef, to: &CellRef) -> CalcResult<()> {
+        let size = usize::try_from(cell::range_size(from, to)).unwrap_or(usize::MAX);
+        let evaluated = self.evaluated.get().saturating_add(size);
+        self.evaluated.set(evaluated);
+        match self.context.limits.max_steps {
+            Some(limit) if evaluated > limit => Err(CalcError::StepLimitExceeded(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Evaluates an argument of `iferror`, `iserror` or `errortype`, turning
+    /// every error that has an error value kind into that error value.
+    ///
+    /// An error without a span of its own points at the argument, or at the
+    /// function name `span` if the argument is a literal.
+    fn eval_catching(&self, node: &Node, span: Span) -> CalcResult<Value<N>> {
+        match self.eval(node) {
+            Err(error) => match ErrorKind::from_error(&error) {
+                Some(kind) => Ok(Value::Error(
+                    kind,
+                    error.span().or(node.span()).unwrap_or(span),
+                )),
+                None => Err(error),
+            },
+            result => result,
+        }
+    }
+
+    /// Applies the context's rounding to the result of one operation, and its
+    /// arithmetic policy to an overflow, underflow or division by zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result of the operation
+    /// * `sign` - The sign of the exact result, used when saturating
+    /// * `span` - The span of the operator or function name
+    fn resolve(
+        &self,
+        result: CalcResult<Value<N>>,
+        sign: Ordering,
+        span: Span,
+    ) -> CalcResult<Value<N>> {
+        let error = match result {
+            Ok(Value::Number(n)) => return Ok(Value::Number(self.context.round_operation(n)?)),
+            Ok(Value::Complex(z)) => {
+                let z = z.try_map(|n| self.context.round_operation(n))?;
+                return Ok(Value::from_complex(z));
+            }
+            Ok(Value::Matrix(m)) => {
+                let m = m.map(|n| self.context.round_operation(n.clone()))?;
+                return Ok(Value::Matrix(m));
+            }
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        let Some(policy) = self.context.policy_for(&error) else {
+            // 没有实数结果、参数无效、维度错误和不可逆矩阵同样指向出错的运算符或函数
+            return Err(match error {
+                CalcError::ComplexResult(_)
+                | CalcError::InvalidArgument { .. }
+                | CalcError::DimensionMismatch { .. }
+                | CalcError::SingularMatrix => error.at(span),
+                error => error,
+            });
+        };
+        match policy {
+            ArithmeticPolicy::Error => Err(error.at(span)),
+            ArithmeticPolicy::Saturate => {
+                let value = match (error.without_span(), sign) {
+                    (CalcError::Underflow, _) | (_, Ordering::Equal) => N::zero(),
+                    (_, Ordering::Greater) => N::max_value(),
+                    (_, Ordering::Less) => N::max_value().neg()?,
+                };
+                Ok(Value::Number(value))
+            }
+            ArithmeticPolicy::Null => Ok(Value::Null),
+            ArithmeticPolicy::ErrorValue => match ErrorKind::from_error(&error) {
+                Some(kind) => Ok(Value::Error(kind, span)),
+                None => Err(error.at(span)),
+            },
+        }
+    }
+}
+
+/// A number or matrix taken out of an operand of a negation or binary
+/// operator.
+enum Operand<N> {
+    Real(N),
+    Complex(Complex<N>),
+    Matrix(Matrix<N>),
+}
+
+impl<N: Numeric> Operand<N> {
+    /// 矩阵不能作为复数参与运算
+    fn into_complex(self) -> CalcResult<Complex<N>> {
+        match self {
+            Self::Real(n) => Ok(Complex::from_real(n)),
+            Self::Complex(z) => Ok(z),
+            Self::Matrix(_) => Err(CalcError::TypeMismatch {
+                expected: ValueType::Complex,
+                actual: ValueType::Matrix,
+            }),
+        }
+    }
+
+    fn into_value(self) -> Value<N> {
+        match self {
+            Self::Real(n) => Value::Number(n),
+            Self::Complex(z) => Value::Complex(z),
+            Self::Matrix(m) => Value::Matrix(m),
+        }
+    }
+}
+
+/// Takes the real number, complex number or matrix out of an operand, or
+/// breaks with a null or error value that the operation passes on
+/// unchanged.
+fn classify<N: Numeric>(value: Value<N>) -> CalcResult<ControlFlow<Value<N>, Operand<N>>> {
+    match value {
+        Value::Null | Value::Error(..) => Ok(ControlFlow::Break(value)),
+        Value::Complex(z) => Ok(ControlFlow::Continue(Operand::Complex(z))),
+        Value::Matrix(m) => Ok(ControlFlow::Continue(Operand::Matrix(m))),
+        value => value
+            .into_number()
+            .map(|n| ControlFlow::Continue(Operand::Real(n))),
+    }
+}
+
+/// Takes the number out of an operand, or breaks with a null or error value
+/// that the operation passes on unchanged.
+fn operand<N: Numeric>(value: Value<N>) -> CalcResult<ControlFlow<Value<N>, N>> {
+    match value {
+        Value::Null | Value::Error(..) => Ok(ControlFlow::Break(value)),
+        value => value.into_number().map(ControlFlow::Continue),
+    }
+}
@@ -0,0 +1,754 @@
+use crate::calc::complex::Complex;
+use crate::calc::context::RoundingMode;
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::locale::Locale;
+use crate::calc::numeric::Numeric;
+use crate::calc::value::Value;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// The characters that separate the integer part from the fraction and the
+/// groups of thousands from each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Separators {
+    /// The decimal separator, `.` in English and `,` in German
+    pub decimal: char,
+    /// The grouping separator, `,` in English and `.` in German
+    pub group: char,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self {
+            decimal: '.',
+            group: ',',
+        }
+    }
+}
+
+impl From<Locale> for Separators {
+    fn from(locale: Locale) -> Self {
+        match locale {
+            Locale::EnUs | Locale::ZhCn => Self::default(),
+        }
+    }
+}
+
+impl FromStr for Separators {
+    type Err = CalcError;
+
+    /// Parses a language tag such as `de-DE`, `fr` or `zh-CN`. The region
+    /// only matters where it changes the convention, as for `de-CH`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tag = s.to_ascii_lowercase().replace('_', "-");
+        let mut parts = tag.split('-');
+        let language = parts.next().unwrap_or_default();
+        let region = parts.next().unwrap_or_default();
+        let (group, decimal) = match (language, region) {
+            ("de" | "it", "ch") => ('\u{2019}', '.'),
+            ("en" | "zh" | "ja" | "ko" | "th" | "he", _) => (',', '.'),
+            ("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el", _) => ('.', ','),
+            ("fr" | "ru" | "uk" | "pl" | "cs" | "sv" | "fi" | "nb", _) => ('\u{202F}', ','),
+            _ => {
+                return Err(CalcError::InvalidArgument {
+                    function: "locale".to_string(),
+                    value: s.to_string(),
+                });
+            }
+        };
+        Ok(Self { decimal, group })
+    }
+}
+
+/// How the magnitude of a number is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    // 普通写法，例如 1234.5
+    #[default]
+    Standard,
+    // 科学计数法，例如 1.2345E3
+    Scientific,
+    // 工程计数法，指数为 3 的倍数，例如 1.2345E3、12.345E3
+    Engineering,
+    // 整数按 2 到 36 进制书写，例如 0xFF、0b1010
+    Radix(u32),
+}
+
+/// The number of decimals the modulus and angle of a complex number in polar
+/// form are rounded to before formatting.
+const POLAR_SCALE: u32 = 20;
+
+/// Returns the literal prefix of a radix: `0x`, `0b` or `0o` for
+/// hexadecimal, binary and octal, and nothing for any other radix.
+pub(crate) fn radix_prefix(radix: u32) -> &'static str {
+    match radix {
+        16 => "0x",
+        2 => "0b",
+        8 => "0o",
+        _ => "",
+    }
+}
+
+/// A way of writing numbers for people, such as `1,234.50`, `12.5%` or
+/// `1.23E4`.
+///
+/// A format is built with the builder methods or parsed from a pattern in
+/// the style of spreadsheets and ICU:
+///
+/// | Pattern       | 1234.567     | Meaning                                 |
+/// |---------------|--------------|-----------------------------------------|
+/// | `#,##0.00`    | `1,234.57`   | grouping, exactly two decimals          |
+/// | `0.0#`        | `1234.57`    | one or two decimals                     |
+/// | `0.0%`        | `123456.7%`  | multiplied by 100                       |
+/// | `@@@`         | `1230`       | three significant digits                |
+/// | `0.00E+00`    | `1.23E+03`   | scientific notation                     |
+/// | `##0.00E+0`   | `1.23E+3`    | engineering notation                    |
+/// | `¥#,##0`      | `¥1,235`     | text before and after is kept as is     |
+///
+/// An exponent pattern whose integer part is `##0` writes exponents in
+/// multiples of three. The separators are written according to the format's
+/// [`Separators`], whatever the pattern uses. Separate sections for negative
+/// numbers and zero, as in `#,##0;(#,##0)`, are not supported.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::format::NumberFormat;
+/// use rust_decimal::dec;
+///
+/// let format: NumberFormat = "#,##0.00".parse().unwrap();
+/// assert_eq!(format.format(dec!(1234.567)).unwrap(), "1,234.57");
+///
+/// let german = format.separators("de-DE".parse().unwrap());
+/// assert_eq!(german.format(dec!(-1234.5)).unwrap(), "-1.234,50");
+///
+/// let scientific = NumberFormat::new().scientific(2);
+/// assert_eq!(scientific.format(dec!(0.000123)).unwrap(), "1.23E-4");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// The text written before the number, such as `¥`
+    pub prefix: String,
+    /// The text written after the number, such as `%` or ` 元`
+    pub suffix: String,
+    /// The minimum number of digits before the decimal separator
+    pub min_integer_digits: usize,
+    /// The minimum number of decimals; missing ones are written as zeros
+    pub min_fraction_digits: u32,
+    /// The maximum number of decimals; `None` keeps them all
+    pub max_fraction_digits: Option<u32>,
+    /// The number of significant digits to round to, instead of decimals
+    pub significant_digits: Option<u32>,
+    /// Whether groups of thousands are separated
+    pub grouping: bool,
+    /// Whether the value is multiplied by 100 before it is written
+    pub percent: bool,
+    /// How the magnitude is written
+    pub notation: Notation,
+    /// The minimum number of digits of the exponent
+    pub min_exponent_digits: usize,
+    /// Whether a positive exponent is written with a `+`
+    pub exponent_sign: bool,
+    /// The decimal and grouping separators
+    pub separators: Separators,
+    /// How the value is rounded to the decimals or significant digits
+    pub rounding: RoundingMode,
+    /// Whether complex numbers are written in polar form, such as
+    /// `5∠53.13°`, instead of rectangular form, such as `3 + 4i`
+    pub polar: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            suffix: String::new(),
+            min_integer_digits: 1,
+            min_fraction_digits: 0,
+            max_fraction_digits: None,
+            significant_digits: None,
+            grouping: false,
+            percent: false,
+            notation: Notation::Standard,
+            min_exponent_digits: 1,
+            exponent_sign: false,
+            separators: Separators::default(),
+            rounding: RoundingMode::HalfUp,
+            polar: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Creates a format that writes numbers like `Decimal`'s `Display`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turns the separation of groups of thousands on or off.
+    pub fn grouping(mut self, grouping: bool) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Writes exactly `scale` decimals.
+    pub fn fixed(mut self, scale: u32) -> Self {
+        self.min_fraction_digits = scale;
+        self.max_fraction_digits = Some(scale);
+        self.significant_digits = None;
+        self
+    }
+
+    /// Rounds to `digits` significant digits instead of a number of decimals.
+    pub fn significant(mut self, digits: u32) -> Self {
+        self.significant_digits = Some(digits.max(1));
+        self.min_fraction_digits = 0;
+        self.max_fraction_digits = None;
+        self
+    }
+
+    /// Writes the value multiplied by 100 with `scale` decimals and a `%`.
+    pub fn percent(mut self, scale: u32) -> Self {
+        self.percent = true;
+        self.suffix = "%".to_string();
+        self.fixed(scale)
+    }
+
+    /// Writes the value as a mantissa between 1 and 10 with `scale` decimals
+    /// and an exponent, such as `1.23E4`.
+    pub fn scientific(mut self, scale: u32) -> Self {
+        self.notation = Notation::Scientific;
+        self.fixed(scale)
+    }
+
+    /// Writes the value like [`scientific`](Self::scientific), but with an
+    /// exponent that is a multiple of three, such as `12.3E3`.
+    pub fn engineering(mut self, scale: u32) -> Self {
+        self.notation = Notation::Engineering;
+        self.fixed(scale)
+    }
+
+    /// Writes integers in the given radix, from 2 to 36, such as `0xFF` for
+    /// 255 in radix 16. Hexadecimal, binary and octal numbers get the same
+    /// prefix as literals; formatting a number with a fraction fails.
+    pub fn radix(mut self, radix: u32) -> Self {
+        self.notation = Notation::Radix(radix);
+        self
+    }
+
+    /// Writes complex numbers in polar form, as a modulus and an angle in
+    /// degrees, instead of rectangular form.
+    pub fn polar(mut self, polar: bool) -> Self {
+        self.polar = polar;
+        self
+    }
+
+    /// Uses the given separators, for example those of a locale.
+    pub fn separators(mut self, separators: Separators) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// Uses the given rounding mode instead of `HALF_UP`.
+    pub fn rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Formats a number.
+    ///
+    /// # Returns
+    ///
+    /// The formatted text, or `CalcError::Overflow` if the value is too large
+    /// to be multiplied by 100 for a percentage or rounded. A radix format
+    /// returns `CalcError::InvalidArgument` for a radix outside 2 to 36 or a
+    /// value that is not an integer.
+    pub fn format(&self, value: Decimal) -> CalcResult<String> {
+        let value = if self.percent {
+            value
+                .checked_mul(Decimal::ONE_HUNDRED)
+                .ok_or(CalcError::Overflow)?
+        } else {
+            value
+        };
+        let (mantissa, exponent) = match self.notation {
+            Notation::Standard => (self.round(value)?, None),
+            Notation::Scientific => self.split_exponent(value, 1)?,
+            Notation::Engineering => self.split_exponent(value, 3)?,
+            Notation::Radix(_) => (value, None),
+        };
+        let mut text = String::new();
+        // 舍入为零后不写负号，避免出现 -0.00
+        if mantissa.is_sign_negative() && !mantissa.is_zero() {
+            text.push('-');
+        }
+        text.push_str(&self.prefix);
+        match self.notation {
+            Notation::Radix(radix) => text.push_str(&self.radix_digits(mantissa, radix)?),
+            _ => text.push_str(&self.digits(mantissa)),
+        }
+        if let Some(exponent) = exponent {
+            text.push('E');
+            if exponent < 0 {
+                text.push('-');
+            } else if self.exponent_sign {
+                text.push('+');
+            }
+            let digits = exponent.unsigned_abs().to_string();
+            text.extend(std::iter::repeat_n(
+                '0',
+                self.min_exponent_digits.saturating_sub(digits.len()),
+            ));
+            text.push_str(&digits);
+        }
+        text.push_str(&self.suffix);
+        Ok(text)
+    }
+
+    /// Formats a value of any numeric backend.
+    ///
+    /// Numbers are formatted with [`format`](Self::format), and the numbers
+    /// of a range or matrix one by one. Both parts of a complex number, or
+    /// its modulus and angle in polar form, are formatted the same way; the
+    /// modulus and angle are first rounded to 20 decimals, so that `1 + 1i`
+    /// is written at an angle of exactly `45°`. Text
+    /// is returned without quotes, and null and error values are written as
+    /// they display.
+    pub fn format_value<N: Numeric>(&self, value: &Value<N>) -> CalcResult<String> {
+        match value {
+            Value::Number(n) => self.format(n.to_decimal()?),
+            Value::Complex(z) => self.format_complex(z),
+            Value::Range(values) => {
+                let values = values
+                    .iter()
+                    .map(|n| self.format(n.to_decimal()?))
+                    .collect::<CalcResult<Vec<_>>>()?;
+                Ok(format!("[{}]", values.join(", ")))
+            }
+            Value::Matrix(m) => {
+                let rows = m
+                    .elements()
+                    .chunks(m.cols().max(1))
+                    .map(|row| {
+                        let row = row
+                            .iter()
+                            .map(|n| self.format(n.to_decimal()?))
+                            .collect::<CalcResult<Vec<_>>>()?;
+                        Ok(row.join(", "))
+                    })
+                    .collect::<CalcResult<Vec<_>>>()?;
+                Ok(format!("[{}]", rows.join("; ")))
+            }
+            Value::Text(text) => Ok(text.clone()),
+            Value::Null | Value::Error(..) => Ok(value.to_string()),
+        }
+    }
+
+    fn format_complex<N: Numeric>(&self, z: &Complex<N>) -> CalcResult<String> {
+        if self.polar {
+            // 模和角度由开方、反正切算出，末几位有误差，先舍入到 POLAR_SCALE 位小数
+            let modulus = z.abs()?.to_decimal()?.round_dp(POLAR_SCALE);
+            let degrees = (z
+                .arg()?
+                .to_decimal()?
+                .checked_mul(Decimal::from(180))
+                .ok_or(CalcError::Overflow)?
+                / Decimal::PI)
+                .round_dp(POLAR_SCALE);
+            return Ok(format!(
+                "{}∠{}°",
+                self.format(modulus)?,
+                self.format(degrees)?
+            ));
+        }
+        let im = self.format(z.im.to_decimal()?)?;
+        if z.re.is_zero() {
+            return Ok(format!("{}i", im));
+        }
+        let re = self.format(z.re.to_decimal()?)?;
+        Ok(match im.strip_prefix('-') {
+            Some(magnitude) => format!("{} - {}i", re, magnitude),
+            None => format!("{} + {}i", re, im),
+        })
+    }
+
+    fn round(&self, value: Decimal) -> CalcResult<Decimal> {
+        let strategy = self.rounding.to_strategy();
+        match (self.significant_digits, self.max_fraction_digits) {
+            (Some(digits), _) => value
+                .round_sf_with_strategy(digits, strategy)
+                .ok_or(CalcError::Overflow),
+            (None, Some(scale)) => Ok(value.round_dp_with_strategy(scale, strategy)),
+            (None, None) => Ok(value),
+        }
+    }
+
+    /// Splits a value into a rounded mantissa and an exponent that is a
+    /// multiple of `step`.
+    fn split_exponent(&self, value: Decimal, step: i64) -> CalcResult<(Decimal, Option<i64>)> {
+        if value.is_zero() {
+            return Ok((self.round(value)?, Some(0)));
+        }
+        let mut exponent = magnitude(value).div_euclid(step) * step;
+        let mut mantissa = self.round(shift(value, exponent)?)?;
+        // 舍入可能进位到下一个数量级，例如 9.996 → 10.00
+        if magnitude(mantissa) >= step {
+            exponent += step;
+            mantissa = self.round(shift(value, exponent)?)?;
+        }
+        Ok((mantissa, Some(exponent)))
+    }
+
+    /// Writes the magnitude of an integer in a radix, with its literal prefix.
+    fn radix_digits(&self, value: Decimal, radix: u32) -> CalcResult<String> {
+        let invalid = |value: String| CalcError::InvalidArgument {
+            function: "format".to_string(),
+            value,
+        };
+        if !(2..=36).contains(&radix) {
+            return Err(invalid(radix.to_string()));
+        }
+        if !value.fract().is_zero() {
+            return Err(invalid(value.to_string()));
+        }
+        // Decimal 的整数部分最多 96 位，u128 一定放得下
+        let mut n = value.abs().trunc().mantissa().unsigned_abs();
+        let mut digits = Vec::new();
+        loop {
+            let digit = (n % radix as u128) as u32;
+            digits.extend(char::from_digit(digit, radix).map(|c| c.to_ascii_uppercase()));
+            n /= radix as u128;
+            if n == 0 {
+                break;
+            }
+        }
+        digits.resize(digits.len().max(self.min_integer_digits), '0');
+        Ok(radix_prefix(radix)
+            .chars()
+            .chain(digits.into_iter().rev())
+            .collect())
+    }
+
+    /// Writes the digits of a rounded value with separators, but without
+    /// its sign.
+    fn digits(&self, value: Decimal) -> String {
+        let text = value.abs().to_string();
+        let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let mut fraction = fraction.to_string();
+        match self.significant_digits {
+            Some(digits) => {
+                // 补零直到有效数字足够，例如三位有效数字的 1.5 写作 1.50
+                // 零本身算一位有效数字，三位有效数字的 0 写作 0.00
+                let significant = if value.is_zero() {
+                    fraction.clear();
+                    1
+                } else {
+                    format!("{integer}{fraction}").trim_start_matches('0').len()
+                };
+                fraction.extend(std::iter::repeat_n(
+                    '0',
+                    (digits as usize).saturating_sub(significant),
+                ));
+            }
+            None => {
+                let min = self.min_fraction_digits as usize;
+                while fraction.len() > min && fraction.ends_with('0') {
+                    fraction.pop();
+                }
+                fraction.extend(std::iter::repeat_n('0', min.saturating_sub(fraction.len())));
+            }
+        }
+        let integer = if integer == "0" && self.min_integer_digits == 0 && !fraction.is_empty() {
+            ""
+        } else {
+            integer
+        };
+        let mut digits: String =
+            std::iter::repeat_n('0', self.min_integer_digits.saturating_sub(integer.len()))
+                .chain(integer.chars())
+                .collect();
+        if self.grouping {
+            digits = group(&digits, self.separators.group);
+        }
+        if !fraction.is_empty() {
+            digits.push(self.separators.decimal);
+            digits.push_str(&fraction);
+        }
+        digits
+    }
+}
+
+impl FromStr for NumberFormat {
+    type Err = CalcError;
+
+    /// Parses a pattern such as `#,##0.00`, `0.0%` or `0.00E+00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CalcError::InvalidArgument {
+            function: "format".to_string(),
+            value: s.to_string(),
+        };
+        // 不支持以 ; 分隔的负数、零值格式段
+        if s.contains(';') {
+            return Err(invalid());
+        }
+        let chars: Vec<char> = s.chars().collect();
+        let start = chars
+            .iter()
+            .position(|c| matches!(c, '#' | '0' | '@' | '.'))
+            .ok_or_else(invalid)?;
+        let length = chars[start..]
+            .iter()
+            .take_while(|c| matches!(c, '#' | '0' | '@' | '.' | ','))
+            .count();
+        let number: String = chars[start..start + length].iter().collect();
+        let mut rest = &chars[start + length..];
+
+        let mut format = Self {
+            prefix: chars[..start].iter().collect(),
+            ..Self::default()
+        };
+        let (integer, fraction) = match number.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (number.as_str(), None),
+        };
+        if fraction.is_some_and(|fraction| fraction.contains(['.', ',', '@'])) {
+            return Err(invalid());
+        }
+        format.grouping = integer.contains(',');
+        format.min_integer_digits = integer.matches('0').count();
+        let places = integer.matches(['#', '0']).count();
+        if integer.contains('@') {
+            // 有效数字不能与小数位、整数位同时指定
+            if fraction.is_some() || integer.contains('0') {
+                return Err(invalid());
+            }
+            format.significant_digits = Some(integer.matches('@').count() as u32);
+            format.min_integer_digits = 1;
+        } else {
+            let fraction = fraction.unwrap_or_default();
+            format.min_fraction_digits = fraction.matches('0').count() as u32;
+            format.max_fraction_digits = Some(fraction.len() as u32);
+            if places == 0 && fraction.is_empty() {
+                return Err(invalid());
+            }
+        }
+
+        if let [e, tail @ ..] = rest
+            && matches!(*e, 'E' | 'e')
+        {
+            let (sign, tail) = match tail {
+                ['+', tail @ ..] => (true, tail),
+                ['-', tail @ ..] => (false, tail),
+                _ => (false, tail),
+            };
+            let digits = tail.iter().take_while(|c| **c == '0').count();
+            if digits > 0 && format.significant_digits.is_none() {
+                format.notation = if places == 3 && integer.starts_with('#') {
+                    Notation::Engineering
+                } else {
+                    Notation::Scientific
+                };
+                format.min_exponent_digits = digits;
+                format.exponent_sign = sign;
+                rest = &tail[digits..];
+            }
+        }
+        format.suffix = rest.iter().collect();
+        format.percent = format.prefix.contains('%') || format.suffix.contains('%');
+        Ok(format)
+    }
+}
+
+/// Returns the exponent of the leading digit of a non-zero value, such as 3
+/// for 1234 and -2 for 0.05.
+fn magnitude(value: Decimal) -> i64 {
+    let value = value.abs().normalize();
+    value.mantissa().to_string().len() as i64 - 1 - value.scale() as i64
+}
+
+/// Divides a value by `10^exponent` by moving its decimal point.
+fn shift(value: Decimal, exponent: i64) -> CalcResult<Decimal> {
+    let value = value.normalize();
+    let scale = value.scale() as i64 + exponent;
+    let result = match u32::try_from(scale) {
+        Ok(scale) => Decimal::try_from_i128_with_scale(value.mantissa(), scale),
+        Err(_) => Decimal::try_from_i128_with_scale(
+            value.mantissa() * 10i128.pow(scale.unsigned_abs() as u32),
+            0,
+        ),
+    };
+    result.map_err(|_| CalcError::Overflow)
+}
+
+/// Inserts a separator between groups of three digits.
+fn group(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3 + 1);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::matrix::Matrix;
+    use rust_decimal::dec;
+
+    fn format(pattern: &str, value: Decimal) -> String {
+        pattern
+            .parse::<NumberFormat>()
+            .unwrap()
+            .format(value)
+            .unwrap()
+    }
+
+    /// Tests the patterns from the table in the documentation and a few
+    /// corner cases of rounding and padding.
+    #[test]
+    fn test_patterns() {
+        let value = dec!(1234.567);
+        assert_eq!(format("#,##0.00", value), "1,234.57");
+        assert_eq!(format("0.0#", value), "1234.57");
+        assert_eq!(format("0.0%", value), "123456.7%");
+        assert_eq!(format("@@@", value), "1230");
+        assert_eq!(format("0.00E+00", value), "1.23E+03");
+        assert_eq!(format("##0.00E+0", value), "1.23E+3");
+        assert_eq!(format("¥#,##0", value), "¥1,235");
+
+        assert_eq!(format("#,##0.00", dec!(-0.001)), "0.00");
+        assert_eq!(format("#,##0", dec!(-1234567)), "-1,234,567");
+        assert_eq!(format("0.0#", dec!(2)), "2.0");
+        assert_eq!(format("000", dec!(7)), "007");
+        assert_eq!(format("#.##", dec!(0.5)), ".5");
+        assert_eq!(format("@@@", dec!(1.5)), "1.50");
+        assert_eq!(format("0.00E0", dec!(9.996)), "1.00E1");
+        assert_eq!(format("##0.0E0", dec!(0.05)), "50.0E-3");
+        assert_eq!(format("0E0", dec!(0)), "0E0");
+
+        for invalid in [
+            "",
+            "abc",
+            "0.0.0",
+            "0.,0",
+            "@.0",
+            "0@",
+            "#,##0.00;(#,##0.00)",
+        ] {
+            assert!(invalid.parse::<NumberFormat>().is_err(), "{invalid}");
+        }
+    }
+
+    /// Tests the builder methods and locale separators.
+    #[test]
+    fn test_builder() {
+        let value = dec!(1234567.891);
+        let format = NumberFormat::new().grouping(true).fixed(2);
+        assert_eq!(format.format(value).unwrap(), "1,234,567.89");
+        let german = format.clone().separators("de-DE".parse().unwrap());
+        assert_eq!(german.format(value).unwrap(), "1.234.567,89");
+        let french = format.separators("fr".parse().unwrap());
+        assert_eq!(french.format(value).unwrap(), "1\u{202F}234\u{202F}567,89");
+        assert_eq!(
+            NumberFormat::new().significant(2).format(value).unwrap(),
+            "1200000"
+        );
+        assert_eq!(
+            NumberFormat::new().significant(3).format(dec!(0)).unwrap(),
+            "0.00"
+        );
+        assert_eq!(
+            NumberFormat::new()
+                .significant(2)
+                .format(dec!(0.000))
+                .unwrap(),
+            "0.0"
+        );
+        assert_eq!(
+            NumberFormat::new().percent(1).format(dec!(0.125)).unwrap(),
+            "12.5%"
+        );
+        assert_eq!(
+            NumberFormat::new().engineering(1).format(value).unwrap(),
+            "1.2E6"
+        );
+        assert_eq!(
+            NumberFormat::new()
+                .fixed(0)
+                .rounding(RoundingMode::HalfEven)
+                .format(dec!(2.5))
+                .unwrap(),
+            "2"
+        );
+        assert_eq!(
+            NumberFormat::new().percent(0).format(Decimal::MAX),
+            Err(CalcError::Overflow)
+        );
+        assert_eq!(
+            NumberFormat::new().radix(16).format(dec!(255)).unwrap(),
+            "0xFF"
+        );
+        assert_eq!(
+            NumberFormat::new().radix(2).format(dec!(-10)).unwrap(),
+            "-0b1010"
+        );
+        assert_eq!(
+            NumberFormat::new().radix(36).format(dec!(35.00)).unwrap(),
+            "Z"
+        );
+        let mut octal = NumberFormat::new().radix(8);
+        octal.min_integer_digits = 4;
+        assert_eq!(octal.format(dec!(15)).unwrap(), "0o0017");
+        for (radix, value) in [(16, dec!(1.5)), (1, dec!(1)), (37, dec!(1))] {
+            assert!(matches!(
+                NumberFormat::new().radix(radix).format(value),
+                Err(CalcError::InvalidArgument { .. })
+            ));
+        }
+        assert!("xx-YY".parse::<Separators>().is_err());
+        assert_eq!(Separators::from(Locale::ZhCn), Separators::default());
+    }
+
+    /// Tests formatting values of other backends and other kinds.
+    #[test]
+    fn test_format_value() {
+        let format = NumberFormat::new().fixed(2);
+        assert_eq!(
+            format.format_value(&Value::Number(0.1_f64 + 0.2)),
+            Ok("0.30".to_string())
+        );
+        assert_eq!(
+            format.format_value(&Value::Range(vec![dec!(1), dec!(2.345)])),
+            Ok("[1.00, 2.35]".to_string())
+        );
+        let m = Matrix::new(2, 2, vec![dec!(1), dec!(0.5), dec!(-2), dec!(3)]).unwrap();
+        assert_eq!(
+            format.format_value(&Value::Matrix(m)),
+            Ok("[1.00, 0.50; -2.00, 3.00]".to_string())
+        );
+        assert_eq!(
+            format.format_value(&Value::<Decimal>::Text("a".to_string())),
+            Ok("a".to_string())
+        );
+        let z = Value::Complex(Complex::new(dec!(3), dec!(-4)));
+        assert_eq!(format.format_value(&z), Ok("3.00 - 4.00i".to_string()));
+        assert_eq!(
+            format.clone().polar(true).format_value(&z),
+            Ok("5.00∠-53.13°".to_string())
+        );
+        // 角度和模的最后几位误差被舍去，45° 不会写成 44.999…°
+        let z = Value::Complex(Complex::new(dec!(1), dec!(1)));
+        assert_eq!(
+            NumberFormat::new().polar(true).format_value(&z),
+            Ok("1.4142135623730950488∠45°".to_string())
+        );
+        let i = Value::Complex(Complex::new(dec!(0), dec!(1)));
+        assert_eq!(
+            format.polar(true).format_value(&i),
+            Ok("1.00∠90.00°".to_string())
+        );
+    }
+}
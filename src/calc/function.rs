@@ -0,0 +1,700 @@
+use crate::calc::chinese;
+use crate::calc::complex::Complex;
+use crate::calc::context::{Context, RoundingMode};
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::format::NumberFormat;
+use crate::calc::locale::Locale;
+use crate::calc::matrix::Matrix;
+use crate::calc::numeric::Numeric;
+use crate::calc::value::{Value, ValueType};
+use rust_decimal::Decimal;
+
+/// Name of the `nvl<value, default>` function.
+///
+/// `nvl` is evaluated lazily by the evaluator, because its first argument is
+/// allowed to reference a variable that does not exist.
+pub const NVL: &str = "nvl";
+
+/// Name of the `iferror<value, fallback>` function.
+///
+/// `iferror` is evaluated lazily by the evaluator: the fallback is only
+/// evaluated when the value is an error value or an evaluation error that
+/// has an error value kind, such as a division by zero.
+pub const IFERROR: &str = "iferror";
+
+/// Name of the `iserror<value>` function, evaluated lazily like `iferror`.
+pub const ISERROR: &str = "iserror";
+
+/// Name of the `errortype<value>` function, evaluated lazily like `iferror`.
+pub const ERRORTYPE: &str = "errortype";
+
+/// Localized names of the built-in functions, as `(locale, alias, name)`.
+///
+/// The parser resolves an alias to the function's name, so `求和<a, b>`
+/// calls the same implementation as `sum<a, b>`.
+const ALIASES: &[(Locale, &str, &str)] = &[
+    (Locale::ZhCn, "绝对值", "abs"),
+    (Locale::ZhCn, "平方根", "sqrt"),
+    (Locale::ZhCn, "阶乘", "factorial"),
+    (Locale::ZhCn, "四舍五入", "round"),
+    (Locale::ZhCn, "求和", "sum"),
+    (Locale::ZhCn, "平均值", "avg"),
+    (Locale::ZhCn, "计数", "count"),
+    (Locale::ZhCn, "最小值", "min"),
+    (Locale::ZhCn, "最大值", "max"),
+    (Locale::ZhCn, "默认值", NVL),
+    (Locale::ZhCn, "如果错误", IFERROR),
+    (Locale::ZhCn, "是否错误", ISERROR),
+    (Locale::ZhCn, "错误类型", ERRORTYPE),
+    (Locale::ZhCn, "人民币大写", "rmb"),
+    (Locale::ZhCn, "格式化", "format"),
+    (Locale::ZhCn, "实部", "re"),
+    (Locale::ZhCn, "虚部", "im"),
+    (Locale::ZhCn, "共轭", "conj"),
+    (Locale::ZhCn, "辐角", "arg"),
+    (Locale::ZhCn, "转置", "transpose"),
+    (Locale::ZhCn, "行列式", "det"),
+    (Locale::ZhCn, "逆矩阵", "inv"),
+    (Locale::ZhCn, "点积", "dot"),
+    (Locale::ZhCn, "求解", "solve"),
+];
+
+/// Resolves a localized alias, such as `求和`, to the name of the built-in
+/// function it stands for. Any other name is returned unchanged.
+pub fn canonical_name(function_prefix: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(_, alias, _)| *alias == function_prefix)
+        .map_or(function_prefix, |(_, _, name)| name)
+}
+
+/// Returns the alias of a built-in function in the given locale, or the
+/// function name itself if the locale has no alias for it.
+pub fn localized_name(function_prefix: &str, locale: Locale) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias_locale, _, name)| *alias_locale == locale && *name == function_prefix)
+        .map_or(function_prefix, |(_, alias, _)| alias)
+}
+
+/// Checks the number of arguments passed to a built-in function.
+///
+/// # Arguments
+///
+/// * `function_prefix` - The name of the function being called
+/// * `actual` - The number of arguments supplied
+///
+/// # Returns
+///
+/// An error if the function is unknown or receives the wrong number of arguments
+pub fn check_arg_count(function_prefix: &str, actual: usize) -> CalcResult<()> {
+    let (min, max) = match function_prefix {
+        "abs" | "sqrt" | "factorial" | "rmb" | "bnot" | ISERROR | ERRORTYPE => (1, Some(1)),
+        "re" | "im" | "conj" | "arg" | "transpose" | "det" | "inv" => (1, Some(1)),
+        NVL | IFERROR | "band" | "bor" | "bxor" | "shl" | "shr" | "dot" | "solve" => (2, Some(2)),
+        "round" => (1, Some(3)),
+        "format" => (2, Some(3)),
+        "sum" | "avg" | "count" | "min" | "max" => (1, None),
+        _ => return Err(CalcError::UnknownFunction(function_prefix.to_string())),
+    };
+    if actual < min || max.is_some_and(|max| actual > max) {
+        let expected = match max {
+            Some(max) if max == min => min.to_string(),
+            Some(max) => format!("{min}..{max}"),
+            None => format!("{min}+"),
+        };
+        return Err(CalcError::ArgumentCount {
+            function: function_prefix.to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Calls a built-in function with already evaluated arguments.
+///
+/// Aggregates (`sum`, `avg`, `count`, `min`, `max`) accept any mix of numbers
+/// and ranges; every other function expects numbers. The arithmetic is
+/// dispatched to the numeric backend `N`, so for example `sqrt` is only
+/// available on `Rational` for perfect squares.
+///
+/// `rmb<x>` returns the amount as uppercase Chinese currency text, such as
+/// `壹佰元整`.
+///
+/// `format<x, pattern, locale>` writes the number with a
+/// [`NumberFormat`] pattern such as `"#,##0.00"`, using the separators of
+/// the optional locale tag.
+///
+/// `re`, `im`, `conj`, `arg` and `abs` accept complex numbers, and `sqrt`
+/// of a negative number returns one when `Context::complex_numbers` is set.
+/// The aggregates reject complex arguments.
+///
+/// `transpose`, `det`, `inv`, `dot` and `solve<a, b>` work on matrices,
+/// treating a number as a 1×1 matrix, and the aggregates take every element
+/// of a matrix argument. `solve` accepts the right-hand side as a column or
+/// a row vector and returns the solution in the same orientation.
+///
+/// The bitwise functions `band`, `bor`, `bxor`, `bnot`, `shl` and `shr`
+/// work on integers as 64-bit two's complement, so `bnot<0>` is `-1` and
+/// `shr` rounds towards negative infinity.
+///
+/// `round<x, scale, mode>` falls back to the context's scale (or 0) and
+/// rounding mode when `scale` or `mode` are omitted.
+///
+/// # Arguments
+///
+/// * `function_prefix` - The name of the function to call
+/// * `args` - The evaluated arguments
+/// * `context` - The evaluation context
+///
+/// # Returns
+///
+/// The result of the function, or an error if the function is unknown or
+/// the arguments do not match its signature
+pub fn call<N: Numeric>(
+    function_prefix: &str,
+    args: &[Value<N>],
+    context: &Context,
+) -> CalcResult<Value<N>> {
+    check_arg_count(function_prefix, args.len())?;
+    // 聚合函数只对实数有定义
+    if matches!(function_prefix, "sum" | "avg" | "count" | "min" | "max")
+        && let Some(arg) = args.iter().find(|arg| matches!(arg, Value::Complex(_)))
+    {
+        return Err(CalcError::TypeMismatch {
+            expected: ValueType::Number,
+            actual: arg.value_type(),
+        });
+    }
+    let result = match function_prefix {
+        "abs" => match &args[0] {
+            Value::Complex(z) => z.abs()?,
+            value => number(value)?.abs()?,
+        },
+        "sqrt" => return sqrt(&args[0], context),
+        "re" | "im" | "conj" | "arg" => return complex_part(function_prefix, &args[0]),
+        "transpose" => return Ok(Value::Matrix(matrix(&args[0])?.transpose())),
+        "det" => matrix(&args[0])?.det()?,
+        "inv" => return Ok(Value::Matrix(matrix(&args[0])?.inv()?)),
+        "dot" => matrix(&args[0])?.dot(&matrix(&args[1])?)?,
+        "solve" => return Ok(Value::Matrix(matrix(&args[0])?.solve(&matrix(&args[1])?)?)),
+        "factorial" => factorial(function_prefix, &number(&args[0])?)?,
+        "round" => round(function_prefix, args, context)?,
+        "band" | "bor" | "bxor" | "bnot" | "shl" | "shr" => bitwise(function_prefix, args)?,
+        "rmb" => {
+            let amount = number(&args[0])?.to_decimal()?;
+            return Ok(Value::Text(chinese::to_rmb(amount)));
+        }
+        "format" => return format_number(args),
+        // 参数已经求值成功，说明第一个参数存在
+        NVL | IFERROR => return Ok(args[0].clone()),
+        ISERROR => N::from_usize(matches!(args[0], Value::Error(..)) as usize),
+        ERRORTYPE => match &args[0] {
+            Value::Error(kind, _) => N::from_usize(kind.code()),
+            other => {
+                return Err(CalcError::InvalidArgument {
+                    function: function_prefix.to_string(),
+                    value: other.to_string(),
+                });
+            }
+        },
+        "sum" => sum(args)?,
+        "avg" => {
+            let count = flatten(args).count();
+            if count == 0 {
+                return Err(CalcError::DivideByZero);
+            }
+            sum(args)?.div(&N::from_usize(count))?
+        }
+        "count" => N::from_usize(flatten(args).count()),
+        // 与电子表格一致，空区域的最小值、最大值为 0
+        "min" => extreme(args, |value, current| value < current),
+        "max" => extreme(args, |value, current| value > current),
+        _ => return Err(CalcError::UnknownFunction(function_prefix.to_string())),
+    };
+    Ok(Value::Number(result))
+}
+
+fn number<N: Numeric>(value: &Value<N>) -> CalcResult<N> {
+    value.clone().into_number()
+}
+
+/// 数字视为 1×1 矩阵
+fn matrix<N: Numeric>(value: &Value<N>) -> CalcResult<Matrix<N>> {
+    match value {
+        Value::Matrix(m) => Ok(m.clone()),
+        Value::Number(n) => Matrix::new(1, 1, vec![n.clone()]),
+        Value::Error(kind, span) => Err(CalcError::ErrorValue {
+            kind: *kind,
+            span: *span,
+        }),
+        other => Err(CalcError::TypeMismatch {
+            expected: ValueType::Matrix,
+            actual: other.value_type(),
+        }),
+    }
+}
+
+/// Iterates over the numbers of all arguments, expanding ranges and
+/// matrices.
+fn flatten<N: Numeric>(args: &[Value<N>]) -> impl Iterator<Item = &N> {
+    args.iter().flat_map(|arg| match arg {
+        Value::Number(n) => std::slice::from_ref(n),
+        Value::Range(values) => values.as_slice(),
+        Value::Matrix(m) => m.elements(),
+        Value::Complex(_) | Value::Text(_) | Value::Null | Value::Error(..) => &[],
+    })
+}
+
+fn sum<N: Numeric>(args: &[Value<N>]) -> CalcResult<N> {
+    flatten(args).try_fold(N::zero(), |acc, n| acc.add(n))
+}
+
+/// 开启复数时，负数的平方根为纯虚数
+fn sqrt<N: Numeric>(value: &Value<N>, context: &Context) -> CalcResult<Value<N>> {
+    let z = match value {
+        Value::Complex(z) => z.clone(),
+        value => {
+            let n = number(value)?;
+            if !context.complex_numbers || n >= N::zero() {
+                return Ok(Value::Number(n.sqrt()?));
+            }
+            Complex::from_real(n)
+        }
+    };
+    Ok(Value::from_complex(z.sqrt()?))
+}
+
+/// re、im、conj、arg 把实数视为虚部为零的复数
+fn complex_part<N: Numeric>(function_prefix: &str, value: &Value<N>) -> CalcResult<Value<N>> {
+    let z = match value {
+        Value::Complex(z) => z.clone(),
+        value => Complex::from_real(number(value)?),
+    };
+    Ok(match function_prefix {
+        "re" => Value::Number(z.re),
+        "im" => Value::Number(z.im),
+        "conj" => Value::from_complex(z.conj()?),
+        _ => Value::Number(z.arg()?),
+    })
+}
+
+fn round<N: Numeric>(function_prefix: &str, args: &[Value<N>], context: &Context) -> CalcResult<N> {
+    let scale = match args.get(1) {
+        Some(scale) => {
+            let scale = number(scale)?;
+            scale
+                .to_i64()
+                .and_then(|scale| u32::try_from(scale).ok())
+                .ok_or_else(|| CalcError::InvalidArgument {
+                    function: function_prefix.to_string(),
+                    value: scale.to_string(),
+                })?
+        }
+        None => context.scale.unwrap_or(0),
+    };
+    let mode = match args.get(2) {
+        Some(mode) => mode.clone().into_text()?.parse::<RoundingMode>()?,
+        None => context.rounding_mode,
+    };
+    number(&args[0])?.round(scale, mode)
+}
+
+fn format_number<N: Numeric>(args: &[Value<N>]) -> CalcResult<Value<N>> {
+    let mut format: NumberFormat = args[1].clone().into_text()?.parse()?;
+    if let Some(locale) = args.get(2) {
+        format = format.separators(locale.clone().into_text()?.parse()?);
+    }
+    let value = number(&args[0])?.to_decimal()?;
+    Ok(Value::Text(format.format(value)?))
+}
+
+/// 按位运算，操作数必须是 i64 范围内的整数
+fn bitwise<N: Numeric>(function_prefix: &str, args: &[Value<N>]) -> CalcResult<N> {
+    let invalid = |value: String| CalcError::InvalidArgument {
+        function: function_prefix.to_string(),
+        value,
+    };
+    let integer = |value: &Value<N>| {
+        let n = number(value)?;
+        n.to_i64().ok_or_else(|| invalid(n.to_string()))
+    };
+    let lhs = integer(&args[0])?;
+    let result = match function_prefix {
+        "bnot" => !lhs,
+        "band" => lhs & integer(&args[1])?,
+        "bor" => lhs | integer(&args[1])?,
+        "bxor" => lhs ^ integer(&args[1])?,
+        _ => {
+            let shift = integer(&args[1])?;
+            let shift = u32::try_from(shift)
+                .ok()
+                .filter(|shift| *shift < i64::BITS)
+                .ok_or_else(|| invalid(shift.to_string()))?;
+            if function_prefix == "shr" {
+                lhs >> shift
+            } else {
+                // 左移丢失了有效位（包括符号位）时视为溢出
+                let shifted = lhs << shift;
+                if shifted >> shift != lhs {
+                    return Err(CalcError::Overflow);
+                }
+                shifted
+            }
+        }
+    };
+    N::from_decimal(Decimal::from(result))
+}
+
+/// n!，要求 n 为非负整数
+pub(crate) fn factorial<N: Numeric>(function_prefix: &str, n: &N) -> CalcResult<N> {
+    let n = n
+        .to_i64()
+        .filter(|n| *n >= 0)
+        .ok_or_else(|| CalcError::InvalidArgument {
+            function: function_prefix.to_string(),
+            value: n.to_string(),
+        })?;
+    (2..=n as usize).try_fold(N::from_usize(1), |acc, i| acc.mul(&N::from_usize(i)))
+}
+
+fn extreme<N: Numeric>(args: &[Value<N>], better: impl Fn(&N, &N) -> bool) -> N {
+    flatten(args)
+        .fold(None, |current: Option<&N>, value| match current {
+            Some(current) if !better(value, current) => Some(current),
+            _ => Some(value),
+        })
+        .cloned()
+        .unwrap_or_else(N::zero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::numeric::Rational;
+    use crate::calc::span::Span;
+    use crate::calc::value::ErrorKind;
+    use rust_decimal::{Decimal, dec};
+
+    fn call<N: Numeric>(function_prefix: &str, args: &[Value<N>]) -> CalcResult<Value<N>> {
+        super::call(function_prefix, args, &Context::default())
+    }
+
+    /// Tests calling built-in functions with valid and invalid arguments.
+    #[test]
+    fn test_call() {
+        let n = |n: Decimal| Value::Number(n);
+        assert_eq!(call("abs", &[n(dec!(-2))]), Ok(n(dec!(2))));
+        assert_eq!(
+            call("max", &[n(dec!(1)), n(dec!(3)), n(dec!(2))]),
+            Ok(n(dec!(3)))
+        );
+        assert_eq!(
+            call::<Decimal>("abs", &[]),
+            Err(CalcError::ArgumentCount {
+                function: "abs".to_string(),
+                expected: "1".to_string(),
+                actual: 0
+            })
+        );
+        assert_eq!(
+            call("foo", &[n(dec!(1))]),
+            Err(CalcError::UnknownFunction("foo".to_string()))
+        );
+    }
+
+    /// Tests that aliases resolve to function names and back, and that names
+    /// without an alias are left alone.
+    #[test]
+    fn test_aliases() {
+        assert_eq!(canonical_name("求和"), "sum");
+        assert_eq!(canonical_name("如果错误"), IFERROR);
+        assert_eq!(canonical_name("sum"), "sum");
+        assert_eq!(canonical_name("合计"), "合计");
+        assert_eq!(localized_name("abs", Locale::ZhCn), "绝对值");
+        assert_eq!(localized_name("abs", Locale::EnUs), "abs");
+        assert_eq!(localized_name("foo", Locale::ZhCn), "foo");
+        for (_, _, name) in ALIASES {
+            assert!(check_arg_count(name, 1).is_ok() || check_arg_count(name, 2).is_ok());
+        }
+    }
+
+    /// Tests `factorial` on valid input, invalid input and backend overflow.
+    #[test]
+    fn test_call_factorial() {
+        let n = |n: Decimal| [Value::Number(n)];
+        assert_eq!(call("factorial", &n(dec!(5))), Ok(Value::Number(dec!(120))));
+        assert_eq!(call("factorial", &n(dec!(0))), Ok(Value::Number(dec!(1))));
+        assert_eq!(call("factorial", &n(dec!(30))), Err(CalcError::Overflow));
+        for invalid in [dec!(-1), dec!(2.5)] {
+            assert_eq!(
+                call("factorial", &n(invalid)),
+                Err(CalcError::InvalidArgument {
+                    function: "factorial".to_string(),
+                    value: invalid.to_string()
+                })
+            );
+        }
+    }
+
+    /// Tests the bitwise functions, including negative operands, shifts out
+    /// of range and non-integer operands.
+    #[test]
+    fn test_call_bitwise() {
+        let n = |n: Decimal| Value::Number(n);
+        for (function_prefix, args, expected) in [
+            ("band", vec![dec!(12), dec!(10)], dec!(8)),
+            ("bor", vec![dec!(12), dec!(10)], dec!(14)),
+            ("bxor", vec![dec!(12), dec!(10)], dec!(6)),
+            ("bnot", vec![dec!(0)], dec!(-1)),
+            ("band", vec![dec!(-1), dec!(255)], dec!(255)),
+            ("shl", vec![dec!(1), dec!(10)], dec!(1024)),
+            ("shl", vec![dec!(-3), dec!(2)], dec!(-12)),
+            ("shr", vec![dec!(1024), dec!(3)], dec!(128)),
+            ("shr", vec![dec!(-5), dec!(1)], dec!(-3)),
+        ] {
+            let args: Vec<_> = args.into_iter().map(n).collect();
+            assert_eq!(
+                call(function_prefix, &args),
+                Ok(n(expected)),
+                "{function_prefix}"
+            );
+        }
+        assert_eq!(
+            call("shl", &[n(dec!(1)), n(dec!(63))]),
+            Err(CalcError::Overflow)
+        );
+        for (function_prefix, args, value) in [
+            ("band", [dec!(1.5), dec!(1)], "1.5"),
+            ("shl", [dec!(1), dec!(64)], "64"),
+            ("shr", [dec!(1), dec!(-1)], "-1"),
+            ("bor", [dec!(1), dec!(1e20)], "100000000000000000000"),
+        ] {
+            assert_eq!(
+                call(function_prefix, &args.map(n)),
+                Err(CalcError::InvalidArgument {
+                    function: function_prefix.to_string(),
+                    value: value.to_string()
+                })
+            );
+        }
+    }
+
+    /// Tests `iserror` and `errortype` on evaluated arguments.
+    #[test]
+    fn test_call_error_functions() {
+        let error = Value::<Decimal>::Error(ErrorKind::Ref, Span::new(0, 2));
+        assert_eq!(
+            call("iserror", std::slice::from_ref(&error)),
+            Ok(Value::Number(dec!(1)))
+        );
+        assert_eq!(
+            call("iserror", &[Value::Number(dec!(1))]),
+            Ok(Value::Number(dec!(0)))
+        );
+        assert_eq!(call("errortype", &[error]), Ok(Value::Number(dec!(4))));
+        assert!(call("errortype", &[Value::Number(dec!(1))]).is_err());
+    }
+
+    /// Tests `round` with explicit and context defaults.
+    #[test]
+    fn test_call_round() {
+        let args = |mode: &str| {
+            [
+                Value::Number(dec!(-2.5)),
+                Value::Number(dec!(0)),
+                Value::Text(mode.to_string()),
+            ]
+        };
+        assert_eq!(call("round", &args("HALF_UP")), Ok(Value::Number(dec!(-3))));
+        assert_eq!(
+            call("round", &args("HALF_EVEN")),
+            Ok(Value::Number(dec!(-2)))
+        );
+        assert_eq!(call("round", &args("CEILING")), Ok(Value::Number(dec!(-2))));
+        let context = Context {
+            scale: Some(1),
+            rounding_mode: RoundingMode::Down,
+            ..Context::default()
+        };
+        assert_eq!(
+            super::call("round", &[Value::Number(dec!(1.99))], &context),
+            Ok(Value::Number(dec!(1.9)))
+        );
+        assert_eq!(
+            call("round", &[Value::Number(dec!(1)), Value::Number(dec!(-1))]),
+            Err(CalcError::InvalidArgument {
+                function: "round".to_string(),
+                value: "-1".to_string()
+            })
+        );
+    }
+
+    /// Tests that `rmb` formats every backend's numbers as text.
+    #[test]
+    fn test_call_rmb() {
+        assert_eq!(
+            call("rmb", &[Value::Number(dec!(1234.5))]),
+            Ok(Value::Text("壹仟贰佰叁拾肆元伍角整".to_string()))
+        );
+        let third = Rational::from_literal("1")
+            .unwrap()
+            .div(&Rational::from_usize(3));
+        assert_eq!(
+            call("rmb", &[Value::Number(third.unwrap())]),
+            Ok(Value::Text("叁角叁分".to_string()))
+        );
+        assert_eq!(
+            call("rmb", &[Value::Number(1e30_f64)]),
+            Err(CalcError::Overflow)
+        );
+        assert!(call::<Decimal>("rmb", &[Value::Text("1".to_string())]).is_err());
+    }
+
+    /// Tests the complex functions on real and complex arguments, and
+    /// `sqrt` of a negative number with and without complex results.
+    #[test]
+    fn test_call_complex() {
+        let z = Value::Complex(Complex::new(dec!(-3), dec!(4)));
+        let n = |n: Decimal| Value::Number(n);
+        assert_eq!(call("re", std::slice::from_ref(&z)), Ok(n(dec!(-3))));
+        assert_eq!(call("im", std::slice::from_ref(&z)), Ok(n(dec!(4))));
+        assert_eq!(call("abs", std::slice::from_ref(&z)), Ok(n(dec!(5))));
+        assert_eq!(
+            call("conj", std::slice::from_ref(&z)),
+            Ok(Value::Complex(Complex::new(dec!(-3), dec!(-4))))
+        );
+        assert_eq!(call("im", &[n(dec!(2))]), Ok(n(dec!(0))));
+        assert_eq!(call("conj", &[n(dec!(2))]), Ok(n(dec!(2))));
+        assert_eq!(call("arg", &[n(dec!(-2))]), Ok(n(Decimal::PI)));
+        assert_eq!(call("arg", &[n(dec!(2))]), Ok(n(dec!(0))));
+        assert_eq!(
+            call("sqrt", &[n(dec!(-9))]),
+            Err(CalcError::ComplexResult("sqrt<-9>".to_string()))
+        );
+        let context = Context {
+            complex_numbers: true,
+            ..Context::default()
+        };
+        assert_eq!(
+            super::call("sqrt", &[n(dec!(-9))], &context),
+            Ok(Value::Complex(Complex::new(dec!(0), dec!(3))))
+        );
+        assert_eq!(super::call("sqrt", &[n(dec!(9))], &context), Ok(n(dec!(3))));
+    }
+
+    /// Tests the matrix functions, including numbers as 1×1 matrices and
+    /// dimension errors.
+    #[test]
+    fn test_call_matrix() {
+        let m = |rows: Vec<Vec<Decimal>>| Value::Matrix(Matrix::from_rows(rows).unwrap());
+        let a = m(vec![vec![dec!(2), dec!(1)], vec![dec!(1), dec!(3)]]);
+        let b = m(vec![vec![dec!(3), dec!(5)]]);
+        assert_eq!(
+            call("det", std::slice::from_ref(&a)),
+            Ok(Value::Number(dec!(5)))
+        );
+        assert_eq!(
+            call("transpose", std::slice::from_ref(&b)),
+            Ok(m(vec![vec![dec!(3)], vec![dec!(5)]]))
+        );
+        assert_eq!(
+            call("inv", std::slice::from_ref(&a)),
+            Ok(m(vec![
+                vec![dec!(0.6), dec!(-0.2)],
+                vec![dec!(-0.2), dec!(0.4)]
+            ]))
+        );
+        assert_eq!(
+            call("solve", &[a.clone(), b.clone()]),
+            Ok(m(vec![vec![dec!(0.8), dec!(1.4)]]))
+        );
+        assert_eq!(
+            call("dot", &[b.clone(), b.clone()]),
+            Ok(Value::Number(dec!(34)))
+        );
+        assert_eq!(
+            call("sum", std::slice::from_ref(&a)),
+            Ok(Value::Number(dec!(7)))
+        );
+        assert_eq!(
+            call("det", &[Value::Number(dec!(4))]),
+            Ok(Value::Number(dec!(4)))
+        );
+        assert_eq!(
+            call("dot", &[a, b]),
+            Err(CalcError::DimensionMismatch {
+                expected: "1×4".to_string(),
+                actual: "2×2".to_string()
+            })
+        );
+        assert_eq!(
+            call(
+                "inv",
+                &[m(vec![vec![dec!(1), dec!(2)], vec![dec!(2), dec!(4)]])]
+            ),
+            Err(CalcError::SingularMatrix)
+        );
+        assert_eq!(
+            call("det", &[Value::<Decimal>::Text("a".to_string())]),
+            Err(CalcError::TypeMismatch {
+                expected: ValueType::Matrix,
+                actual: ValueType::Text
+            })
+        );
+    }
+
+    /// Tests `format` with and without a locale, and with a bad pattern.
+    #[test]
+    fn test_call_format() {
+        let args = |pattern: &str| {
+            vec![
+                Value::Number(dec!(1234.5)),
+                Value::Text(pattern.to_string()),
+            ]
+        };
+        assert_eq!(
+            call("format", &args("#,##0.00")),
+            Ok(Value::Text("1,234.50".to_string()))
+        );
+        let mut german = args("#,##0.00");
+        german.push(Value::Text("de-DE".to_string()));
+        assert_eq!(
+            call("format", &german),
+            Ok(Value::Text("1.234,50".to_string()))
+        );
+        assert_eq!(
+            call("format", &args("abc")),
+            Err(CalcError::InvalidArgument {
+                function: "format".to_string(),
+                value: "abc".to_string()
+            })
+        );
+    }
+
+    /// Tests aggregates over ranges mixed with plain numbers.
+    #[test]
+    fn test_call_aggregates() {
+        let args = [
+            Value::Range(vec![dec!(1), dec!(2), dec!(6)]),
+            Value::Number(dec!(3)),
+        ];
+        assert_eq!(call("sum", &args), Ok(Value::Number(dec!(12))));
+        assert_eq!(call("avg", &args), Ok(Value::Number(dec!(3))));
+        assert_eq!(call("count", &args), Ok(Value::Number(dec!(4))));
+        assert_eq!(call("min", &args), Ok(Value::Number(dec!(1))));
+        assert_eq!(call("max", &args), Ok(Value::Number(dec!(6))));
+        assert_eq!(
+            call::<Decimal>("avg", &[Value::Range(vec![])]),
+            Err(CalcError::DivideByZero)
+        );
+        assert_eq!(
+            call::<Decimal>("abs", &[Value::Range(vec![])]),
+            Err(CalcError::TypeMismatch {
+                expected: ValueType::Number,
+                actual: ValueType::Range
+            })
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::calc::ast::Node;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Summary of an expression tree, computed in a single traversal.
+///
+/// Services use it to prefetch the inputs a formula needs and to reject
+/// formulas that are too complex before evaluating them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ExpressionStats {
+    /// The variables the expression reads, including those inside function arguments
+    pub variables: BTreeSet<String>,
+    /// The functions the expression calls, with the number of calls to each
+    pub functions: BTreeMap<String, usize>,
+    /// The depth of the tree; a single number or variable has depth 1
+    pub max_depth: usize,
+    /// The total number of nodes in the tree
+    pub node_count: usize,
+}
+
+impl Node {
+    /// Returns the set of free variables referenced by the expression.
+    ///
+    /// Variables used inside function arguments, such as `x` in `nvl<abs<x>, 0>`,
+    /// are included.
+    pub fn free_variables(&self) -> BTreeSet<String> {
+        self.stats().variables
+    }
+
+    /// Returns the functions called by the expression with their call counts.
+    pub fn function_calls(&self) -> BTreeMap<String, usize> {
+        self.stats().functions
+    }
+
+    /// Returns the depth of the expression tree.
+    pub fn max_depth(&self) -> usize {
+        self.stats().max_depth
+    }
+
+    /// Returns the number of nodes in the expression tree.
+    pub fn node_count(&self) -> usize {
+        self.stats().node_count
+    }
+
+    /// Collects variables, function calls, depth and node count in one pass.
+    ///
+    /// The tree is walked with an explicit stack, so a long operator chain
+    /// such as `1 + 2 + ... + n` does not recurse once per operator.
+    pub fn stats(&self) -> ExpressionStats {
+        let mut stats = ExpressionStats::default();
+        let mut pending = vec![(self, 1)];
+        while let Some((node, depth)) = pending.pop() {
+            stats.node_count += 1;
+            stats.max_depth = stats.max_depth.max(depth);
+            match node {
+                Node::Variable(name, _) => {
+                    stats.variables.insert(name.clone());
+                }
+                Node::Function {
+                    function_prefix, ..
+                } => {
+                    *stats.functions.entry(function_prefix.clone()).or_default() += 1;
+                }
+                _ => {}
+            }
+            pending.extend(node.children().into_iter().map(|child| (child, depth + 1)));
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::parser::Parser;
+
+    /// Tests that variables and calls nested in function arguments are found
+    /// and that depth and node count match the tree shape.
+    #[test]
+    fn test_stats() {
+        let node = Parser::new("a * nvl<abs<b - a>, max<c, abs<d>>> + 1")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let stats = node.stats();
+        assert_eq!(
+            stats.variables,
+            BTreeSet::from(["a", "b", "c", "d"].map(String::from))
+        );
+        assert_eq!(
+            stats.functions,
+            BTreeMap::from([
+                ("abs".to_string(), 2),
+                ("max".to_string(), 1),
+                ("nvl".to_string(), 1)
+            ])
+        );
+        // + -> * -> nvl -> max -> abs -> d
+        assert_eq!(stats.max_depth, 6);
+        assert_eq!(stats.node_count, 13);
+        assert_eq!(node.free_variables(), stats.variables);
+        assert_eq!(node.function_calls(), stats.functions);
+    }
+
+    /// Tests the statistics of a single literal.
+    #[test]
+    fn test_stats_literal() {
+        let node = Parser::new("42").unwrap().parse().unwrap();
+        assert!(node.free_variables().is_empty());
+        assert_eq!(node.max_depth(), 1);
+        assert_eq!(node.node_count(), 1);
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+/// Bounds on the work spent parsing and evaluating one expression.
+///
+/// Formulas may come from untrusted users, so every stage that grows with the
+/// input is bounded: the source length and token count before parsing, the
+/// nesting depth while parsing, and the number of evaluation steps and the
+/// wall-clock time while evaluating. `None` disables a limit.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::error::CalcError;
+/// use rust_calculate::calc::limits::Limits;
+/// use rust_calculate::calc::parser::Parser;
+///
+/// let limits = Limits {
+///     max_depth: Some(3),
+///     ..Limits::default()
+/// };
+/// let result = Parser::with_limits("((((1))))", &limits).and_then(|mut parser| parser.parse());
+/// assert_eq!(result, Err(CalcError::NestingTooDeep(3)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of characters in an expression
+    pub max_length: Option<usize>,
+    /// The maximum number of tokens in an expression
+    pub max_tokens: Option<usize>,
+    /// The maximum nesting of brackets, function calls, negations and
+    /// right-associative operators such as `^`; a flat chain such as
+    /// `1 + 2 + ... + n` is only bounded by `max_tokens`
+    pub max_depth: Option<usize>,
+    /// The maximum number of nodes evaluated for one expression, where every
+    /// cell of a range counts as one
+    pub max_steps: Option<usize>,
+    /// The maximum time one evaluation may take
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_length: Some(10_000),
+            max_tokens: Some(5_000),
+            max_depth: Some(100),
+            max_steps: None,
+            timeout: None,
+        }
+    }
+}
+
+impl Limits {
+    /// Returns limits that allow anything. Only use this for trusted input.
+    pub fn unlimited() -> Self {
+        Self {
+            max_length: None,
+            max_tokens: None,
+            max_depth: None,
+            max_steps: None,
+            timeout: None,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::calc::error::CalcError;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The language human-readable output is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    // 英文
+    #[default]
+    EnUs,
+    // 简体中文
+    ZhCn,
+}
+
+impl Locale {
+    /// Picks the supported locale closest to a language tag, such as one
+    /// from an `Accept-Language` header.
+    ///
+    /// Any Chinese tag, such as `zh-TW` or `zh-Hans`, selects Chinese, and
+    /// every other language falls back to English.
+    pub fn negotiate(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        if language.eq_ignore_ascii_case("zh") {
+            Self::ZhCn
+        } else {
+            Self::EnUs
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = CalcError;
+
+    /// Parses a language tag such as `en-US` or `zh-CN`; the region may be
+    /// omitted and case is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "en" | "en-us" => Ok(Self::EnUs),
+            "zh" | "zh-cn" => Ok(Self::ZhCn),
+            _ => Err(CalcError::InvalidArgument {
+                function: "locale".to_string(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::EnUs => "en-US",
+            Self::ZhCn => "zh-CN",
+        })
+    }
+}
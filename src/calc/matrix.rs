@@ -0,0 +1,461 @@
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::numeric::Numeric;
+use rust_decimal::Decimal;
+use std::fmt::{Display, Formatter};
+
+/// A matrix of numbers stored row by row, written `[1, 2; 3, 4]`.
+///
+/// A vector is a matrix with a single row or column. Operands whose shapes
+/// do not fit together are reported as `CalcError::DimensionMismatch` with
+/// the expected and actual shapes.
+///
+/// `det`, `inv` and `solve` use fraction-free Gauss-Jordan elimination, in
+/// which every intermediate division is exact for integer matrices, and
+/// divide by the determinant only once at the end. The inverse of an integer
+/// matrix is therefore exact whenever the backend can represent it.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::matrix::Matrix;
+/// use rust_decimal::dec;
+///
+/// let m = Matrix::from_rows(vec![vec![dec!(1), dec!(2)], vec![dec!(3), dec!(4)]]).unwrap();
+/// assert_eq!(m.det(), Ok(dec!(-2)));
+/// let inverse = Matrix::from_rows(vec![vec![dec!(-2), dec!(1)], vec![dec!(1.5), dec!(-0.5)]]);
+/// assert_eq!(m.inv(), inverse);
+/// assert_eq!(m.transpose().to_string(), "[1, 3; 2, 4]");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<N = Decimal> {
+    rows: usize,
+    cols: usize,
+    // 按行存储的元素
+    data: Vec<N>,
+}
+
+impl<N: Numeric> Matrix<N> {
+    /// Creates a matrix from its elements in row order.
+    ///
+    /// # Returns
+    ///
+    /// The matrix, or `CalcError::DimensionMismatch` if there are not
+    /// `rows * cols` elements
+    pub fn new(rows: usize, cols: usize, data: Vec<N>) -> CalcResult<Self> {
+        if rows * cols != data.len() {
+            return Err(CalcError::DimensionMismatch {
+                expected: shape(rows, cols),
+                actual: shape(1, data.len()),
+            });
+        }
+        Ok(Self { rows, cols, data })
+    }
+
+    /// Creates a matrix from its rows.
+    ///
+    /// # Returns
+    ///
+    /// The matrix, or `CalcError::DimensionMismatch` if a row is not as long
+    /// as the first one
+    pub fn from_rows(rows: Vec<Vec<N>>) -> CalcResult<Self> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if let Some(row) = rows.iter().find(|row| row.len() != cols) {
+            return Err(CalcError::DimensionMismatch {
+                expected: shape(1, cols),
+                actual: shape(1, row.len()),
+            });
+        }
+        Ok(Self {
+            rows: rows.len(),
+            cols,
+            data: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Creates the `n×n` identity matrix.
+    pub fn identity(n: usize) -> Self {
+        let data = (0..n * n)
+            .map(|i| N::from_usize((i / n == i % n) as usize))
+            .collect();
+        Self {
+            rows: n,
+            cols: n,
+            data,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the elements in row order.
+    pub fn elements(&self) -> &[N] {
+        &self.data
+    }
+
+    /// Returns the shape as written in error messages, such as `2×3`.
+    pub fn shape(&self) -> String {
+        shape(self.rows, self.cols)
+    }
+
+    /// Returns whether the matrix has a single row or column.
+    pub fn is_vector(&self) -> bool {
+        self.rows == 1 || self.cols == 1
+    }
+
+    /// Applies a fallible function to every element.
+    pub fn map(&self, f: impl FnMut(&N) -> CalcResult<N>) -> CalcResult<Self> {
+        Ok(Self {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().map(f).collect::<CalcResult<_>>()?,
+        })
+    }
+
+    /// Combines the elements of two matrices of the same shape one by one.
+    pub fn zip_with(
+        &self,
+        other: &Self,
+        mut f: impl FnMut(&N, &N) -> CalcResult<N>,
+    ) -> CalcResult<Self> {
+        if (self.rows, self.cols) != (other.rows, other.cols) {
+            return Err(CalcError::DimensionMismatch {
+                expected: self.shape(),
+                actual: other.shape(),
+            });
+        }
+        let data = self
+            .data
+            .iter()
+            .zip(&other.data)
+            .map(|(lhs, rhs)| f(lhs, rhs))
+            .collect::<CalcResult<_>>()?;
+        Ok(Self { data, ..*self })
+    }
+
+    /// Multiplies by another matrix, whose row count must equal this
+    /// matrix's column count.
+    pub fn mul(&self, other: &Self) -> CalcResult<Self> {
+        if self.cols != other.rows {
+            return Err(CalcError::DimensionMismatch {
+                expected: shape(self.cols, other.cols),
+                actual: other.shape(),
+            });
+        }
+        let mut data = Vec::with_capacity(self.rows * other.cols);
+        for row in 0..self.rows {
+            for col in 0..other.cols {
+                let sum = (0..self.cols).try_fold(N::zero(), |sum, k| {
+                    sum.add(&self.get(row, k).mul(other.get(k, col))?)
+                })?;
+                data.push(sum);
+            }
+        }
+        Ok(Self {
+            rows: self.rows,
+            cols: other.cols,
+            data,
+        })
+    }
+
+    pub fn transpose(&self) -> Self {
+        let data = (0..self.rows * self.cols)
+            .map(|i| self.get(i % self.rows, i / self.rows).clone())
+            .collect();
+        Self {
+            rows: self.cols,
+            cols: self.rows,
+            data,
+        }
+    }
+
+    /// Returns the dot product of two vectors of the same length, each of
+    /// which may be a row or a column.
+    pub fn dot(&self, other: &Self) -> CalcResult<N> {
+        for vector in [self, other] {
+            if !vector.is_vector() {
+                return Err(CalcError::DimensionMismatch {
+                    expected: shape(1, vector.data.len()),
+                    actual: vector.shape(),
+                });
+            }
+        }
+        if self.data.len() != other.data.len() {
+            let expected = if other.rows == 1 {
+                shape(1, self.data.len())
+            } else {
+                shape(self.data.len(), 1)
+            };
+            return Err(CalcError::DimensionMismatch {
+                expected,
+                actual: other.shape(),
+            });
+        }
+        self.data
+            .iter()
+            .zip(&other.data)
+            .try_fold(N::zero(), |sum, (lhs, rhs)| sum.add(&lhs.mul(rhs)?))
+    }
+
+    /// Returns the determinant of a square matrix.
+    pub fn det(&self) -> CalcResult<N> {
+        let n = self.check_square()?;
+        let mut rows = self.to_rows();
+        match eliminate(&mut rows, n)? {
+            Some((det, false)) => Ok(det),
+            Some((det, true)) => det.neg(),
+            None => Ok(N::zero()),
+        }
+    }
+
+    /// Returns the inverse of a square matrix.
+    ///
+    /// # Returns
+    ///
+    /// The inverse, or `CalcError::SingularMatrix` if the determinant is zero
+    pub fn inv(&self) -> CalcResult<Self> {
+        let n = self.check_square()?;
+        self.solve(&Self::identity(n))
+    }
+
+    /// Solves `self · x = rhs` for `x`.
+    ///
+    /// `rhs` is a matrix with as many rows as `self`, or a row vector with
+    /// as many elements, in which case `x` is a row vector too.
+    ///
+    /// # Returns
+    ///
+    /// The solution, `CalcError::SingularMatrix` if `self` has no inverse,
+    /// or `CalcError::DimensionMismatch` if the shapes do not fit
+    pub fn solve(&self, rhs: &Self) -> CalcResult<Self> {
+        let n = self.check_square()?;
+        // 行向量按列向量求解，结果再转回行向量
+        if rhs.rows == 1 && rhs.cols == n && n > 1 {
+            return Ok(self.solve(&rhs.transpose())?.transpose());
+        }
+        if rhs.rows != n {
+            return Err(CalcError::DimensionMismatch {
+                expected: shape(n, rhs.cols),
+                actual: rhs.shape(),
+            });
+        }
+        let mut rows: Vec<Vec<N>> = self
+            .to_rows()
+            .into_iter()
+            .zip(rhs.to_rows())
+            .map(|(mut row, rhs)| {
+                row.extend(rhs);
+                row
+            })
+            .collect();
+        let (det, _) = eliminate(&mut rows, n)?.ok_or(CalcError::SingularMatrix)?;
+        // 消元后左侧为 det·I，右侧为 det·x
+        let data = rows
+            .iter()
+            .flat_map(|row| &row[n..])
+            .map(|value| value.div(&det))
+            .collect::<CalcResult<_>>()?;
+        Ok(Self {
+            rows: n,
+            cols: rhs.cols,
+            data,
+        })
+    }
+
+    /// Raises a square matrix to an integer power; a negative power is a
+    /// power of the inverse.
+    pub fn pow(&self, exponent: i64) -> CalcResult<Self> {
+        let n = self.check_square()?;
+        let mut base = if exponent < 0 {
+            self.inv()?
+        } else {
+            self.clone()
+        };
+        let mut result = Self::identity(n);
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn get(&self, row: usize, col: usize) -> &N {
+        &self.data[row * self.cols + col]
+    }
+
+    fn to_rows(&self) -> Vec<Vec<N>> {
+        self.data
+            .chunks(self.cols.max(1))
+            .map(<[N]>::to_vec)
+            .collect()
+    }
+
+    /// Returns the size of a square matrix, or `CalcError::DimensionMismatch`.
+    fn check_square(&self) -> CalcResult<usize> {
+        if self.rows != self.cols {
+            return Err(CalcError::DimensionMismatch {
+                expected: shape(self.rows, self.rows),
+                actual: self.shape(),
+            });
+        }
+        Ok(self.rows)
+    }
+}
+
+impl<N: Numeric> Display for Matrix<N> {
+    /// Writes the matrix as a literal, such as `[1, 2; 3, 4]`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[")?;
+        for (i, value) in self.data.iter().enumerate() {
+            if i > 0 {
+                f.write_str(if i % self.cols == 0 { "; " } else { ", " })?;
+            }
+            write!(f, "{}", value)?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// Writes a shape such as `2×3`.
+fn shape(rows: usize, cols: usize) -> String {
+    format!("{rows}×{cols}")
+}
+
+/// Runs fraction-free Gauss-Jordan elimination on the first `n` columns.
+///
+/// Afterwards the left `n×n` block is `d·I`, where `d` is the determinant of
+/// the rows in their swapped order, and every other column has been
+/// multiplied by the adjugate.
+///
+/// # Returns
+///
+/// `d` and whether an odd number of rows were swapped, or `None` if the
+/// left block is singular
+fn eliminate<N: Numeric>(rows: &mut [Vec<N>], n: usize) -> CalcResult<Option<(N, bool)>> {
+    let width = rows.first().map_or(0, Vec::len);
+    let mut previous = N::from_usize(1);
+    let mut swapped = false;
+    for k in 0..n {
+        let Some(pivot) = (k..n).find(|&row| !rows[row][k].is_zero()) else {
+            return Ok(None);
+        };
+        if pivot != k {
+            rows.swap(pivot, k);
+            swapped = !swapped;
+        }
+        let (above, rest) = rows.split_at_mut(k);
+        let (pivot_row, below) = rest.split_first_mut().expect("k < n");
+        for row in above.iter_mut().chain(below.iter_mut()) {
+            // Bareiss 递推: (p·a - a_k·p_j) / 上一个主元，对整数矩阵总能整除
+            for j in (0..width).filter(|&j| j != k) {
+                let value = pivot_row[k]
+                    .mul(&row[j])?
+                    .sub(&row[k].mul(&pivot_row[j])?)?;
+                row[j] = value.div(&previous)?;
+            }
+            row[k] = N::zero();
+        }
+        previous = pivot_row[k].clone();
+    }
+    Ok(Some((previous, swapped)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::numeric::Rational;
+    use rust_decimal::dec;
+
+    fn matrix(rows: &[&[Decimal]]) -> Matrix {
+        Matrix::from_rows(rows.iter().map(|row| row.to_vec()).collect()).unwrap()
+    }
+
+    /// Tests products, transposes and dot products, and their shape errors.
+    #[test]
+    fn test_matrix_products() {
+        let a = matrix(&[&[dec!(1), dec!(2), dec!(3)], &[dec!(4), dec!(5), dec!(6)]]);
+        let b = matrix(&[&[dec!(1)], &[dec!(0)], &[dec!(-1)]]);
+        assert_eq!(a.mul(&b), Ok(matrix(&[&[dec!(-2)], &[dec!(-2)]])));
+        assert_eq!(
+            a.mul(&a),
+            Err(CalcError::DimensionMismatch {
+                expected: "3×3".to_string(),
+                actual: "2×3".to_string()
+            })
+        );
+        assert_eq!(a.transpose().shape(), "3×2");
+        assert_eq!(a.transpose().transpose(), a);
+        let row = matrix(&[&[dec!(1), dec!(2), dec!(3)]]);
+        assert_eq!(row.dot(&b), Ok(dec!(-2)));
+        assert!(matches!(
+            row.dot(&a),
+            Err(CalcError::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            Matrix::from_rows(vec![vec![dec!(1), dec!(2)], vec![dec!(3)]]),
+            Err(CalcError::DimensionMismatch { .. })
+        ));
+    }
+
+    /// Tests determinants, inverses and solutions, including row swaps and
+    /// singular matrices.
+    #[test]
+    fn test_matrix_elimination() {
+        let a = matrix(&[
+            &[dec!(0), dec!(2), dec!(1)],
+            &[dec!(1), dec!(1), dec!(0)],
+            &[dec!(2), dec!(0), dec!(3)],
+        ]);
+        assert_eq!(a.det(), Ok(dec!(-8)));
+        let inverse = a.inv().unwrap();
+        assert_eq!(inverse.mul(&a), Ok(Matrix::identity(3)));
+        assert_eq!(
+            inverse,
+            matrix(&[
+                &[dec!(-0.375), dec!(0.75), dec!(0.125)],
+                &[dec!(0.375), dec!(0.25), dec!(-0.125)],
+                &[dec!(0.25), dec!(-0.5), dec!(0.25)],
+            ])
+        );
+        let b = matrix(&[&[dec!(7), dec!(3), dec!(11)]]);
+        assert_eq!(a.solve(&b), Ok(matrix(&[&[dec!(1), dec!(2), dec!(3)]])));
+        assert_eq!(a.pow(-1), Ok(inverse));
+        assert_eq!(a.pow(0), Ok(Matrix::identity(3)));
+
+        let singular = matrix(&[&[dec!(1), dec!(2)], &[dec!(2), dec!(4)]]);
+        assert_eq!(singular.det(), Ok(dec!(0)));
+        assert_eq!(singular.inv(), Err(CalcError::SingularMatrix));
+        assert_eq!(
+            matrix(&[&[dec!(1), dec!(2)]]).det(),
+            Err(CalcError::DimensionMismatch {
+                expected: "1×1".to_string(),
+                actual: "1×2".to_string()
+            })
+        );
+
+        // 有理数后端的结果是精确的
+        let third = Rational::new(1, 3).unwrap();
+        let m = Matrix::new(
+            2,
+            2,
+            vec![
+                third,
+                Rational::from_usize(1),
+                Rational::from_usize(0),
+                third,
+            ],
+        )
+        .unwrap();
+        assert_eq!(m.inv().unwrap().elements()[0], Rational::from_usize(3));
+    }
+}
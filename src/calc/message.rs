@@ -0,0 +1,404 @@
+use crate::calc::error::CalcError;
+use crate::calc::locale::Locale;
+use crate::calc::value::ValueType;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The Chinese message catalog, keyed by error code.
+const ZH_CN: &[(&str, &str)] = &[
+    ("E001", "非法字符: {char}"),
+    ("E002", "无效运算符: {token}"),
+    ("E003", "意外的符号: {token}"),
+    ("E004", "缺少运算符: {token} 前"),
+    ("E005", "函数未闭合: {function}"),
+    ("E006", "未定义的变量: {name}"),
+    ("E007", "未知函数: {function}"),
+    (
+        "E008",
+        "函数 {function} 参数个数错误: 期望 {expected}, 实际 {actual}",
+    ),
+    ("E009", "无效数字: {number}"),
+    ("E010", "结果无法精确表示: {expression}"),
+    ("E011", "函数 {function} 参数无效: {value}"),
+    ("E012", "除数不能为零"),
+    ("E013", "数值溢出"),
+    ("E014", "数值下溢"),
+    ("E015", "未定义的单元格: {cell}"),
+    ("E016", "类型错误: 期望{expected}, 实际为{actual}"),
+    ("E017", "循环引用: {cycle}"),
+    ("E018", "错误值 {kind}"),
+    ("E019", "表达式过长: {length} 个字符, 上限 {limit}"),
+    ("E020", "符号过多: {count} 个, 上限 {limit}"),
+    ("E021", "嵌套层数超过上限 {limit}"),
+    ("E022", "求值步数超过上限 {limit}"),
+    ("E023", "求值超时: {timeout}"),
+    ("E024", "不允许使用: {name}"),
+    ("E025", "结果为复数: {expression}"),
+    ("E026", "维度不匹配: 期望 {expected}, 实际为 {actual}"),
+    ("E027", "矩阵不可逆"),
+    ("location", "{message} (位置 {span})"),
+    ("error", "错误"),
+    ("type.number", "数字"),
+    ("type.complex", "复数"),
+    ("type.matrix", "矩阵"),
+    ("type.range", "区域"),
+    ("type.text", "文本"),
+    ("type.null", "空值"),
+    ("type.error", "错误值"),
+];
+
+/// The English message catalog, keyed by error code.
+const EN_US: &[(&str, &str)] = &[
+    ("E001", "unexpected character: {char}"),
+    ("E002", "invalid operator: {token}"),
+    ("E003", "unexpected token: {token}"),
+    ("E004", "missing operator before {token}"),
+    ("E005", "unclosed function: {function}"),
+    ("E006", "undefined variable: {name}"),
+    ("E007", "unknown function: {function}"),
+    (
+        "E008",
+        "wrong number of arguments to {function}: expected {expected}, got {actual}",
+    ),
+    ("E009", "invalid number: {number}"),
+    ("E010", "result cannot be represented exactly: {expression}"),
+    ("E011", "invalid argument to {function}: {value}"),
+    ("E012", "division by zero"),
+    ("E013", "numeric overflow"),
+    ("E014", "numeric underflow"),
+    ("E015", "undefined cell: {cell}"),
+    ("E016", "type mismatch: expected {expected}, got {actual}"),
+    ("E017", "circular reference: {cycle}"),
+    ("E018", "error value {kind}"),
+    (
+        "E019",
+        "expression too long: {length} characters, limit {limit}",
+    ),
+    ("E020", "too many tokens: {count}, limit {limit}"),
+    ("E021", "nesting deeper than {limit} levels"),
+    ("E022", "more than {limit} evaluation steps"),
+    ("E023", "evaluation timed out after {timeout}"),
+    ("E024", "not allowed: {name}"),
+    ("E025", "result is a complex number: {expression}"),
+    (
+        "E026",
+        "dimension mismatch: expected {expected}, got {actual}",
+    ),
+    ("E027", "matrix is singular"),
+    ("location", "{message} (at {span})"),
+    ("error", "error"),
+    ("type.number", "number"),
+    ("type.complex", "complex number"),
+    ("type.matrix", "matrix"),
+    ("type.range", "range"),
+    ("type.text", "text"),
+    ("type.null", "null"),
+    ("type.error", "error value"),
+];
+
+// Display 使用的语言，默认中文
+static MESSAGE_LOCALE: AtomicU8 = AtomicU8::new(1);
+
+/// Sets the language `Display for CalcError` writes messages in.
+///
+/// The setting is process-wide and defaults to Chinese. To choose the
+/// language per request, call [`CalcError::message`] instead.
+pub fn set_message_locale(locale: Locale) {
+    let value = match locale {
+        Locale::EnUs => 0,
+        Locale::ZhCn => 1,
+    };
+    MESSAGE_LOCALE.store(value, Ordering::Relaxed);
+}
+
+/// Returns the language `Display for CalcError` writes messages in.
+pub fn message_locale() -> Locale {
+    match MESSAGE_LOCALE.load(Ordering::Relaxed) {
+        0 => Locale::EnUs,
+        _ => Locale::ZhCn,
+    }
+}
+
+/// Looks up a message template, falling back to English and then to the
+/// key itself when the catalog has no entry.
+fn template(key: &str, locale: Locale) -> &str {
+    let catalog = match locale {
+        Locale::EnUs => EN_US,
+        Locale::ZhCn => ZH_CN,
+    };
+    [catalog, EN_US]
+        .iter()
+        .find_map(|catalog| {
+            catalog
+                .iter()
+                .find(|(code, _)| *code == key)
+                .map(|(_, template)| *template)
+        })
+        .unwrap_or(key)
+}
+
+/// Replaces every `{name}` placeholder in a template with its argument.
+fn fill(template: &str, args: &[(&str, String)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+/// Translates a value type, as reported by `Value::value_type`.
+fn type_name(value_type: ValueType, locale: Locale) -> String {
+    let key = match value_type {
+        ValueType::Number => "type.number",
+        ValueType::Complex => "type.complex",
+        ValueType::Matrix => "type.matrix",
+        ValueType::Range => "type.range",
+        ValueType::Text => "type.text",
+        ValueType::Null => "type.null",
+        ValueType::Error => "type.error",
+    };
+    template(key, locale).to_string()
+}
+
+impl CalcError {
+    /// Returns the stable code of the error, such as `E012` for a division
+    /// by zero. The code of an error with a span is the code of the error
+    /// it wraps.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedChar(_) => "E001",
+            Self::InvalidOperator(_) => "E002",
+            Self::UnexpectedToken(_) => "E003",
+            Self::MissingOperator { .. } => "E004",
+            Self::UnclosedFunction(_) => "E005",
+            Self::UndefinedVariable(_) => "E006",
+            Self::UnknownFunction(_) => "E007",
+            Self::ArgumentCount { .. } => "E008",
+            Self::InvalidNumber(_) => "E009",
+            Self::InexactResult(_) => "E010",
+            Self::InvalidArgument { .. } => "E011",
+            Self::DivideByZero => "E012",
+            Self::Overflow => "E013",
+            Self::Underflow => "E014",
+            Self::UndefinedCell(_) => "E015",
+            Self::TypeMismatch { .. } => "E016",
+            Self::CircularReference(_) => "E017",
+            Self::At { error, .. } => error.code(),
+            Self::ErrorValue { .. } => "E018",
+            Self::InputTooLong { .. } => "E019",
+            Self::TooManyTokens { .. } => "E020",
+            Self::NestingTooDeep(_) => "E021",
+            Self::StepLimitExceeded(_) => "E022",
+            Self::Timeout(_) => "E023",
+            Self::Forbidden { .. } => "E024",
+            Self::ComplexResult(_) => "E025",
+            Self::DimensionMismatch { .. } => "E026",
+            Self::SingularMatrix => "E027",
+        }
+    }
+
+    /// Returns the message of the error in the given language, followed by
+    /// the position the error points at, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::error::CalcError;
+    /// use rust_calculate::calc::locale::Locale;
+    ///
+    /// let error = CalcError::UnexpectedChar('$');
+    /// assert_eq!(error.message(Locale::EnUs), "unexpected character: $");
+    /// assert_eq!(error.message(Locale::ZhCn), "非法字符: $");
+    /// ```
+    pub fn message(&self, locale: Locale) -> String {
+        let message = self.detail(locale);
+        match self.span() {
+            Some(span) => fill(
+                template("location", locale),
+                &[("message", message), ("span", span.to_string())],
+            ),
+            None => message,
+        }
+    }
+
+    /// Renders the error as a diagnostic: the code and message, followed by
+    /// the line of the source the error points at with the span underlined.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The expression the error came from
+    /// * `locale` - The language of the message
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::error::CalcError;
+    /// use rust_calculate::calc::locale::Locale;
+    /// use rust_calculate::calc::span::Span;
+    ///
+    /// let error = CalcError::DivideByZero.at(Span::new(4, 5));
+    /// assert_eq!(
+    ///     error.render("1 + 2 / 0", Locale::EnUs),
+    ///     "error[E012]: division by zero\n  1 + 2 / 0\n      ^\n"
+    /// );
+    /// ```
+    pub fn render(&self, source: &str, locale: Locale) -> String {
+        let mut text = format!(
+            "{}[{}]: {}\n",
+            template("error", locale),
+            self.code(),
+            self.detail(locale)
+        );
+        let Some(span) = self.span().filter(|span| span.start <= source.len()) else {
+            return text;
+        };
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[line_start..]
+            .find('\n')
+            .map_or(source.len(), |i| line_start + i);
+        let line = &source[line_start..line_end];
+        let prefix = source.get(line_start..span.start).unwrap_or("");
+        let marked = source.get(span.start..span.end.min(line_end)).unwrap_or("");
+        let _ = writeln!(text, "  {}", line);
+        let _ = writeln!(
+            text,
+            "  {}{}",
+            " ".repeat(display_width(prefix)),
+            "^".repeat(display_width(marked).max(1))
+        );
+        text
+    }
+
+    /// Returns the message of the error without its position.
+    fn detail(&self, locale: Locale) -> String {
+        let args = match self {
+            Self::UnexpectedChar(c) => vec![("char", c.to_string())],
+            Self::InvalidOperator(token) | Self::UnexpectedToken(token) => {
+                vec![("token", token.clone())]
+            }
+            Self::MissingOperator { token, .. } => vec![("token", token.clone())],
+            Self::UnclosedFunction(function) | Self::UnknownFunction(function) => {
+                vec![("function", function.clone())]
+            }
+            Self::UndefinedVariable(name) | Self::Forbidden { name, .. } => {
+                vec![("name", name.clone())]
+            }
+            Self::ArgumentCount {
+                function,
+                expected,
+                actual,
+            } => vec![
+                ("function", function.clone()),
+                ("expected", expected.clone()),
+                ("actual", actual.to_string()),
+            ],
+            Self::InvalidNumber(number) => vec![("number", number.clone())],
+            Self::InexactResult(expression) | Self::ComplexResult(expression) => {
+                vec![("expression", expression.clone())]
+            }
+            Self::InvalidArgument { function, value } => {
+                vec![("function", function.clone()), ("value", value.clone())]
+            }
+            Self::DivideByZero | Self::Overflow | Self::Underflow | Self::SingularMatrix => {
+                Vec::new()
+            }
+            Self::UndefinedCell(cell) => vec![("cell", cell.clone())],
+            Self::TypeMismatch { expected, actual } => vec![
+                ("expected", type_name(*expected, locale)),
+                ("actual", type_name(*actual, locale)),
+            ],
+            Self::DimensionMismatch { expected, actual } => {
+                vec![("expected", expected.clone()), ("actual", actual.clone())]
+            }
+            Self::CircularReference(cycle) => vec![("cycle", cycle.join(" -> "))],
+            Self::At { error, .. } => return error.detail(locale),
+            Self::ErrorValue { kind, .. } => vec![("kind", kind.to_string())],
+            Self::InputTooLong { length, limit } => {
+                vec![("length", length.to_string()), ("limit", limit.to_string())]
+            }
+            Self::TooManyTokens { count, limit } => {
+                vec![("count", count.to_string()), ("limit", limit.to_string())]
+            }
+            Self::NestingTooDeep(limit) | Self::StepLimitExceeded(limit) => {
+                vec![("limit", limit.to_string())]
+            }
+            Self::Timeout(timeout) => vec![("timeout", format!("{:?}", timeout))],
+        };
+        fill(template(self.code(), locale), &args)
+    }
+}
+
+/// Returns the number of terminal columns a string takes, counting East
+/// Asian wide characters, such as Chinese and full-width forms, as two.
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::span::Span;
+
+    /// Tests that every code has a message in both catalogs and that the
+    /// placeholders are filled in.
+    #[test]
+    fn test_catalogs() {
+        for (code, _) in ZH_CN {
+            assert!(EN_US.iter().any(|(key, _)| key == code), "{code}");
+        }
+        assert_eq!(ZH_CN.len(), EN_US.len());
+        let error = CalcError::TypeMismatch {
+            expected: ValueType::Number,
+            actual: ValueType::Range,
+        };
+        assert_eq!(error.code(), "E016");
+        assert_eq!(
+            error.message(Locale::EnUs),
+            "type mismatch: expected number, got range"
+        );
+        assert_eq!(
+            error.message(Locale::ZhCn),
+            "类型错误: 期望数字, 实际为区域"
+        );
+        let error = CalcError::DivideByZero.at(Span::new(2, 3));
+        assert_eq!(error.code(), "E012");
+        assert_eq!(error.message(Locale::EnUs), "division by zero (at 2..3)");
+        assert_eq!(error.to_string(), "除数不能为零 (位置 2..3)");
+        assert_eq!(
+            error.message(Locale::negotiate("zh-Hant-TW")),
+            "除数不能为零 (位置 2..3)"
+        );
+        assert_eq!(
+            error.message(Locale::negotiate("fr")),
+            "division by zero (at 2..3)"
+        );
+    }
+
+    /// Tests that diagnostics underline the span, counting Chinese
+    /// characters as two columns.
+    #[test]
+    fn test_render() {
+        let error = CalcError::Forbidden {
+            name: "工资".to_string(),
+            span: Span::new(9, 15),
+        };
+        assert_eq!(
+            error.render("单价 + 工资", Locale::ZhCn),
+            "错误[E024]: 不允许使用: 工资\n  单价 + 工资\n         ^^^^\n"
+        );
+        assert_eq!(
+            CalcError::Overflow.render("1", Locale::EnUs),
+            "error[E013]: numeric overflow\n"
+        );
+    }
+}
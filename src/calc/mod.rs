@@ -1,5 +1,25 @@
-mod ast;
-mod token;
-mod tokenizer;
-mod parser;
-mod error;
+pub mod ast;
+pub mod big;
+pub mod cell;
+pub mod chinese;
+pub mod complex;
+pub mod context;
+pub mod error;
+pub mod evaluator;
+pub mod format;
+pub mod function;
+pub mod introspect;
+pub mod limits;
+pub mod locale;
+pub mod matrix;
+pub mod message;
+pub mod numeric;
+pub mod parser;
+pub mod partial;
+pub mod sandbox;
+pub mod span;
+pub mod token;
+pub mod tokenizer;
+pub mod trace;
+pub mod value;
+pub mod workbook;
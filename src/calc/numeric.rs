@@ -0,0 +1,751 @@
+use crate::calc::context::RoundingMode;
+use crate::calc::error::{CalcError, CalcResult};
+use rust_decimal::{Decimal, MathematicalOps};
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+
+/// A number type the evaluator can compute with.
+///
+/// `Decimal` is the default backend. `f64` trades exactness for speed and
+/// range, and `Rational` keeps every quotient exact so `1 / 3 * 3` is exactly
+/// `1`. All operations are checked and report failures as `CalcError`
+/// instead of panicking.
+pub trait Numeric: Clone + PartialEq + PartialOrd + Debug + Display {
+    /// Parses a numeric literal such as `42` or `1.25`.
+    fn from_literal(literal: &str) -> CalcResult<Self>;
+
+    /// Converts a `Decimal`, such as a parsed literal or a cell value.
+    fn from_decimal(value: Decimal) -> CalcResult<Self> {
+        Self::from_literal(&value.to_string())
+    }
+
+    fn from_usize(value: usize) -> Self;
+
+    /// Returns the value as an `i64` if it is an integer in range.
+    fn to_i64(&self) -> Option<i64>;
+
+    /// Converts the value to a `Decimal`, for formatting that only works
+    /// on decimals.
+    ///
+    /// # Returns
+    ///
+    /// `CalcError::Overflow` if the value does not fit into a `Decimal`
+    fn to_decimal(&self) -> CalcResult<Decimal> {
+        self.to_string().parse().map_err(|_| CalcError::Overflow)
+    }
+
+    fn zero() -> Self {
+        Self::from_usize(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    /// Returns the largest finite value of the backend, used when
+    /// saturating an overflow.
+    fn max_value() -> Self;
+
+    fn add(&self, rhs: &Self) -> CalcResult<Self>;
+
+    fn sub(&self, rhs: &Self) -> CalcResult<Self>;
+
+    fn mul(&self, rhs: &Self) -> CalcResult<Self>;
+
+    /// Divides, reporting `CalcError::DivideByZero` for a zero divisor.
+    fn div(&self, rhs: &Self) -> CalcResult<Self>;
+
+    /// Divides and rounds the quotient toward negative infinity, like
+    /// `7 // 2 = 3` and `-7 // 2 = -4`.
+    fn div_floor(&self, rhs: &Self) -> CalcResult<Self> {
+        self.div(rhs)?.round(0, RoundingMode::Floor)
+    }
+
+    /// Returns the remainder of [`div_floor`](Numeric::div_floor), which
+    /// has the sign of the divisor as in spreadsheets: `-7 % 3 = 2`.
+    fn rem_floor(&self, rhs: &Self) -> CalcResult<Self> {
+        self.sub(&rhs.mul(&self.div_floor(rhs)?)?)
+    }
+
+    fn pow(&self, exponent: &Self) -> CalcResult<Self>;
+
+    fn neg(&self) -> CalcResult<Self>;
+
+    fn abs(&self) -> CalcResult<Self>;
+
+    fn sqrt(&self) -> CalcResult<Self>;
+
+    /// Rounds to `scale` decimal places.
+    fn round(&self, scale: u32, mode: RoundingMode) -> CalcResult<Self>;
+}
+
+impl Numeric for Decimal {
+    fn from_literal(literal: &str) -> CalcResult<Self> {
+        // 拒绝需要舍入的字面量，而不是悄悄丢失精度
+        Decimal::from_str_exact(literal).map_err(|_| CalcError::InvalidNumber(literal.to_string()))
+    }
+
+    fn from_decimal(value: Decimal) -> CalcResult<Self> {
+        Ok(value)
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Decimal::from(value)
+    }
+
+    fn max_value() -> Self {
+        Decimal::MAX
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.fract().is_zero() {
+            rust_decimal::prelude::ToPrimitive::to_i64(self)
+        } else {
+            None
+        }
+    }
+
+    fn to_decimal(&self) -> CalcResult<Decimal> {
+        Ok(*self)
+    }
+
+    fn add(&self, rhs: &Self) -> CalcResult<Self> {
+        self.checked_add(*rhs).ok_or(CalcError::Overflow)
+    }
+
+    fn sub(&self, rhs: &Self) -> CalcResult<Self> {
+        self.checked_sub(*rhs).ok_or(CalcError::Overflow)
+    }
+
+    fn mul(&self, rhs: &Self) -> CalcResult<Self> {
+        let product = self.checked_mul(*rhs).ok_or(CalcError::Overflow)?;
+        underflow(product, !self.is_zero() && !rhs.is_zero())
+    }
+
+    fn div(&self, rhs: &Self) -> CalcResult<Self> {
+        if rhs.is_zero() {
+            return Err(CalcError::DivideByZero);
+        }
+        let quotient = self.checked_div(*rhs).ok_or(CalcError::Overflow)?;
+        underflow(quotient, !self.is_zero())
+    }
+
+    fn div_floor(&self, rhs: &Self) -> CalcResult<Self> {
+        // 减去余数后恰好整除，避免商在第 28 位舍入
+        let remainder = self.rem_floor(rhs)?;
+        let dividend = self.checked_sub(remainder).ok_or(CalcError::Overflow)?;
+        Ok(dividend
+            .checked_div(*rhs)
+            .ok_or(CalcError::Overflow)?
+            .floor())
+    }
+
+    fn rem_floor(&self, rhs: &Self) -> CalcResult<Self> {
+        let remainder = self.checked_rem(*rhs).ok_or(CalcError::DivideByZero)?;
+        if !remainder.is_zero() && remainder.is_sign_negative() != rhs.is_sign_negative() {
+            Ok(remainder + rhs)
+        } else {
+            Ok(remainder)
+        }
+    }
+
+    /// Raises to a power with checked arithmetic.
+    ///
+    /// Integer exponents are computed by repeated squaring, half-integer
+    /// exponents through the square root and any other exponent as
+    /// `exp(exponent * ln(self))`. A negative base with a fractional
+    /// exponent has a complex result and is reported as
+    /// `CalcError::ComplexResult`; `0` to a negative power is a division by
+    /// zero.
+    fn pow(&self, exponent: &Self) -> CalcResult<Self> {
+        if self.is_zero() {
+            return match exponent.cmp(&Decimal::ZERO) {
+                Ordering::Less => Err(CalcError::DivideByZero),
+                Ordering::Equal => Ok(Decimal::ONE),
+                Ordering::Greater => Ok(Decimal::ZERO),
+            };
+        }
+        if exponent.fract().is_zero() {
+            return integer_pow(*self, *exponent);
+        }
+        if self.is_sign_negative() {
+            return Err(CalcError::ComplexResult(format!("{} ^ {}", self, exponent)));
+        }
+        // 半整数指数借助平方根计算，例如 4^0.5 恰好为 2
+        if let Some(doubled) = exponent.checked_mul(Decimal::TWO)
+            && doubled.fract().is_zero()
+        {
+            let root = MathematicalOps::sqrt(self).ok_or(CalcError::Overflow)?;
+            return integer_pow(root, doubled);
+        }
+        // 结果的数量级由 exponent * ln(self) 的符号决定
+        let out_of_range = || {
+            if (*self > Decimal::ONE) == exponent.is_sign_positive() {
+                CalcError::Overflow
+            } else {
+                CalcError::Underflow
+            }
+        };
+        let power = self
+            .checked_ln()
+            .and_then(|ln| ln.checked_mul(*exponent))
+            .ok_or_else(out_of_range)?;
+        let result = power
+            .checked_exp_with_tolerance(Decimal::new(1, 28))
+            .ok_or_else(out_of_range)?;
+        underflow(result, true)
+    }
+
+    fn neg(&self) -> CalcResult<Self> {
+        Ok(-*self)
+    }
+
+    fn abs(&self) -> CalcResult<Self> {
+        Ok(Decimal::abs(self))
+    }
+
+    fn sqrt(&self) -> CalcResult<Self> {
+        MathematicalOps::sqrt(self).ok_or(CalcError::ComplexResult(format!("sqrt<{}>", self)))
+    }
+
+    fn round(&self, scale: u32, mode: RoundingMode) -> CalcResult<Self> {
+        Ok(self.round_dp_with_strategy(scale, mode.to_strategy()))
+    }
+}
+
+/// Raises a non-zero `Decimal` to an integer power.
+fn integer_pow(base: Decimal, exponent: Decimal) -> CalcResult<Decimal> {
+    // 底数绝对值大于 1 且指数为正，或小于 1 且指数为负时结果过大，否则过小
+    let out_of_range = || {
+        if (base.abs() > Decimal::ONE) == exponent.is_sign_positive() {
+            CalcError::Overflow
+        } else {
+            CalcError::Underflow
+        }
+    };
+    let Some(n) = Numeric::to_i64(&exponent) else {
+        // 指数超出 i64 时，只有 ±1 的幂既不溢出也不下溢
+        if base.abs() == Decimal::ONE {
+            let odd = !(exponent % Decimal::TWO).is_zero();
+            return Ok(if base.is_sign_negative() && odd {
+                Decimal::NEGATIVE_ONE
+            } else {
+                Decimal::ONE
+            });
+        }
+        return Err(out_of_range());
+    };
+    underflow(base.checked_powi(n).ok_or_else(out_of_range)?, true)
+}
+
+/// 非零操作数的乘除结果被舍入为 0 时视为下溢
+fn underflow<N: Numeric>(result: N, nonzero_operands: bool) -> CalcResult<N> {
+    if nonzero_operands && result.is_zero() {
+        Err(CalcError::Underflow)
+    } else {
+        Ok(result)
+    }
+}
+
+/// 结果为 NaN 或无穷大时视为溢出
+fn finite(value: f64) -> CalcResult<f64> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CalcError::Overflow)
+    }
+}
+
+impl Numeric for f64 {
+    fn from_literal(literal: &str) -> CalcResult<Self> {
+        literal
+            .parse()
+            .map_err(|_| CalcError::InvalidNumber(literal.to_string()))
+    }
+
+    fn from_usize(value: usize) -> Self {
+        value as f64
+    }
+
+    fn max_value() -> Self {
+        f64::MAX
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.fract() == 0.0 && f64::abs(*self) < i64::MAX as f64 {
+            Some(*self as i64)
+        } else {
+            None
+        }
+    }
+
+    fn add(&self, rhs: &Self) -> CalcResult<Self> {
+        finite(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> CalcResult<Self> {
+        finite(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> CalcResult<Self> {
+        underflow(finite(self * rhs)?, *self != 0.0 && *rhs != 0.0)
+    }
+
+    fn div(&self, rhs: &Self) -> CalcResult<Self> {
+        if *rhs == 0.0 {
+            return Err(CalcError::DivideByZero);
+        }
+        underflow(finite(self / rhs)?, *self != 0.0)
+    }
+
+    fn pow(&self, exponent: &Self) -> CalcResult<Self> {
+        if *self < 0.0 && exponent.fract() != 0.0 {
+            return Err(CalcError::ComplexResult(format!("{} ^ {}", self, exponent)));
+        }
+        if *self == 0.0 && *exponent < 0.0 {
+            return Err(CalcError::DivideByZero);
+        }
+        underflow(finite(self.powf(*exponent))?, *self != 0.0)
+    }
+
+    fn neg(&self) -> CalcResult<Self> {
+        Ok(-self)
+    }
+
+    fn abs(&self) -> CalcResult<Self> {
+        Ok(f64::abs(*self))
+    }
+
+    fn sqrt(&self) -> CalcResult<Self> {
+        if *self < 0.0 {
+            return Err(CalcError::ComplexResult(format!("sqrt<{}>", self)));
+        }
+        Ok(f64::sqrt(*self))
+    }
+
+    fn round(&self, scale: u32, mode: RoundingMode) -> CalcResult<Self> {
+        // 借助 Decimal 按十进制舍入；超出 Decimal 范围的数没有小数部分可舍
+        match Decimal::try_from(*self) {
+            Ok(value) => Ok(rust_decimal::prelude::ToPrimitive::to_f64(&Numeric::round(
+                &value, scale, mode,
+            )?)
+            .unwrap_or(*self)),
+            Err(_) => Ok(*self),
+        }
+    }
+}
+
+/// An exact fraction of two `i128` values.
+///
+/// The fraction is always reduced and its denominator is positive, so equal
+/// values have equal representations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+
+impl Rational {
+    /// Creates a reduced fraction.
+    ///
+    /// # Returns
+    ///
+    /// The fraction, or `CalcError::DivideByZero` if `denom` is zero
+    pub fn new(numer: i128, denom: i128) -> CalcResult<Self> {
+        if denom == 0 {
+            return Err(CalcError::DivideByZero);
+        }
+        // 符号统一放在分子上
+        let negative = (numer < 0) != (denom < 0);
+        let gcd = gcd(numer.unsigned_abs(), denom.unsigned_abs());
+        let magnitude =
+            i128::try_from(numer.unsigned_abs() / gcd).map_err(|_| CalcError::Overflow)?;
+        let denom = i128::try_from(denom.unsigned_abs() / gcd).map_err(|_| CalcError::Overflow)?;
+        Ok(Self {
+            numer: if negative { -magnitude } else { magnitude },
+            denom,
+        })
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+/// 返回整数平方根，若不是完全平方数则返回 None
+fn exact_sqrt(value: i128) -> Option<i128> {
+    if value < 0 {
+        return None;
+    }
+    // 整数平方根，避免浮点估计在 2^106 以上失准
+    let root = value.isqrt();
+    (root * root == value).then_some(root)
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // 分母均为正数，交叉相乘比较；溢出时退化为浮点比较
+        match (
+            self.numer.checked_mul(other.denom),
+            other.numer.checked_mul(self.denom),
+        ) {
+            (Some(lhs), Some(rhs)) => lhs.cmp(&rhs),
+            _ => {
+                let lhs = self.numer as f64 / self.denom as f64;
+                let rhs = other.numer as f64 / other.denom as f64;
+                lhs.total_cmp(&rhs)
+            }
+        }
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+impl Numeric for Rational {
+    fn from_literal(literal: &str) -> CalcResult<Self> {
+        let invalid = || CalcError::InvalidNumber(literal.to_string());
+        let (negative, digits) = match literal.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, literal),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        // 1.25 -> 125 / 100
+        let numer: i128 = format!("{integer}{fraction}")
+            .parse()
+            .map_err(|_| CalcError::Overflow)?;
+        let denom = u32::try_from(fraction.len())
+            .ok()
+            .and_then(|scale| 10i128.checked_pow(scale))
+            .ok_or(CalcError::Overflow)?;
+        Self::new(if negative { -numer } else { numer }, denom)
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Self {
+            numer: value as i128,
+            denom: 1,
+        }
+    }
+
+    fn max_value() -> Self {
+        Self {
+            numer: i128::MAX,
+            denom: 1,
+        }
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.is_integer() {
+            i64::try_from(self.numer).ok()
+        } else {
+            None
+        }
+    }
+
+    fn to_decimal(&self) -> CalcResult<Decimal> {
+        let part = |value: i128| {
+            Decimal::try_from_i128_with_scale(value, 0).map_err(|_| CalcError::Overflow)
+        };
+        part(self.numer)?
+            .checked_div(part(self.denom)?)
+            .ok_or(CalcError::Overflow)
+    }
+
+    fn add(&self, rhs: &Self) -> CalcResult<Self> {
+        let numer = self
+            .numer
+            .checked_mul(rhs.denom)
+            .zip(rhs.numer.checked_mul(self.denom))
+            .and_then(|(lhs, rhs)| lhs.checked_add(rhs))
+            .ok_or(CalcError::Overflow)?;
+        let denom = self
+            .denom
+            .checked_mul(rhs.denom)
+            .ok_or(CalcError::Overflow)?;
+        Self::new(numer, denom)
+    }
+
+    fn sub(&self, rhs: &Self) -> CalcResult<Self> {
+        self.add(&rhs.neg()?)
+    }
+
+    fn mul(&self, rhs: &Self) -> CalcResult<Self> {
+        // 先约分再相乘，减少溢出的可能
+        let a = gcd(self.numer.unsigned_abs(), rhs.denom.unsigned_abs()) as i128;
+        let b = gcd(rhs.numer.unsigned_abs(), self.denom.unsigned_abs()) as i128;
+        let numer = (self.numer / a)
+            .checked_mul(rhs.numer / b)
+            .ok_or(CalcError::Overflow)?;
+        let denom = (self.denom / b)
+            .checked_mul(rhs.denom / a)
+            .ok_or(CalcError::Overflow)?;
+        Self::new(numer, denom)
+    }
+
+    fn div(&self, rhs: &Self) -> CalcResult<Self> {
+        if rhs.numer == 0 {
+            return Err(CalcError::DivideByZero);
+        }
+        self.mul(&Self::new(rhs.denom, rhs.numer)?)
+    }
+
+    fn pow(&self, exponent: &Self) -> CalcResult<Self> {
+        if !exponent.is_integer() {
+            let expression = format!("{} ^ {}", self, exponent);
+            return Err(if self.numer < 0 {
+                CalcError::ComplexResult(expression)
+            } else {
+                CalcError::InexactResult(expression)
+            });
+        }
+        let base = if exponent.numer < 0 {
+            Self::from_usize(1).div(self)?
+        } else {
+            *self
+        };
+        let exponent =
+            u32::try_from(exponent.numer.unsigned_abs()).map_err(|_| CalcError::Overflow)?;
+        Ok(Self {
+            numer: base
+                .numer
+                .checked_pow(exponent)
+                .ok_or(CalcError::Overflow)?,
+            denom: base
+                .denom
+                .checked_pow(exponent)
+                .ok_or(CalcError::Overflow)?,
+        })
+    }
+
+    fn neg(&self) -> CalcResult<Self> {
+        Ok(Self {
+            numer: self.numer.checked_neg().ok_or(CalcError::Overflow)?,
+            denom: self.denom,
+        })
+    }
+
+    fn abs(&self) -> CalcResult<Self> {
+        Ok(Self {
+            numer: self.numer.checked_abs().ok_or(CalcError::Overflow)?,
+            denom: self.denom,
+        })
+    }
+
+    fn sqrt(&self) -> CalcResult<Self> {
+        if self.numer < 0 {
+            return Err(CalcError::ComplexResult(format!("sqrt<{}>", self)));
+        }
+        // 只有分子、分母都是完全平方数时结果才能精确表示
+        match (exact_sqrt(self.numer), exact_sqrt(self.denom)) {
+            (Some(numer), Some(denom)) => Self::new(numer, denom),
+            _ => Err(CalcError::InexactResult(format!("sqrt<{}>", self))),
+        }
+    }
+
+    fn round(&self, scale: u32, mode: RoundingMode) -> CalcResult<Self> {
+        let unit = 10i128.checked_pow(scale).ok_or(CalcError::Overflow)?;
+        let scaled = self.numer.checked_mul(unit).ok_or(CalcError::Overflow)?;
+        let (quotient, remainder) = (scaled / self.denom, scaled % self.denom);
+        let half = (remainder.unsigned_abs() * 2).cmp(&self.denom.unsigned_abs());
+        let away = mode.rounds_away(scaled < 0, half, remainder == 0, quotient % 2 != 0);
+        let quotient = if away {
+            quotient + scaled.signum()
+        } else {
+            quotient
+        };
+        Self::new(quotient, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn rational(numer: i128, denom: i128) -> Rational {
+        Rational::new(numer, denom).unwrap()
+    }
+
+    /// Tests that fractions are reduced and keep their sign on the numerator.
+    #[test]
+    fn test_rational_new() {
+        assert_eq!(rational(2, -4), rational(-1, 2));
+        assert_eq!(rational(-3, -9).to_string(), "1/3");
+        assert_eq!(rational(0, -5), Rational::zero());
+        assert_eq!(Rational::new(1, 0), Err(CalcError::DivideByZero));
+    }
+
+    /// Tests exact rational arithmetic and literal parsing.
+    #[test]
+    fn test_rational_arithmetic() {
+        let third = Rational::from_usize(1)
+            .div(&Rational::from_usize(3))
+            .unwrap();
+        assert_eq!(
+            third.mul(&Rational::from_usize(3)),
+            Ok(Rational::from_usize(1))
+        );
+        assert_eq!(Rational::from_literal("-1.25"), Ok(rational(-5, 4)));
+        assert_eq!(Rational::from_decimal(dec!(0.1)), Ok(rational(1, 10)));
+        assert_eq!(rational(2, 3).pow(&rational(-2, 1)), Ok(rational(9, 4)));
+        assert_eq!(rational(9, 4).sqrt(), Ok(rational(3, 2)));
+        assert!(rational(1, 3) < rational(1, 2));
+        assert!(matches!(
+            rational(2, 1).sqrt(),
+            Err(CalcError::InexactResult(_))
+        ));
+        let root = 1_000_000_000_000_000_007;
+        assert_eq!(rational(root * root, 1).sqrt(), Ok(rational(root, 1)));
+        assert!(matches!(
+            rational(root * root + 1, 1).sqrt(),
+            Err(CalcError::InexactResult(_))
+        ));
+        assert!(matches!(
+            rational(2, 1).pow(&rational(1, 2)),
+            Err(CalcError::InexactResult(_))
+        ));
+        assert_eq!(
+            Rational::from_usize(10).pow(&Rational::from_usize(40)),
+            Err(CalcError::Overflow)
+        );
+        assert_eq!(
+            rational(-7, 2).div_floor(&rational(1, 1)),
+            Ok(rational(-4, 1))
+        );
+        assert_eq!(
+            rational(-7, 2).rem_floor(&rational(1, 1)),
+            Ok(rational(1, 2))
+        );
+    }
+
+    /// Tests floored division and remainder on `Decimal`, which take the
+    /// sign of the divisor, and a zero divisor.
+    #[test]
+    fn test_decimal_floor_division() {
+        for (lhs, rhs, quotient, remainder) in [
+            (dec!(7), dec!(2), dec!(3), dec!(1)),
+            (dec!(-7), dec!(2), dec!(-4), dec!(1)),
+            (dec!(7), dec!(-3), dec!(-3), dec!(-2)),
+            (dec!(-7), dec!(-3), dec!(2), dec!(-1)),
+            (dec!(5.5), dec!(2), dec!(2), dec!(1.5)),
+            (dec!(6), dec!(3), dec!(2), dec!(0)),
+        ] {
+            assert_eq!(lhs.div_floor(&rhs), Ok(quotient), "{lhs} // {rhs}");
+            assert_eq!(lhs.rem_floor(&rhs), Ok(remainder), "{lhs} % {rhs}");
+        }
+        assert_eq!(dec!(1).rem_floor(&dec!(0)), Err(CalcError::DivideByZero));
+        assert_eq!(dec!(1).div_floor(&dec!(0)), Err(CalcError::DivideByZero));
+    }
+
+    /// Tests `Decimal` powers with integer, negative and fractional
+    /// exponents and their errors.
+    #[test]
+    fn test_decimal_pow() {
+        for (base, exponent, expected) in [
+            (dec!(2), dec!(10), dec!(1024)),
+            (dec!(-2), dec!(3), dec!(-8)),
+            (dec!(2), dec!(-3), dec!(0.125)),
+            (dec!(4), dec!(0.5), dec!(2)),
+            (dec!(4), dec!(-1.5), dec!(0.125)),
+            (dec!(2), dec!(0.5), dec!(1.4142135623730950488016887242)),
+            (dec!(8), dec!(1) / dec!(3), dec!(2)),
+            (dec!(0), dec!(0), dec!(1)),
+            (dec!(0), dec!(2.5), dec!(0)),
+            (dec!(-1), dec!(1e20), dec!(1)),
+        ] {
+            let result = base.pow(&exponent).unwrap();
+            assert_eq!(
+                result.round_dp(20),
+                expected.round_dp(20),
+                "{base} ^ {exponent}"
+            );
+        }
+        assert_eq!(dec!(0).pow(&dec!(-1)), Err(CalcError::DivideByZero));
+        assert_eq!(dec!(2).pow(&dec!(100)), Err(CalcError::Overflow));
+        assert_eq!(dec!(2).pow(&dec!(100.5)), Err(CalcError::Overflow));
+        assert_eq!(dec!(0.5).pow(&dec!(1000.3)), Err(CalcError::Underflow));
+        assert_eq!(dec!(10).pow(&dec!(-30)), Err(CalcError::Underflow));
+        assert_eq!(dec!(2).pow(&dec!(-100)), Err(CalcError::Underflow));
+        assert_eq!(dec!(0.5).pow(&dec!(-100)), Err(CalcError::Overflow));
+        assert_eq!(dec!(10).pow(&dec!(1e20)), Err(CalcError::Overflow));
+        assert_eq!(
+            dec!(-8).pow(&dec!(0.5)),
+            Err(CalcError::ComplexResult("-8 ^ 0.5".to_string()))
+        );
+        assert_eq!(
+            Numeric::sqrt(&dec!(-4)),
+            Err(CalcError::ComplexResult("sqrt<-4>".to_string()))
+        );
+    }
+
+    /// Tests that Decimal literals are rejected rather than rounded when
+    /// they do not fit.
+    #[test]
+    fn test_decimal_from_literal() {
+        assert_eq!(Decimal::from_literal("-1.25"), Ok(dec!(-1.25)));
+        for literal in [
+            "0.0000000000000000000000000000000001",
+            "1.00000000000000000000000000001",
+            "123456789012345678901234567890",
+        ] {
+            assert_eq!(
+                Decimal::from_literal(literal),
+                Err(CalcError::InvalidNumber(literal.to_string()))
+            );
+        }
+    }
+
+    /// Tests the checked floating-point backend.
+    #[test]
+    fn test_f64() {
+        assert_eq!(f64::from_literal("1.5").unwrap().mul(&2.0), Ok(3.0));
+        assert_eq!(1.0f64.div(&0.0), Err(CalcError::DivideByZero));
+        assert_eq!(10f64.pow(&400.0), Err(CalcError::Overflow));
+        assert_eq!(0f64.pow(&-1.0), Err(CalcError::DivideByZero));
+        assert!(matches!(
+            (-8f64).pow(&0.5),
+            Err(CalcError::ComplexResult(_))
+        ));
+        assert!(matches!(
+            Numeric::sqrt(&-1f64),
+            Err(CalcError::ComplexResult(_))
+        ));
+    }
+}
@@ -1,35 +1,158 @@
-use crate::calc::ast::Node;
+use crate::calc::ast::{BinaryOp, Node, PostfixOp};
 use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::function;
+use crate::calc::limits::Limits;
+use crate::calc::span::Span;
 use crate::calc::token::{OperatorPrecedence, Token};
 use crate::calc::tokenizer::Tokenizer;
 
-pub struct Parser<'a> {
-    tokenizer: Tokenizer<'a>,
+/// Settings that control how an expression is parsed.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::parser::{ParseOptions, Parser};
+///
+/// let options = ParseOptions {
+///     full_width: true,
+///     ..ParseOptions::default()
+/// };
+/// let node = Parser::with_options("（１＋２）×３", &options).unwrap().parse().unwrap();
+/// assert_eq!(node.to_string(), "(1 + 2) * 3");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// The length, token and nesting limits
+    pub limits: Limits,
+    /// Whether punctuation, operators and digits typed with a Chinese input
+    /// method, such as `（`, `，`, `×` or `１`, are read as their ASCII forms
+    pub full_width: bool,
+    /// Whether words made of Chinese numerals, such as `一百二十`, are read
+    /// as numbers instead of variables
+    pub chinese_numerals: bool,
+    /// Whether adjacent operands, as in `2x`, `3(a + b)` or `(a)(b)`, are
+    /// multiplied as if a `*` stood between them
+    pub implicit_multiplication: bool,
+    /// Whether `%` divides the operand before it by 100, as in `15%`,
+    /// instead of taking the remainder of a division, as in `7 % 3`
+    pub postfix_percent: bool,
+    /// Whether a number followed by `i`, as in `3 + 4i`, is read as an
+    /// imaginary literal
+    pub complex_numbers: bool,
+    /// Whether A1-style references such as `B12` or `A1:C10` are read as
+    /// cells and ranges instead of variables
+    pub cell_references: bool,
+}
+
+pub struct Parser {
+    tokens: std::vec::IntoIter<(Token, Span)>,
     current_token: Token,
+    current_span: Span,
+    max_depth: Option<usize>,
+    // 当前 parse_expression 的递归层数
+    depth: usize,
+    implicit_multiplication: bool,
+    postfix_percent: bool,
 }
 
+impl Parser {
+    /// Creates a parser with the default `ParseOptions`.
+    pub fn new(expression: &str) -> CalcResult<Self> {
+        Self::with_options(expression, &ParseOptions::default())
+    }
 
-impl<'a> Parser<'a> {
-    pub fn new(expression: &'a str) -> CalcResult<Self> {
-        let mut tokenizer = Tokenizer::new(expression);
-        let current_token = tokenizer
-            .next()
-            .ok_or_else(|| CalcError::UnexpectedChar(tokenizer.get_unexpected_char().unwrap()))?;
+    /// Creates a parser that enforces the length, token and nesting limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - The expression to parse
+    /// * `limits` - The limits; the step budget and timeout are only used
+    ///   when evaluating
+    ///
+    /// # Returns
+    ///
+    /// The parser, or an error if the expression is too long, has too many
+    /// tokens or contains an unexpected character or invalid number
+    pub fn with_limits(expression: &str, limits: &Limits) -> CalcResult<Self> {
+        Self::with_options(
+            expression,
+            &ParseOptions {
+                limits: *limits,
+                ..ParseOptions::default()
+            },
+        )
+    }
+
+    /// Creates a parser with the given options.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - The expression to parse
+    /// * `options` - The limits and syntax options
+    ///
+    /// # Returns
+    ///
+    /// The parser, or an error if the expression is too long, has too many
+    /// tokens or contains an unexpected character or invalid number
+    pub fn with_options(expression: &str, options: &ParseOptions) -> CalcResult<Self> {
+        let limits = &options.limits;
+        if let Some(limit) = limits.max_length {
+            let length = expression.chars().count();
+            if length > limit {
+                return Err(CalcError::InputTooLong { length, limit });
+            }
+        }
+        let mut tokenizer = Tokenizer::new(expression)
+            .full_width(options.full_width)
+            .chinese_numerals(options.chinese_numerals)
+            .complex_numbers(options.complex_numbers)
+            .cell_references(options.cell_references);
+        let tokens = tokenizer.spanned_tokens();
+        if let Some(c) = tokenizer.get_unexpected_char() {
+            return Err(CalcError::UnexpectedChar(c));
+        }
+        if let Some(number) = tokenizer.get_invalid_number() {
+            return Err(CalcError::InvalidNumber(number.to_string()));
+        }
+        if let Some(limit) = limits.max_tokens {
+            // 结尾的 EOF 不计入
+            let count = tokens
+                .iter()
+                .filter(|(token, _)| *token != Token::EOF)
+                .count();
+            if count > limit {
+                return Err(CalcError::TooManyTokens { count, limit });
+            }
+        }
+        let mut tokens = tokens.into_iter();
+        let (current_token, current_span) = tokens.next().unwrap_or((Token::EOF, Span::default()));
         Ok(Parser {
-            tokenizer,
+            tokens,
             current_token,
+            current_span,
+            max_depth: limits.max_depth,
+            depth: 0,
+            implicit_multiplication: options.implicit_multiplication,
+            postfix_percent: options.postfix_percent,
         })
     }
 
-    pub fn parse(&self) -> CalcResult<Node> {
-        todo!()
+    /// Parses the whole expression into a `Node` tree.
+    ///
+    /// # Returns
+    ///
+    /// The root node, or an error if the expression is malformed, contains
+    /// trailing tokens or is nested too deeply
+    pub fn parse(&mut self) -> CalcResult<Node> {
+        let node = self.parse_expression(OperatorPrecedence::Default)?;
+        if self.current_token != Token::EOF {
+            return Err(self.unexpected_token());
+        }
+        Ok(node)
     }
 }
 
-
-impl<'a> Parser<'a> {
-
-    
+impl Parser {
     ///
     /// [
     //     {
@@ -97,19 +220,719 @@ impl<'a> Parser<'a> {
     //     },
     //     "EOF"
     // ]
-    /// 
-    fn parse_expression(&self,operation_precedence:OperatorPrecedence)->CalcResult<Node>{
-        todo!()
+    ///
+    fn parse_expression(&mut self, operation_precedence: OperatorPrecedence) -> CalcResult<Node> {
+        // 括号、函数、负号和右结合运算符都会递归到这里，限制层数以免栈溢出
+        if let Some(limit) = self.max_depth.filter(|limit| self.depth >= *limit) {
+            return Err(CalcError::NestingTooDeep(limit));
+        }
+        self.depth += 1;
+        let result = self.parse_operators(operation_precedence);
+        self.depth -= 1;
+        result
+    }
+
+    /// Parses an operand followed by a loop of binary operators.
+    ///
+    /// A long chain such as `1 + 1 + ... + 1` is parsed without recursion, so
+    /// it does not count against the nesting limit; its length is bounded by
+    /// the token limit instead.
+    ///
+    /// With implicit multiplication on, an operand following another one is
+    /// treated as if a `*` stood in front of it.
+    fn parse_operators(&mut self, operation_precedence: OperatorPrecedence) -> CalcResult<Node> {
+        let mut left = self.parse_number()?;
+        loop {
+            let precedence = self.precedence();
+            // 右结合运算符遇到同级运算时继续向右吸收，例如 2^3^2 = 2^(3^2)
+            if operation_precedence > precedence
+                || (operation_precedence == precedence && !self.right_associative())
+            {
+                break;
+            }
+            left = self.convert_token_to_node(left)?;
+        }
+        Ok(left)
+    }
+
+    /// Parses an operand: a number, variable, function call, negation or a
+    /// bracketed sub-expression.
+    fn parse_number(&mut self) -> CalcResult<Node> {
+        let token = self.current_token.clone();
+        let span = self.current_span;
+        match token {
+            Token::Sub => {
+                self.next_token();
+                let expr = self.parse_expression(OperatorPrecedence::Negative)?;
+                Ok(Node::Negative {
+                    expr: Box::new(expr),
+                    span,
+                })
+            }
+            Token::Number(n) => {
+                self.next_token();
+                Ok(Node::Number(n))
+            }
+            Token::BigNumber(literal) => {
+                self.next_token();
+                Ok(Node::BigNumber(literal))
+            }
+            Token::Imaginary(n) => {
+                self.next_token();
+                Ok(Node::Imaginary(n))
+            }
+            Token::Variable(name) => {
+                self.next_token();
+                Ok(Node::Variable(name, span))
+            }
+            Token::Cell(cell) => {
+                self.next_token();
+                Ok(Node::Cell(cell, span))
+            }
+            Token::Range(from, to) => {
+                self.next_token();
+                Ok(Node::Range(from, to, span))
+            }
+            Token::Text(text) => {
+                self.next_token();
+                Ok(Node::Text(text))
+            }
+            Token::Function {
+                function_prefix, ..
+            } => self.parse_function(function_prefix),
+            Token::LeftSmallParen => self.parse_paren(Token::RightSmallParen),
+            Token::LeftMidParen => self.parse_bracket(),
+            Token::LeftBigParen => self.parse_paren(Token::RightBigParen),
+            Token::EOF => Err(CalcError::UnexpectedToken(token.to_string())),
+            _ => Err(CalcError::InvalidOperator(token.to_string())),
+        }
+    }
+
+    /// Parses the `<arg, ...>` argument list following a function name.
+    ///
+    /// Empty arguments are skipped, and so are stray closing brackets at the
+    /// end of an argument, as the grouping tokenizer has always done.
+    fn parse_function(&mut self, function_prefix: String) -> CalcResult<Node> {
+        let span = self.current_span;
+        self.next_token();
+        // 函数名后一定紧跟 <
+        self.check_paren(Token::LeftFuncParen)?;
+        let mut args = Vec::new();
+        loop {
+            match self.current_token {
+                Token::RightFuncParen => break,
+                Token::Comma => self.next_token(),
+                Token::EOF => return Err(CalcError::UnclosedFunction(function_prefix)),
+                _ => {
+                    args.push(self.parse_expression(OperatorPrecedence::Default)?);
+                    while matches!(
+                        self.current_token,
+                        Token::RightSmallParen | Token::RightMidParen | Token::RightBigParen
+                    ) {
+                        self.next_token();
+                    }
+                    match self.current_token {
+                        Token::Comma | Token::RightFuncParen => {}
+                        Token::EOF => return Err(CalcError::UnclosedFunction(function_prefix)),
+                        _ => return Err(self.unexpected_token()),
+                    }
+                }
+            }
+        }
+        self.next_token();
+        // 本地化的函数别名统一解析为函数名
+        Ok(Node::Function {
+            function_prefix: function::canonical_name(&function_prefix).to_string(),
+            args,
+            span,
+        })
+    }
+
+    /// Parses a bracketed sub-expression and checks the closing bracket.
+    fn parse_paren(&mut self, expected: Token) -> CalcResult<Node> {
+        self.next_token();
+        let expr = self.parse_expression(OperatorPrecedence::Default)?;
+        self.check_paren(expected)?;
+        Ok(expr)
+    }
+
+    /// Parses what follows a `[`: a bracketed sub-expression such as
+    /// `[1 + 2]`, or a matrix literal such as `[1, 2; 3, 4]` once a `,` or
+    /// `;` follows the first element.
+    ///
+    /// # Returns
+    ///
+    /// The node, or `CalcError::DimensionMismatch` pointing at the `[` if
+    /// the rows of the matrix have different lengths
+    fn parse_bracket(&mut self) -> CalcResult<Node> {
+        let span = self.current_span;
+        self.next_token();
+        let first = self.parse_expression(OperatorPrecedence::Default)?;
+        if !matches!(self.current_token, Token::Comma | Token::Semicolon) {
+            self.check_paren(Token::RightMidParen)?;
+            return Ok(first);
+        }
+        let mut rows = vec![vec![first]];
+        loop {
+            match self.current_token {
+                // 逗号分隔同一行的元素，分号开始新的一行
+                Token::Comma => {
+                    self.next_token();
+                    let value = self.parse_expression(OperatorPrecedence::Default)?;
+                    rows.last_mut().expect("rows is not empty").push(value);
+                }
+                Token::Semicolon => {
+                    self.next_token();
+                    rows.push(vec![self.parse_expression(OperatorPrecedence::Default)?]);
+                }
+                _ => break,
+            }
+        }
+        self.check_paren(Token::RightMidParen)?;
+        let cols = rows[0].len();
+        if let Some(row) = rows.iter().find(|row| row.len() != cols) {
+            return Err(CalcError::DimensionMismatch {
+                expected: format!("1×{cols}"),
+                actual: format!("1×{}", row.len()),
+            }
+            .at(span));
+        }
+        Ok(Node::Matrix { rows, span })
+    }
+
+    /// Turns the operator under the cursor into a binary node with `left`
+    /// as its left operand, or into a postfix node applied to `left`.
+    fn convert_token_to_node(&mut self, left: Node) -> CalcResult<Node> {
+        let postfix = match self.current_token {
+            Token::Exclamation => Some(PostfixOp::Factorial),
+            Token::Percent if self.postfix_percent => Some(PostfixOp::Percent),
+            _ => None,
+        };
+        if let Some(op) = postfix {
+            let node = Node::Postfix {
+                op,
+                expr: Box::new(left),
+                span: self.current_span,
+            };
+            self.next_token();
+            return Ok(node);
+        }
+        let (op, span) = if self.implies_multiplication() {
+            // 隐式乘法不消耗 token，跨度为右侧操作数前的空位置
+            let start = self.current_span.start;
+            (BinaryOp::Mul, Span::new(start, start))
+        } else {
+            let op = match self.current_token {
+                Token::Add => BinaryOp::Add,
+                Token::Sub => BinaryOp::Sub,
+                Token::Mul => BinaryOp::Mul,
+                Token::Div => BinaryOp::Div,
+                Token::Caret => BinaryOp::Caret,
+                Token::Percent => BinaryOp::Rem,
+                Token::DoubleSlash => BinaryOp::IntDiv,
+                _ => return Err(self.unexpected_token()),
+            };
+            let span = self.current_span;
+            self.next_token();
+            (op, span)
+        };
+        let right = self.parse_expression(op.get_precedence())?;
+        Ok(Node::Binary {
+            op,
+            lhs: Box::new(left),
+            rhs: Box::new(right),
+            span,
+        })
+    }
+
+    /// Returns whether the token under the cursor is a right-associative
+    /// binary operator.
+    fn right_associative(&self) -> bool {
+        self.current_token == Token::Caret && !self.implies_multiplication()
+    }
+
+    /// Returns the precedence of the token under the cursor as the operator
+    /// following a complete operand.
+    fn precedence(&self) -> OperatorPrecedence {
+        if self.implies_multiplication() {
+            BinaryOp::Mul.get_precedence()
+        } else if self.postfix_percent && self.current_token == Token::Percent {
+            OperatorPrecedence::Postfix
+        } else {
+            self.current_token.get_precedence()
+        }
+    }
+
+    /// Returns whether the token under the cursor starts an operand that is
+    /// implicitly multiplied with the operand before it.
+    fn implies_multiplication(&self) -> bool {
+        self.implicit_multiplication
+            && matches!(
+                self.current_token,
+                Token::Number(_)
+                    | Token::BigNumber(_)
+                    | Token::Imaginary(_)
+                    | Token::Variable(_)
+                    | Token::Function { .. }
+                    | Token::LeftSmallParen
+                    | Token::LeftMidParen
+                    | Token::LeftBigParen
+            )
+    }
+
+    fn check_paren(&mut self, expected: Token) -> CalcResult<()> {
+        if self.current_token == expected {
+            self.next_token();
+            Ok(())
+        } else {
+            Err(self.unexpected_token())
+        }
+    }
+
+    /// Returns the error for a token that cannot follow a complete operand.
+    ///
+    /// An operand right after another one, as in `a b` or `2 x`, is reported
+    /// as a missing operator instead of being joined to it.
+    fn unexpected_token(&self) -> CalcError {
+        match &self.current_token {
+            // 函数名 token 尚未带参数，只显示函数名
+            Token::Function {
+                function_prefix, ..
+            } => CalcError::MissingOperator {
+                token: function_prefix.clone(),
+                span: self.current_span,
+            },
+            Token::Number(_)
+            | Token::BigNumber(_)
+            | Token::Imaginary(_)
+            | Token::Variable(_)
+            | Token::Cell(_)
+            | Token::Range(..)
+            | Token::Text(_)
+            | Token::LeftSmallParen
+            | Token::LeftMidParen
+            | Token::LeftBigParen => CalcError::MissingOperator {
+                token: self.current_token.to_string(),
+                span: self.current_span,
+            },
+            _ => CalcError::UnexpectedToken(self.current_token.to_string()),
+        }
+    }
+
+    fn next_token(&mut self) {
+        if let Some((token, span)) = self.tokens.next() {
+            self.current_token = token;
+            self.current_span = span;
+        } else {
+            self.current_token = Token::EOF;
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::calc::parser::Parser;
+    use crate::calc::ast::{BinaryOp, Node, PostfixOp};
+    use crate::calc::error::CalcError;
+    use crate::calc::limits::Limits;
+    use crate::calc::parser::{ParseOptions, Parser};
+    use crate::calc::span::Span;
+    use rust_decimal::dec;
+    use std::collections::HashMap;
 
     #[test]
     fn test_new_parser() {
         let result = Parser::new("1 + 2 * nvl < abs < 1 + 2 * 3 + [ ( 1+ 3 ) / 2 ] ) , 0 > , 0 >");
         let _ = result.is_err_and(|_| panic!("test_new_parser error"));
     }
+
+    #[test]
+    fn test_parse() {
+        let node = Parser::new("1 + 2 * x").unwrap().parse().unwrap();
+        assert_eq!(
+            node,
+            Node::Binary {
+                op: BinaryOp::Add,
+                lhs: Box::new(Node::Number(dec!(1))),
+                rhs: Box::new(Node::Binary {
+                    op: BinaryOp::Mul,
+                    lhs: Box::new(Node::Number(dec!(2))),
+                    rhs: Box::new(Node::Variable("x".to_string(), Span::default())),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
+            }
+        );
+    }
+
+    /// Tests that imaginary literals are parsed as atoms and print back the
+    /// way they were written.
+    #[test]
+    fn test_parse_imaginary() {
+        let options = ParseOptions {
+            complex_numbers: true,
+            ..ParseOptions::default()
+        };
+        let parse = |expression: &str| {
+            Parser::with_options(expression, &options)
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+        let node = parse("3 - 0.5i * x ^ 2");
+        assert_eq!(node.to_string(), "3 - 0.5i * x ^ 2");
+        assert_eq!(parse(&node.to_string()), node);
+        let Node::Binary { rhs, .. } = node else {
+            panic!("expected a binary node");
+        };
+        let Node::Binary { lhs, .. } = *rhs else {
+            panic!("expected a product");
+        };
+        assert_eq!(*lhs, Node::Imaginary(dec!(0.5)));
+        assert_eq!(
+            Parser::new("4i").unwrap().parse().err(),
+            Some(CalcError::MissingOperator {
+                token: "i".to_string(),
+                span: Span::new(1, 2)
+            })
+        );
+    }
+
+    /// Tests matrix literals, square brackets as grouping, and rows of
+    /// different lengths.
+    #[test]
+    fn test_parse_matrix() {
+        let parse = |expression: &str| Parser::new(expression).unwrap().parse();
+        let node = parse("[1, x + 1; -2, 3] * [2; 1]").unwrap();
+        assert_eq!(node.to_string(), "[1, x + 1; -2, 3] * [2; 1]");
+        assert_eq!(parse(&node.to_string()), Ok(node.clone()));
+        let Node::Binary { lhs, .. } = node else {
+            panic!("expected a product");
+        };
+        let Node::Matrix { rows, span } = *lhs else {
+            panic!("expected a matrix");
+        };
+        assert_eq!((rows.len(), rows[1].len(), span), (2, 2, Span::new(0, 1)));
+        assert_eq!(parse("[1 + 2] * 3"), parse("(1 + 2) * 3"));
+        assert_eq!(
+            parse("sum<[1, 2], 3>").unwrap().to_string(),
+            "sum<[1, 2], 3>"
+        );
+        assert_eq!(
+            parse("2 + [1, 2; 3]"),
+            Err(CalcError::DimensionMismatch {
+                expected: "1×2".to_string(),
+                actual: "1×1".to_string()
+            }
+            .at(Span::new(4, 5)))
+        );
+        assert!(parse("[1, 2").is_err());
+    }
+
+    /// Tests that `^` is right-associative while the other operators group
+    /// from the left.
+    #[test]
+    fn test_parse_associativity() {
+        let parse = |expression: &str| Parser::new(expression).unwrap().parse().unwrap();
+        let binary = |op, lhs, rhs| Node::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            span: Span::default(),
+        };
+        let n = |n: i64| Node::Number(n.into());
+        assert_eq!(
+            parse("2 ^ 3 ^ 2"),
+            binary(BinaryOp::Caret, n(2), binary(BinaryOp::Caret, n(3), n(2)))
+        );
+        assert_eq!(
+            parse("2 ^ 3 ^ 2 * 4"),
+            binary(
+                BinaryOp::Mul,
+                binary(BinaryOp::Caret, n(2), binary(BinaryOp::Caret, n(3), n(2))),
+                n(4)
+            )
+        );
+        assert_eq!(
+            parse("8 - 3 - 2"),
+            binary(BinaryOp::Sub, binary(BinaryOp::Sub, n(8), n(3)), n(2))
+        );
+    }
+
+    /// Tests that operators, negations and function names keep their spans.
+    #[test]
+    fn test_parse_spans() {
+        let source = "-a + max < b ,  c / 0 >";
+        let node = Parser::new(source).unwrap().parse().unwrap();
+        let Node::Binary { lhs, rhs, span, .. } = node else {
+            panic!("expected a binary node");
+        };
+        assert_eq!(span.slice(source), "+");
+        let Node::Negative { span, .. } = *lhs else {
+            panic!("expected a negation");
+        };
+        assert_eq!(span, Span::new(0, 1));
+        let Node::Function { args, span, .. } = *rhs else {
+            panic!("expected a function call");
+        };
+        assert_eq!(span.slice(source), "max");
+        let Node::Binary { span, .. } = &args[1] else {
+            panic!("expected a binary node");
+        };
+        assert_eq!(*span, Span::new(18, 19));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let parse = |expression| Parser::new(expression).and_then(|mut parser| parser.parse());
+        assert_eq!(parse("1 + $"), Err(CalcError::UnexpectedChar('$')));
+        assert_eq!(
+            parse("(1 + 2"),
+            Err(CalcError::UnexpectedToken("EOF".to_string()))
+        );
+        assert_eq!(
+            parse("1 + 2]"),
+            Err(CalcError::UnexpectedToken("]".to_string()))
+        );
+        assert_eq!(
+            parse("nvl<1, 0"),
+            Err(CalcError::UnclosedFunction("nvl".to_string()))
+        );
+        assert_eq!(
+            parse("* 2"),
+            Err(CalcError::InvalidOperator("×".to_string()))
+        );
+    }
+
+    /// Tests that identifiers end at whitespace, may contain digits, `_` and
+    /// dots, and that two adjacent operands are a missing operator rather
+    /// than one merged name.
+    #[test]
+    fn test_parse_identifiers() {
+        let parse = |expression| Parser::new(expression).and_then(|mut parser| parser.parse());
+        assert_eq!(
+            parse("order.total * rate_2024 + abs <x>").map(|node| node.to_string()),
+            Ok("order.total * rate_2024 + abs<x>".to_string())
+        );
+        assert_eq!(
+            parse("a b"),
+            Err(CalcError::MissingOperator {
+                token: "b".to_string(),
+                span: Span::new(2, 3)
+            })
+        );
+        let error = parse("price tax<1>").unwrap_err();
+        assert_eq!(
+            error,
+            CalcError::MissingOperator {
+                token: "tax".to_string(),
+                span: Span::new(6, 9)
+            }
+        );
+        assert_eq!(error.span(), Some(Span::new(6, 9)));
+        assert_eq!(
+            parse("max<1 2>"),
+            Err(CalcError::MissingOperator {
+                token: "2".to_string(),
+                span: Span::new(6, 7)
+            })
+        );
+        assert_eq!(parse("order."), Err(CalcError::UnexpectedChar('.')));
+        assert_eq!(parse("order.2"), Err(CalcError::UnexpectedChar('.')));
+    }
+
+    /// Tests that full-width input is only accepted when normalisation is on,
+    /// and that spans point at the original full-width characters.
+    #[test]
+    fn test_parse_full_width() {
+        let source = "求和《１，２》×【３－单价】";
+        assert_eq!(
+            Parser::new(source).err(),
+            Some(CalcError::UnexpectedChar('《'))
+        );
+        let options = ParseOptions {
+            full_width: true,
+            ..ParseOptions::default()
+        };
+        let node = Parser::with_options(source, &options)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(node.to_string(), "sum<1, 2> * (3 - 单价)");
+        let Node::Binary { rhs, span, .. } = node else {
+            panic!("expected a binary node");
+        };
+        assert_eq!(span.slice(source), "×");
+        let Node::Binary { rhs, span, .. } = *rhs else {
+            panic!("expected a binary node");
+        };
+        assert_eq!(span.slice(source), "－");
+        assert_eq!(rhs.span().map(|span| span.slice(source)), Some("单价"));
+    }
+
+    /// Tests that adjacent operands are multiplied with the precedence of
+    /// `*` only when implicit multiplication is on.
+    #[test]
+    fn test_parse_implicit_multiplication() {
+        let options = ParseOptions {
+            implicit_multiplication: true,
+            ..ParseOptions::default()
+        };
+        let parse = |expression| Parser::with_options(expression, &options)?.parse();
+        for (expression, expected) in [
+            ("2x", "2 * x"),
+            ("3(a + b)", "3 * (a + b)"),
+            ("(a)(b)", "a * b"),
+            ("2[x+1]", "2 * (x + 1)"),
+            ("2 abs<x> y", "2 * abs<x> * y"),
+            ("1 + 2x ^ 2", "1 + 2 * x ^ 2"),
+            ("a / 2b", "a / 2 * b"),
+            ("-2x", "-2 * x"),
+            ("0x", "0 * x"),
+        ] {
+            assert_eq!(parse(expression).unwrap().to_string(), expected);
+        }
+        let Node::Binary { span, .. } = parse("2x").unwrap() else {
+            panic!("expected a binary node");
+        };
+        assert_eq!(span, Span::new(1, 1));
+        assert_eq!(
+            Parser::new("2x").unwrap().parse(),
+            Err(CalcError::MissingOperator {
+                token: "x".to_string(),
+                span: Span::new(1, 2)
+            })
+        );
+        assert_eq!(
+            parse("2 \"a\""),
+            Err(CalcError::MissingOperator {
+                token: "\"a\"".to_string(),
+                span: Span::new(2, 5)
+            })
+        );
+    }
+
+    /// Tests the precedence of `%`, `//` and `!`, and `%` as a percentage
+    /// when the postfix mode is on.
+    #[test]
+    fn test_parse_remainder_and_postfix() {
+        for (expression, expected) in [
+            ("a + b % c", "a + b % c"),
+            ("(a + b) % c // d", "(a + b) % c // d"),
+            ("-x!", "-x!"),
+            ("(-x)!", "(-x)!"),
+            ("2 ^ n!!", "2 ^ n!!"),
+        ] {
+            let node = Parser::new(expression).unwrap().parse().unwrap();
+            assert_eq!(node.to_string(), expected);
+        }
+        let node = Parser::new("a % b").unwrap().parse().unwrap();
+        assert!(matches!(
+            node,
+            Node::Binary {
+                op: BinaryOp::Rem,
+                ..
+            }
+        ));
+        assert_eq!(
+            Parser::new("15%").unwrap().parse(),
+            Err(CalcError::UnexpectedToken("EOF".to_string()))
+        );
+
+        let options = ParseOptions {
+            postfix_percent: true,
+            ..ParseOptions::default()
+        };
+        let node = Parser::with_options("price * 15% - 2%", &options)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(node.to_string(), "price * 15% - 2%");
+        let Node::Binary { lhs, .. } = node else {
+            panic!("expected a binary node");
+        };
+        let Node::Binary { rhs, .. } = *lhs else {
+            panic!("expected a binary node");
+        };
+        assert!(matches!(
+            *rhs,
+            Node::Postfix {
+                op: PostfixOp::Percent,
+                span: Span { start: 10, end: 11 },
+                ..
+            }
+        ));
+    }
+
+    /// Tests that Chinese numerals are read as numbers only when enabled,
+    /// and that a malformed numeral is an invalid number.
+    #[test]
+    fn test_parse_chinese_numerals() {
+        let options = ParseOptions {
+            chinese_numerals: true,
+            ..ParseOptions::default()
+        };
+        let node = Parser::with_options("一百二十 * 单价 + 二〇二四", &options)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(node.to_string(), "120 * 单价 + 2024");
+        let node = Parser::new("一百二十 * 单价").unwrap().parse().unwrap();
+        assert_eq!(node.to_string(), "一百二十 * 单价");
+        assert_eq!(
+            Parser::with_options("万 + 1", &options).err(),
+            Some(CalcError::InvalidNumber("万".to_string()))
+        );
+    }
+
+    /// Tests the length, token and nesting limits, including inputs nested
+    /// far deeper than the call stack could handle recursively.
+    #[test]
+    fn test_parse_limits() {
+        let parse = |expression: &str, limits: &Limits| {
+            Parser::with_limits(expression, limits).and_then(|mut parser| parser.parse())
+        };
+        let limits = Limits::default();
+        assert_eq!(
+            parse(&"(".repeat(20_000), &limits),
+            Err(CalcError::InputTooLong {
+                length: 20_000,
+                limit: 10_000
+            })
+        );
+        assert_eq!(
+            parse(&format!("{}1", "1+".repeat(3_000)), &limits),
+            Err(CalcError::TooManyTokens {
+                count: 6_001,
+                limit: 5_000
+            })
+        );
+        // 运算符链通过循环解析，长度只受 token 数限制，不计入嵌套层数
+        let sum = parse(&format!("{}1", "1+".repeat(150)), &limits).unwrap();
+        assert_eq!(sum.eval(&HashMap::new()), Ok(dec!(151)));
+        let chain = parse(&format!("-({}1)", "1+".repeat(2_000)), &limits).unwrap();
+        assert_eq!(chain.to_string(), format!("-({}1)", "1 + ".repeat(2_000)));
+        assert_eq!(chain.eval(&HashMap::new()), Ok(dec!(-2001)));
+        let nested = format!("{}1{}", "(".repeat(99), ")".repeat(99));
+        assert_eq!(parse(&nested, &limits), Ok(Node::Number(dec!(1))));
+        for deep in [
+            format!("{}1{}", "(".repeat(100), ")".repeat(100)),
+            format!("{}1{}", "nvl<".repeat(100), ", 0>".repeat(100)),
+        ] {
+            assert_eq!(parse(&deep, &limits), Err(CalcError::NestingTooDeep(100)));
+        }
+
+        let limits = Limits {
+            max_length: None,
+            max_tokens: None,
+            ..Limits::default()
+        };
+        for deep in [
+            format!("{}1{}", "nvl<".repeat(100_000), ", 0>".repeat(100_000)),
+            format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+            format!("{}1", "-".repeat(100_000)),
+            format!("{}1", "2^".repeat(100_000)),
+        ] {
+            assert_eq!(parse(&deep, &limits), Err(CalcError::NestingTooDeep(100)));
+        }
+    }
 }
@@ -0,0 +1,255 @@
+use crate::calc::ast::Node;
+use crate::calc::context::Context;
+use crate::calc::error::CalcResult;
+use crate::calc::function;
+use crate::calc::value::{ErrorKind, Value};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+/// Partially evaluates an expression tree with the variables that are known.
+///
+/// Known variables are substituted by their values and every sub-expression
+/// whose operands are all constant is folded into a single number. Whatever
+/// still depends on unknown variables is returned as a residual `Node`, which
+/// can be pretty-printed, cached and evaluated later with the remaining
+/// variables.
+///
+/// `nvl<value, default>` is only folded once its first argument is constant,
+/// because an unknown variable may still be supplied later. Its default is
+/// left as it is if folding it fails, since evaluation only needs it when
+/// the value is missing.
+///
+/// # Arguments
+///
+/// * `node` - The expression tree to specialise
+/// * `known` - Values for the variables that are already known
+///
+/// # Returns
+///
+/// The residual expression, or an error if folding a constant part fails
+/// (for example a division by zero)
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::parser::Parser;
+/// use rust_calculate::calc::partial::partial_eval;
+/// use rust_decimal::dec;
+/// use std::collections::HashMap;
+///
+/// let ast = Parser::new("price * qty * (1 + rate)").unwrap().parse().unwrap();
+/// let known = HashMap::from([("price".to_string(), dec!(20)), ("rate".to_string(), dec!(0.13))]);
+/// let residual = partial_eval(&ast, &known).unwrap();
+/// assert_eq!(residual.to_string(), "20 * qty * 1.13");
+/// ```
+pub fn partial_eval(node: &Node, known: &HashMap<String, Decimal>) -> CalcResult<Node> {
+    // 运算符链沿左操作数循环折叠，递归深度不随链长增长
+    let mut chain = Vec::new();
+    let mut operand = node;
+    while let Some(next) = operand.chain_operand() {
+        chain.push(operand);
+        operand = next;
+    }
+    let mut residual = fold(operand, known)?;
+    for link in chain.into_iter().rev() {
+        residual = fold_operator(link, residual, known)?;
+    }
+    Ok(residual)
+}
+
+/// Folds a binary or postfix operator whose left operand has already been
+/// folded into `lhs`.
+fn fold_operator(node: &Node, lhs: Node, known: &HashMap<String, Decimal>) -> CalcResult<Node> {
+    match node {
+        Node::Postfix { op, span, .. } => match lhs {
+            Node::Number(n) => Ok(Node::Number(op.apply(n)?)),
+            residual => Ok(Node::Postfix {
+                op: *op,
+                expr: Box::new(residual),
+                span: *span,
+            }),
+        },
+        Node::Binary { op, rhs, span, .. } => {
+            let rhs = partial_eval(rhs, known)?;
+            match (&lhs, &rhs) {
+                (Node::Number(l), Node::Number(r)) => Ok(Node::Number(op.apply(*l, *r)?)),
+                _ => Ok(Node::Binary {
+                    op: *op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    span: *span,
+                }),
+            }
+        }
+        _ => unreachable!("only operators are collected"),
+    }
+}
+
+/// Folds a node that is not a binary or postfix operator.
+fn fold(node: &Node, known: &HashMap<String, Decimal>) -> CalcResult<Node> {
+    match node {
+        Node::Number(n) => Ok(Node::Number(*n)),
+        // 虚数留给求值时处理，折叠结果只能是实数；超出 Decimal 精度的字面量
+        // 同样只能由求值时的数值后端转换
+        Node::Text(_) | Node::Imaginary(_) | Node::BigNumber(_) => Ok(node.clone()),
+        // 单元格只能在求值时通过 CellProvider 解析
+        Node::Cell(..) | Node::Range(..) => Ok(node.clone()),
+        Node::Variable(name, span) => Ok(known.get(name).map_or_else(
+            || Node::Variable(name.clone(), *span),
+            |value| Node::Number(*value),
+        )),
+        Node::Negative { expr, span } => match partial_eval(expr, known)? {
+            Node::Number(n) => Ok(Node::Number(-n)),
+            residual => Ok(Node::Negative {
+                expr: Box::new(residual),
+                span: *span,
+            }),
+        },
+        Node::Postfix { .. } | Node::Binary { .. } => {
+            unreachable!("operator chains are unwound by partial_eval")
+        }
+        // 矩阵只折叠元素，矩阵运算留给求值时处理
+        Node::Matrix { rows, span } => Ok(Node::Matrix {
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|value| partial_eval(value, known)).collect())
+                .collect::<CalcResult<_>>()?,
+            span: *span,
+        }),
+        Node::Function {
+            function_prefix,
+            args,
+            span,
+        } => {
+            // iferror 的第一个参数折叠出错时直接折叠为备用值
+            if function_prefix == function::IFERROR {
+                function::check_arg_count(function_prefix, args.len())?;
+                if let Err(error) = partial_eval(&args[0], known) {
+                    return match ErrorKind::from_error(&error) {
+                        Some(_) => partial_eval(&args[1], known),
+                        None => Err(error),
+                    };
+                }
+            }
+            // nvl 的第一个参数已知时直接取该值，默认值无需折叠
+            if function_prefix == function::NVL {
+                function::check_arg_count(function_prefix, args.len())?;
+                let value = partial_eval(&args[0], known)?;
+                if matches!(value, Node::Number(_) | Node::Text(_)) {
+                    return Ok(value);
+                }
+                // 默认值只在求值时才可能用到，折叠出错时保留原样
+                let default = partial_eval(&args[1], known).unwrap_or_else(|_| args[1].clone());
+                return Ok(Node::Function {
+                    function_prefix: function_prefix.clone(),
+                    args: vec![value, default],
+                    span: *span,
+                });
+            }
+            let args = args
+                .iter()
+                .map(|arg| partial_eval(arg, known))
+                .collect::<CalcResult<Vec<_>>>()?;
+            let values = args
+                .iter()
+                .map(|arg| match arg {
+                    Node::Number(n) => Some(Value::Number(*n)),
+                    Node::Text(text) => Some(Value::Text(text.clone())),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            let result = values
+                .map(|values| function::call(function_prefix, &values, &Context::default()))
+                .transpose()?;
+            match result {
+                Some(Value::Text(text)) => Ok(Node::Text(text)),
+                // 参数不全是常量，或者结果是无法写成数字的矩阵
+                Some(Value::Matrix(_)) | None => Ok(Node::Function {
+                    function_prefix: function_prefix.clone(),
+                    args,
+                    span: *span,
+                }),
+                Some(value) => Ok(Node::Number(value.into_number()?)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::error::CalcError;
+    use crate::calc::parser::Parser;
+    use crate::calc::span::Span;
+    use rust_decimal::dec;
+
+    fn parse(expression: &str) -> Node {
+        Parser::new(expression).unwrap().parse().unwrap()
+    }
+
+    /// Tests that known variables are substituted and constant parts folded,
+    /// and that the residual evaluates to the same value as the original.
+    #[test]
+    fn test_partial_eval_residual() {
+        let ast = parse("price * qty * (1 + rate) - abs<discount - 2 * 3>");
+        let known = HashMap::from([
+            ("price".to_string(), dec!(20)),
+            ("rate".to_string(), dec!(0.13)),
+        ]);
+        let residual = partial_eval(&ast, &known).unwrap();
+        assert_eq!(residual.to_string(), "20 * qty * 1.13 - abs<discount - 6>");
+
+        let rest = HashMap::from([
+            ("qty".to_string(), dec!(3)),
+            ("discount".to_string(), dec!(1)),
+        ]);
+        let mut all = known.clone();
+        all.extend(rest.clone());
+        assert_eq!(residual.eval(&rest), ast.eval(&all));
+        assert_eq!(residual.eval(&rest), Ok(dec!(62.80)));
+    }
+
+    /// Tests folding of fully known expressions, `nvl` and folding errors.
+    #[test]
+    fn test_partial_eval_fold() {
+        let known = HashMap::from([("x".to_string(), dec!(-2))]);
+        assert_eq!(
+            partial_eval(&parse("-x * max<x, 1, 3>"), &known),
+            Ok(Node::Number(dec!(6)))
+        );
+        assert_eq!(
+            partial_eval(&parse("nvl<x, y>"), &known),
+            Ok(Node::Number(dec!(-2)))
+        );
+        assert_eq!(
+            partial_eval(&parse("rmb<-x>"), &known),
+            Ok(Node::Text("贰元整".to_string()))
+        );
+        assert_eq!(
+            partial_eval(&parse("nvl<y, 1 + 1>"), &known)
+                .unwrap()
+                .to_string(),
+            "nvl<y, 2>"
+        );
+        let lazy = parse("nvl<x, 1 / 0>");
+        assert_eq!(partial_eval(&lazy, &known), Ok(Node::Number(dec!(-2))));
+        assert_eq!(
+            partial_eval(&lazy, &known),
+            Ok(Node::Number(lazy.eval(&known).unwrap()))
+        );
+        assert_eq!(
+            partial_eval(&parse("nvl<y, 1 / 0>"), &known)
+                .unwrap()
+                .to_string(),
+            "nvl<y, 1 / 0>"
+        );
+        assert_eq!(
+            partial_eval(&parse("y + 1 / (x + 2)"), &known),
+            Err(CalcError::DivideByZero)
+        );
+        assert_eq!(
+            partial_eval(&parse("iferror<1 / (x + 2), y>"), &known),
+            Ok(Node::Variable("y".to_string(), Span::default()))
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use crate::calc::ast::{Node, PostfixOp};
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::span::Span;
+use std::collections::BTreeSet;
+
+/// The functions and variables an expression is allowed to use.
+///
+/// Each tenant of a service can be given its own sandbox. `None` allows
+/// everything, so the default sandbox places no restrictions. Variable
+/// patterns may contain `*`, which matches any run of characters, for
+/// example `tax*` or `*rate`.
+///
+/// The sandbox is enforced twice: [`check`](Sandbox::check) rejects a parsed
+/// expression up front, and the evaluator checks every variable and function
+/// again when the sandbox is set on the `Context`, so an expression that was
+/// never checked cannot escape it either.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::error::CalcError;
+/// use rust_calculate::calc::parser::Parser;
+/// use rust_calculate::calc::sandbox::Sandbox;
+/// use rust_calculate::calc::span::Span;
+///
+/// let sandbox = Sandbox::new()
+///     .allow_functions(["abs", "round"])
+///     .allow_variables(["price", "tax*"]);
+/// let node = Parser::new("abs<taxrate * price>").unwrap().parse().unwrap();
+/// assert_eq!(sandbox.check(&node), Ok(()));
+///
+/// let node = Parser::new("price * salary").unwrap().parse().unwrap();
+/// assert_eq!(
+///     sandbox.check(&node),
+///     Err(CalcError::Forbidden { name: "salary".to_string(), span: Span::new(8, 14) })
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sandbox {
+    /// The names of the functions that may be called; `None` allows all
+    pub functions: Option<BTreeSet<String>>,
+    /// The patterns of the variable names that may be read; `None` allows all
+    pub variables: Option<Vec<String>>,
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the given functions, in addition to those already allowed.
+    pub fn allow_functions<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.functions
+            .get_or_insert_with(BTreeSet::new)
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allows the variables matching the given patterns, in addition to
+    /// those already allowed.
+    pub fn allow_variables<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.variables
+            .get_or_insert_with(Vec::new)
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns whether the function may be called.
+    pub fn allows_function(&self, name: &str) -> bool {
+        self.functions
+            .as_ref()
+            .is_none_or(|functions| functions.contains(name))
+    }
+
+    /// Returns whether the variable may be read.
+    pub fn allows_variable(&self, name: &str) -> bool {
+        self.variables.as_ref().is_none_or(|patterns| {
+            patterns
+                .iter()
+                .any(|pattern| matches_pattern(pattern, name))
+        })
+    }
+
+    /// Checks a single variable or function node.
+    ///
+    /// # Returns
+    ///
+    /// `CalcError::Forbidden` with the span of the name if the node uses a
+    /// function or variable the sandbox does not allow. A postfix `!` counts
+    /// as a call to `factorial` and is reported with the span of the `!`.
+    pub fn check_node(&self, node: &Node) -> CalcResult<()> {
+        match node {
+            Node::Variable(name, span) if !self.allows_variable(name) => {
+                Err(forbidden(name, *span))
+            }
+            Node::Function {
+                function_prefix,
+                span,
+                ..
+            } if !self.allows_function(function_prefix) => Err(forbidden(function_prefix, *span)),
+            // 后缀 ! 就是 factorial 函数，不能借此绕过沙箱
+            Node::Postfix {
+                op: PostfixOp::Factorial,
+                span,
+                ..
+            } if !self.allows_function("factorial") => Err(forbidden("factorial", *span)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks every function and variable in an expression tree.
+    ///
+    /// # Returns
+    ///
+    /// `CalcError::Forbidden` for the first forbidden name in the source,
+    /// with the span of the name
+    pub fn check(&self, node: &Node) -> CalcResult<()> {
+        // 子节点逆序入栈，按源码顺序检查
+        let mut pending = vec![node];
+        while let Some(node) = pending.pop() {
+            self.check_node(node)?;
+            pending.extend(node.children().into_iter().rev());
+        }
+        Ok(())
+    }
+}
+
+fn forbidden(name: &str, span: Span) -> CalcError {
+    CalcError::Forbidden {
+        name: name.to_string(),
+        span,
+    }
+}
+
+/// Matches a name against a pattern in which `*` matches any run of
+/// characters, including an empty one.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    // 没有 * 时必须完全相同
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    // 中间的片段贪心地取最早出现的位置
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::cell::NoCells;
+    use crate::calc::context::Context;
+    use crate::calc::parser::Parser;
+    use rust_decimal::{Decimal, dec};
+    use std::collections::HashMap;
+
+    fn parse(expression: &str) -> Node {
+        Parser::new(expression).unwrap().parse().unwrap()
+    }
+
+    /// Tests exact names, wildcards at either end and in the middle.
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("price", "price"));
+        assert!(!matches_pattern("price", "prices"));
+        assert!(matches_pattern("order.*", "order.qty"));
+        assert!(!matches_pattern("order.*", "orders"));
+        assert!(matches_pattern("*_amount", "tax_amount"));
+        assert!(matches_pattern("a*b*c", "aXbYbc"));
+        assert!(!matches_pattern("a*b*c", "acb"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+        assert!(matches_pattern("*", ""));
+    }
+
+    /// Tests that the first forbidden name in the source is reported with
+    /// its span, and that the default sandbox allows everything.
+    #[test]
+    fn test_check() {
+        let sandbox = Sandbox::new()
+            .allow_functions(["nvl"])
+            .allow_variables(["x", "y*"]);
+        assert_eq!(sandbox.check(&parse("nvl<x, yes> + y")), Ok(()));
+        assert_eq!(
+            sandbox.check(&parse("x + abs<z>")),
+            Err(forbidden("abs", Span::new(4, 7)))
+        );
+        assert_eq!(
+            sandbox.check(&parse("nvl<z, 1> * w")),
+            Err(forbidden("z", Span::new(4, 5)))
+        );
+        assert_eq!(Sandbox::new().check(&parse("abs<z>")), Ok(()));
+
+        let sandbox = Sandbox::new().allow_functions(["abs"]);
+        assert_eq!(
+            sandbox.check(&parse("abs<5!>")),
+            Err(forbidden("factorial", Span::new(5, 6)))
+        );
+        assert_eq!(
+            sandbox.allow_functions(["factorial"]).check(&parse("5!")),
+            Ok(())
+        );
+    }
+
+    /// Tests that the evaluator enforces the context's sandbox, even where
+    /// `nvl` or `iferror` would otherwise hide the error.
+    #[test]
+    fn test_eval_in_sandbox() {
+        let variables = HashMap::from([("x".to_string(), dec!(2)), ("z".to_string(), dec!(3))]);
+        let context = Context {
+            sandbox: Sandbox::new()
+                .allow_functions(["nvl", "iferror"])
+                .allow_variables(["x"]),
+            error_values: true,
+            ..Context::default()
+        };
+        let eval = |expression| {
+            parse(expression).eval_with_context::<Decimal>(&variables, &NoCells, &context)
+        };
+        assert_eq!(eval("nvl<x, 0> * 2"), Ok(dec!(4)));
+        assert_eq!(eval("nvl<z, 0>"), Err(forbidden("z", Span::new(4, 5))));
+        assert_eq!(
+            eval("iferror<abs<x>, 0>"),
+            Err(forbidden("abs", Span::new(8, 11)))
+        );
+        assert_eq!(eval("x!"), Err(forbidden("factorial", Span::new(1, 2))));
+    }
+}
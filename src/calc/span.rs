@@ -0,0 +1,42 @@
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// A range of byte offsets into the original expression string.
+///
+/// Spans point at the source text a token or node came from, so errors can
+/// show the user exactly which operator or function failed.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::span::Span;
+///
+/// let span = Span::new(2, 3);
+/// assert_eq!(span.slice("1 / 0"), "/");
+/// assert_eq!(span.to_string(), "2..3");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub struct Span {
+    /// The byte offset of the first character
+    pub start: usize,
+    /// The byte offset just past the last character
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the source text the span covers, or an empty string if the
+    /// span does not belong to `source`.
+    pub fn slice<'a>(&self, source: &'a str) -> &'a str {
+        source.get(self.start..self.end).unwrap_or("")
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
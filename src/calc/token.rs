@@ -1,14 +1,26 @@
+use crate::calc::cell::CellRef;
 use rust_decimal::Decimal;
-use std::fmt::{Display, Formatter};
 use serde::Serialize;
+use std::fmt::{Display, Formatter};
 
 /// Represents a token in the calculator's syntax.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum Token {
     // 数字
     Number(Decimal),
+    // 超出 Decimal 精度的数字字面量，保留原文交给数值后端转换
+    BigNumber(String),
+    // 虚数字面量，例如 4i
+    Imaginary(Decimal),
     // 变量
     Variable(String),
+    // 单元格引用，例如 A1
+    Cell(CellRef),
+    // 单元格区域，例如 A1:B10
+    Range(CellRef, CellRef),
+    // 字符串，例如 "HALF_EVEN"
+    Text(String),
     // 嵌套函数结构
     Function {
         function_prefix: String,
@@ -24,6 +36,12 @@ pub enum Token {
     Div,
     // ^
     Caret,
+    // %，取余或百分号
+    Percent,
+    // //，整除
+    DoubleSlash,
+    // !，阶乘
+    Exclamation,
     // (
     LeftSmallParen,
     // )
@@ -42,6 +60,8 @@ pub enum Token {
     RightFuncParen,
     // ,
     Comma,
+    // ;，矩阵的行分隔符
+    Semicolon,
     EOF,
 }
 
@@ -61,19 +81,24 @@ impl Token {
     /// - `Default`: Any token that is not an operator. This includes
     ///   numbers, variables, and parentheses.
     /// - `AddOrSubtract`: The addition and subtraction operators.
-    /// - `MultiplyOrDivide`: The multiplication, division, and modulus
-    ///   operators.
+    /// - `MultiplyOrDivide`: The multiplication, division, integer division
+    ///   and remainder operators.
     /// - `Power`: The power operator.
-    /// - `Function`: Function calls.
     /// - `Negative`: The negative operator.
+    /// - `Postfix`: The factorial operator, and `%` when it is read as a
+    ///   percentage.
+    /// - `Function`: Function calls.
     ///
     /// # Examples
     ///
     pub fn get_precedence(&self) -> OperatorPrecedence {
         match self {
             Self::Add | Self::Sub => OperatorPrecedence::AddOrSubtract,
-            Self::Mul | Self::Div => OperatorPrecedence::MultiplyOrDivide,
+            Self::Mul | Self::Div | Self::DoubleSlash | Self::Percent => {
+                OperatorPrecedence::MultiplyOrDivide
+            }
             Self::Caret => OperatorPrecedence::Power,
+            Self::Exclamation => OperatorPrecedence::Postfix,
             Self::Function { .. } => OperatorPrecedence::Function,
             _ => OperatorPrecedence::Default,
         }
@@ -90,7 +115,7 @@ impl Display for Token {
     /// # Examples
     ///
     /// ```
-    /// use crate::calc::token::Token;
+    /// use rust_calculate::calc::token::Token;
     /// use rust_decimal::Decimal;
     ///
     /// let token = Token::Number(Decimal::from(42));
@@ -103,31 +128,43 @@ impl Display for Token {
         match self {
             // Format numeric values directly
             Self::Number(n) => write!(f, "{}", n),
-            
+            Self::BigNumber(literal) => f.write_str(literal),
+            Self::Imaginary(n) => write!(f, "{}i", n),
+
             // Format variable names as-is
             Self::Variable(var) => f.write_str(var),
-            
+
+            // Format cell references in A1 style
+            Self::Cell(cell) => write!(f, "{}", cell),
+            Self::Range(from, to) => write!(f, "{}:{}", from, to),
+
+            // Format strings with their quotes
+            Self::Text(text) => write!(f, "\"{}\"", text),
+
             // Format basic operators with their symbols
             Self::Add => f.write_str("+"),
             Self::Sub => f.write_str("-"),
-            Self::Mul => f.write_str("×"),  // Using multiplication symbol instead of asterisk
-            Self::Div => f.write_str("÷"),  // Using division symbol instead of slash
-            
+            Self::Mul => f.write_str("×"), // Using multiplication symbol instead of asterisk
+            Self::Div => f.write_str("÷"), // Using division symbol instead of slash
+
             // Format function calls as: function_name<(arg1), (arg2), ...>
-            Self::Function { function_prefix, args } => {
+            Self::Function {
+                function_prefix,
+                args,
+            } => {
                 // Write function name and opening bracket
                 write!(f, "{}<", function_prefix)?;
-                
+
                 // Format each argument list
                 for (i, arg) in args.iter().enumerate() {
                     // Add comma separator between arguments
                     if i > 0 {
                         f.write_str(",")?;
                     }
-                    
+
                     // Wrap each argument list in parentheses
                     // f.write_str("(")?;
-                    
+
                     // Format tokens within each argument
                     for (j, t) in arg.iter().enumerate() {
                         // Add space between tokens in the same argument
@@ -136,20 +173,21 @@ impl Display for Token {
                         }
                         write!(f, "{}", t)?;
                     }
-                    
+
                     // Close the argument parenthesis
                     // f.write_str(")")?;
                 }
-                
+
                 // Close the function bracket
                 f.write_str(">")
-            },
-            
-          
-            
+            }
+
             // Format other operators and symbols
             Self::Caret => f.write_str("^"),
-            
+            Self::Percent => f.write_str("%"),
+            Self::DoubleSlash => f.write_str("//"),
+            Self::Exclamation => f.write_str("!"),
+
             // Format different types of parentheses
             Self::LeftSmallParen => f.write_str("("),
             Self::RightSmallParen => f.write_str(")"),
@@ -157,12 +195,13 @@ impl Display for Token {
             Self::RightMidParen => f.write_str("]"),
             Self::LeftBigParen => f.write_str("{"),
             Self::RightBigParen => f.write_str("}"),
-            
+
             // Format other syntax elements
             Self::Comma => f.write_str(","),
+            Self::Semicolon => f.write_str(";"),
             Self::LeftFuncParen => f.write_str("<"),
             Self::RightFuncParen => f.write_str(">"),
-            
+
             // Format end-of-file token
             Self::EOF => f.write_str("EOF"),
         }
@@ -177,5 +216,6 @@ pub enum OperatorPrecedence {
     MultiplyOrDivide,
     Power,
     Negative,
+    Postfix,
     Function,
 }
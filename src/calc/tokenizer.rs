@@ -1,8 +1,13 @@
+use crate::calc::cell::CellRef;
+use crate::calc::chinese;
+use crate::calc::span::Span;
 use crate::calc::token::Token;
+use log::debug;
+use rust_decimal::Decimal;
 use serde::{Serialize, Serializer};
 use std::iter::Peekable;
 use std::str::Chars;
-use log::{debug, info};
+use unicode_ident::{is_xid_continue, is_xid_start};
 
 /// A tokenizer that parses an expression string into a sequence of tokens.
 ///
@@ -15,11 +20,101 @@ pub struct Tokenizer<'a> {
     original_expression: &'a str, // 存储原始表达式字符串
     end: bool,
     unexpected_char: Option<char>,
+    invalid_number: Option<String>,
+    position: usize,        // 已读取的字节数
+    full_width: bool,       // 是否将全角字符规范化为 ASCII
+    chinese_numerals: bool, // 是否将中文数字读作数字字面量
+    complex_numbers: bool,  // 是否将 4i 读作虚数字面量
+    cell_references: bool,  // 是否将 A1、B2:C10 读作单元格引用
 }
 
 impl<'a> Tokenizer<'a> {
     pub(crate) fn get_unexpected_char(&self) -> Option<char> {
-        todo!()
+        self.unexpected_char
+    }
+
+    /// Returns the numeric literal that stopped the tokenizer because it does
+    /// not fit into a `Decimal`.
+    pub(crate) fn get_invalid_number(&self) -> Option<&str> {
+        self.invalid_number.as_deref()
+    }
+
+    /// Tokenizes the whole expression, pairing every token with its span.
+    ///
+    /// Unlike the iterator, function arguments are not grouped into
+    /// `Token::Function`: a call yields a `Token::Function` without arguments
+    /// for the function name, followed by the `<`, `,` and `>` tokens of the
+    /// call, so that every token keeps its position in the source. The
+    /// result ends with `Token::EOF` unless tokenizing stopped at an error.
+    pub(crate) fn spanned_tokens(&mut self) -> Vec<(Token, Span)> {
+        let mut tokens = Vec::new();
+        loop {
+            while self.next_char_if(|c| c.is_whitespace()).is_some() {}
+            let start = self.position;
+            let Some(token) = self.next_token_internal(true, true, false) else {
+                break;
+            };
+            // 函数名后的空白也会被读入，需要从跨度中去掉
+            let text = &self.original_expression[start..self.position];
+            let span = Span::new(start, start + text.trim_end().len());
+            let end = token == Token::EOF;
+            tokens.push((token, span));
+            if end {
+                break;
+            }
+        }
+        tokens
+    }
+
+    /// Reads the next character, normalised if full-width normalisation is on.
+    ///
+    /// `position` always advances by the length of the original character,
+    /// so spans keep pointing at what the user typed.
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.next_raw_char()?;
+        Some(normalize(c, self.full_width))
+    }
+
+    fn next_char_if(&mut self, func: impl FnOnce(&char) -> bool) -> Option<char> {
+        let full_width = self.full_width;
+        let c = self
+            .expression
+            .next_if(|c| func(&normalize(*c, full_width)))?;
+        self.position += c.len_utf8();
+        Some(normalize(c, full_width))
+    }
+
+    fn next_raw_char(&mut self) -> Option<char> {
+        let c = self.expression.next()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        let full_width = self.full_width;
+        self.expression.peek().map(|c| normalize(*c, full_width))
+    }
+}
+
+/// Maps a character typed with a Chinese input method to its ASCII form:
+/// full-width forms such as `（`, `，` or `１`, the `×` and `÷` signs, the
+/// `【】` and `《》` brackets and curly double quotes. Other characters, and
+/// every character when `full_width` is false, are returned unchanged.
+fn normalize(c: char, full_width: bool) -> char {
+    if !full_width {
+        return c;
+    }
+    match c {
+        // 全角 ASCII 区：！ 到 ～
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '×' => '*',
+        '÷' => '/',
+        '【' => '[',
+        '】' => ']',
+        '《' => '<',
+        '》' => '>',
+        '“' | '”' => '"',
+        _ => c,
     }
 }
 
@@ -60,7 +155,7 @@ impl<'a> Serialize for Tokenizer<'a> {
     }
 }
 
-impl <'a> Tokenizer<'a>{
+impl<'a> Tokenizer<'a> {
     /// Creates a new Tokenizer from the given expression string.
     ///
     /// # Arguments
@@ -76,27 +171,201 @@ impl <'a> Tokenizer<'a>{
             original_expression: expression,
             end: false,
             unexpected_char: None,
+            invalid_number: None,
+            position: 0,
+            full_width: false,
+            chinese_numerals: false,
+            complex_numbers: false,
+            cell_references: false,
         }
     }
+
+    /// Turns normalisation of full-width input on or off.
+    ///
+    /// With normalisation on, punctuation typed with a Chinese input method,
+    /// such as `（１＋２）×３`, is read as its ASCII equivalent. Spans still
+    /// point at the original characters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::token::Token;
+    /// use rust_calculate::calc::tokenizer::Tokenizer;
+    /// use rust_decimal::dec;
+    ///
+    /// let tokens: Vec<_> = Tokenizer::new("３×４").full_width(true).collect();
+    /// assert_eq!(tokens, [Token::Number(dec!(3)), Token::Mul, Token::Number(dec!(4)), Token::EOF]);
+    /// ```
+    pub fn full_width(mut self, full_width: bool) -> Self {
+        self.full_width = full_width;
+        self
+    }
+
+    /// Turns reading of Chinese numeral literals on or off.
+    ///
+    /// With this on, a word made only of Chinese numeral characters, such as
+    /// `一百二十` or `三点一四`, is read as a number instead of a variable. A
+    /// malformed numeral such as `十点` stops the tokenizer like a number that
+    /// does not fit into a `Decimal`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::token::Token;
+    /// use rust_calculate::calc::tokenizer::Tokenizer;
+    /// use rust_decimal::dec;
+    ///
+    /// let tokens: Vec<_> = Tokenizer::new("一百二十 * 单价").chinese_numerals(true).collect();
+    /// assert_eq!(
+    ///     tokens,
+    ///     [Token::Number(dec!(120)), Token::Mul, Token::Variable("单价".to_string()), Token::EOF]
+    /// );
+    /// ```
+    pub fn chinese_numerals(mut self, chinese_numerals: bool) -> Self {
+        self.chinese_numerals = chinese_numerals;
+        self
+    }
+
+    /// Turns reading of imaginary literals on or off.
+    ///
+    /// With this on, a number directly followed by `i`, as in `4i` or
+    /// `0.5i`, is read as an imaginary number. An `i` that starts a longer
+    /// word, as in `2in`, is still read as the start of a variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::token::Token;
+    /// use rust_calculate::calc::tokenizer::Tokenizer;
+    /// use rust_decimal::dec;
+    ///
+    /// let tokens: Vec<_> = Tokenizer::new("3 + 4i").complex_numbers(true).collect();
+    /// assert_eq!(
+    ///     tokens,
+    ///     [Token::Number(dec!(3)), Token::Add, Token::Imaginary(dec!(4)), Token::EOF]
+    /// );
+    /// ```
+    pub fn complex_numbers(mut self, complex_numbers: bool) -> Self {
+        self.complex_numbers = complex_numbers;
+        self
+    }
+
+    /// Turns reading of A1-style cell references on or off.
+    ///
+    /// With this on, one to three uppercase letters up to `XFD` followed by
+    /// a row number, such as `B12` or `A1:C10`, are read as a cell or range.
+    /// With it off they are ordinary identifiers, so a variable may be named
+    /// `VAT1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::cell::CellRef;
+    /// use rust_calculate::calc::token::Token;
+    /// use rust_calculate::calc::tokenizer::Tokenizer;
+    ///
+    /// let tokens: Vec<_> = Tokenizer::new("B12").cell_references(true).collect();
+    /// assert_eq!(tokens, [Token::Cell(CellRef::new(2, 12)), Token::EOF]);
+    /// let tokens: Vec<_> = Tokenizer::new("B12").collect();
+    /// assert_eq!(tokens, [Token::Variable("B12".to_string()), Token::EOF]);
+    /// ```
+    pub fn cell_references(mut self, cell_references: bool) -> Self {
+        self.cell_references = cell_references;
+        self
+    }
 }
 
 #[allow(unused)]
 impl<'a> Tokenizer<'a> {
+    fn judge_function_part(&mut self) -> bool {
+        self.peek_char() == Some('<')
+    }
 
+    /// Returns true if the collected letters start an A1-style cell reference,
+    /// i.e. cell references are on and the letters are a column up to `XFD`
+    /// followed by a digit.
+    fn judge_cell_part(&mut self, words: &str) -> bool {
+        self.is_cell_column(words) && self.peek_char().is_some_and(|c| c.is_ascii_digit())
+    }
 
-    fn judge_function_part(&mut self) -> bool {
-        self.expression.peek() == Some(&'<')
+    /// Returns true if cell references are on and `words` can be the column
+    /// of a cell reference.
+    fn is_cell_column(&self, words: &str) -> bool {
+        self.cell_references && CellRef::parse_column(words).is_some()
+    }
+
+    /// Parses a cell reference such as `B12`, or a range such as `A1:B10`,
+    /// whose column letters have already been collected.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Token::Cell)` or `Some(Token::Range)` - The reference
+    /// * `None` - If the reference is malformed; the offending character is recorded
+    fn parse_cell(&mut self, column: String) -> Option<Token> {
+        let from = self.collect_cell_ref(column)?;
+        if self.next_char_if(|c| *c == ':').is_none() {
+            return Some(Token::Cell(from));
+        }
+        let column = self.collect_uppercase_chars();
+        if column.is_empty() {
+            self.unexpected_char = Some(':');
+            return None;
+        }
+        let to = self.collect_cell_ref(column)?;
+        Some(Token::Range(from, to))
+    }
+
+    fn collect_cell_ref(&mut self, mut text: String) -> Option<CellRef> {
+        let digits_start = text.len();
+        while let Some(digit) = self.next_char_if(|c| c.is_ascii_digit()) {
+            text.push(digit);
+        }
+        let cell = CellRef::parse(&text);
+        if cell.is_none() {
+            // 行号缺失或为 0
+            self.unexpected_char = Some(text[digits_start..].chars().next().unwrap_or(':'));
+        }
+        cell
+    }
+
+    fn collect_uppercase_chars(&mut self) -> String {
+        let mut letters = String::new();
+        while let Some(letter) = self.next_char_if(|c| c.is_ascii_uppercase()) {
+            letters.push(letter);
+        }
+        letters
+    }
+
+    /// Parses a string literal whose opening `"` has already been consumed.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Token::Text)` - The characters up to the closing `"`
+    /// * `None` - If the string is not closed; `"` is recorded as the unexpected character
+    fn parse_text(&mut self) -> Option<Token> {
+        let mut text = String::new();
+        // 字符串内容保持原样，不做全角规范化
+        while let Some(c) = self.next_raw_char() {
+            if normalize(c, self.full_width) == '"' {
+                return Some(Token::Text(text));
+            }
+            text.push(c);
+        }
+        self.unexpected_char = Some('"');
+        None
     }
 
     fn stepping_expression(&mut self) {
-        self.expression.next();
+        self.next_char();
     }
 
     /// Parses a function expression and its parameters.
     ///
     /// This method handles the parsing of function expressions in the format `func<param1, param2, ...>`.
     /// It groups parameters by commas, handling nested functions and expressions correctly.
-    /// The method maintains counters for different types of brackets to ensure proper nesting:
+    /// Nested function calls are kept on an explicit stack of `FunctionFrame`s instead of
+    /// recursing, so deeply nested input cannot overflow the call stack.
+    /// Each frame maintains counters for different types of brackets to ensure proper nesting:
     /// - angle: for function brackets `<` and `>`
     /// - paren: for small parentheses `(` and `)`
     /// - bracket: for mid-parentheses `[` and `]`
@@ -110,156 +379,82 @@ impl<'a> Tokenizer<'a> {
     ///
     /// A `Token::Function` containing the function name and its grouped parameters
     fn parse_function(&mut self, func_name: String) -> Token {
-        let mut args: Vec<Vec<Token>> = Vec::new();
-        let mut current_param: Vec<Token> = Vec::new();
-        let mut angle = 0; // < 计数
-        let mut paren = 0; // ( 计数
-        let mut bracket = 0; // [ 计数
-        let mut brace = 0; // { 计数
-        // 辅助函数：将当前收集的 tokens 添加到参数列表中
-        let add_current_tokens_to_args = |tokens: &mut Vec<Token>, args: &mut Vec<Vec<Token>>| {
-            if tokens.is_empty() {
-                return;
-            }
-
-            // 将当前参数添加到参数列表中
-            args.push(tokens.clone());
-            tokens.clear();
-        };
-
+        let mut frames = vec![FunctionFrame::new(func_name)];
         loop {
             let token = self.next_token_for_parse();
-            debug!("parse function token is {:?}",token);
-            match &token {
-                Some(Token::Comma) => {
-                    if paren > 0 || bracket > 0 || brace > 0 {
-                        // 在括号内的逗号作为表达式的一部分
-                        current_param.push(Token::Comma);
-                    } else if angle == 0 {
-                        // 顶层函数参数分隔符
-                        add_current_tokens_to_args(&mut current_param, &mut args);
-                    } else if angle == 1 {
-                        // 直接嵌套函数的参数分隔符，例如 abs<2,0> 中的逗号
-                        // 这里不应该将逗号添加到 current_param 中
-                        // 而是应该将当前收集的 tokens 添加到参数列表中，并清空 current_param
-                        add_current_tokens_to_args(&mut current_param, &mut args);
-                    } else {
-                        // 更深层嵌套函数的逗号，作为表达式的一部分
-                        current_param.push(Token::Comma);
-                    }
-                }
-
-                Some(Token::LeftFuncParen) => {
-                    angle += 1;
-                    if angle > 1 {
-                        // 嵌套函数的左括号
-                        current_param.push(Token::LeftFuncParen);
-                    }
-                }
-
-
-                Some(Token::RightFuncParen) => {
-                    angle -= 1;
-                    if angle == 0 && paren == 0 && bracket == 0 && brace == 0 {
-                        // 顶层函数结束
-                        add_current_tokens_to_args(&mut current_param, &mut args);
-                        break;
-                    } else {
-                        if angle > 0 {
-                            // 嵌套函数的右括号
-                            current_param.push(Token::RightFuncParen);
-                        }
-                    }
-                }
-
-                Some(Token::EOF) | None => {
-                    current_param.push(Token::EOF);
-                    // 文件结束或无更多 token
-                    add_current_tokens_to_args(&mut current_param, &mut args);
-                    break;
-                }
-
-                Some(Token::LeftSmallParen) => {
-                    paren += 1;
-                    current_param.push(Token::LeftSmallParen);
-                }
-
-                Some(Token::RightSmallParen) => {
-                    // 特殊处理：测试用例中有不匹配的右括号
-                    if paren > 0 {
-                        paren -= 1;
-                        // 只有当不是最外层的右括号时才添加
-                        if paren > 0 || angle > 0 || bracket > 0 || brace > 0 {
-                            current_param.push(Token::RightSmallParen);
-                        }
-                    }
-                    // 如果 paren 已经是 0，忽略多余的右括号
-                }
-
-                Some(Token::LeftMidParen) => {
-                    bracket += 1;
-                    current_param.push(Token::LeftMidParen);
-                }
-
-                Some(Token::RightMidParen) => {
-                    if bracket > 0 {
-                        bracket -= 1;
-                        current_param.push(Token::RightMidParen);
-                    }
-                    // 忽略多余的右中括号
-                }
-
-                Some(Token::LeftBigParen) => {
-                    brace += 1;
-                    current_param.push(Token::LeftBigParen);
-                }
-
-                Some(Token::RightBigParen) => {
-                    if brace > 0 {
-                        brace -= 1;
-                        current_param.push(Token::RightBigParen);
-                    }
-                    // 忽略多余的右大括号zs
-                }
-
-                Some(t) => {
-                    // 其他 token 直接添加到当前列表
-                    current_param.push(t.clone());
-                }
+            debug!("parse function token is {:?}", token);
+            // 嵌套函数：压入新的帧，参数收集完成后作为一个 token 放回外层
+            if let Some(Token::Function {
+                function_prefix, ..
+            }) = token
+            {
+                frames.push(FunctionFrame::new(function_prefix));
+                continue;
+            }
+            let Some(frame) = frames.last_mut() else {
+                unreachable!("the outermost frame is only popped when returning");
+            };
+            if !frame.push(token) {
+                continue;
+            }
+            let Some(function) = frames.pop().map(FunctionFrame::into_token) else {
+                unreachable!("a frame has just been finished");
+            };
+            match frames.last_mut() {
+                Some(outer) => outer.current_param.push(function),
+                None => return function,
             }
-            debug!("this is  angle:{angle}")
-        }
-
-        // 创建并返回函数 token
-        Token::Function {
-            function_prefix: func_name,
-            args,
         }
     }
 
-    fn collect_alphabetic_chars(&mut self, initial_char: char) -> String {
+    /// Collects an identifier: a Unicode XID start character or `_`, followed
+    /// by XID continue characters, which include digits and `_`.
+    ///
+    /// The identifier ends at whitespace, so `a b` is two identifiers. Dots
+    /// join identifiers into a path such as `order.total`. With cell
+    /// references on, the identifier stops before a digit that would turn
+    /// column letters into a cell reference such as `B12`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` - The identifier
+    /// * `None` - If a dot is not followed by another identifier; the dot is
+    ///   recorded as the unexpected character
+    fn collect_identifier(&mut self, initial_char: char) -> Option<String> {
         let mut words = String::with_capacity(8); // Pre-allocate reasonable capacity
         words.push(initial_char);
 
-        // Collect all consecutive alphabetic characters, ignoring whitespace
-        while let Some(word) = self
-            .expression
-            .next_if(|word| word.is_ascii_alphabetic() || word.is_whitespace())
-        {
-            if !word.is_whitespace() {
+        loop {
+            let cell = self.is_cell_column(&words);
+            if let Some(word) =
+                self.next_char_if(|word| is_xid_continue(*word) && !(cell && word.is_ascii_digit()))
+            {
                 words.push(word);
+            } else if let Some(dot) = self.next_char_if(|c| *c == '.') {
+                // 路径中的每一段都必须是标识符
+                if !self
+                    .peek_char()
+                    .is_some_and(|c| is_xid_start(c) || c == '_')
+                {
+                    self.unexpected_char = Some(dot);
+                    return None;
+                }
+                words.push(dot);
+            } else {
+                break;
             }
         }
 
-        words
+        Some(words)
     }
 
     /// Returns the next token from the expression for parsing functions.
     ///
     /// This method is specifically designed for use by the `parse_function` method.
-    /// Unlike the standard `next()` method, it does not handle function expressions
+    /// Unlike the standard `next()` method, it does not group function expressions
     /// with the `<func>` syntax, as this would lead to recursive parsing issues.
-    /// Instead, it processes all other token types and returns them.
+    /// Instead, a nested function name is returned as a `Token::Function` without
+    /// arguments, and `parse_function` tracks the nesting itself.
     ///
     /// # Returns
     ///
@@ -267,7 +462,77 @@ impl<'a> Tokenizer<'a> {
     /// * `None` - If the end of the expression has been reached or an unexpected character is encountered
     ///
     fn next_token_for_parse(&mut self) -> Option<Token> {
-        self.next_token_internal(true, true)
+        self.next_token_internal(true, true, false)
+    }
+
+    /// Consumes the `i` after a number if imaginary literals are on and the
+    /// `i` does not start a longer word.
+    fn imaginary_suffix(&mut self) -> bool {
+        if !self.complex_numbers || self.peek_char() != Some('i') {
+            return false;
+        }
+        let mut ahead = self.expression.clone();
+        ahead.next();
+        let full_width = self.full_width;
+        if ahead
+            .next()
+            .is_some_and(|c| is_xid_continue(normalize(c, full_width)))
+        {
+            return false;
+        }
+        self.next_char();
+        true
+    }
+
+    /// Returns whether the `0` just read starts a radix literal, that is
+    /// whether it is followed by `x`, `b` or `o` and another identifier
+    /// character; `parse_radix_number` then rejects characters that are not
+    /// digits of that radix, so `0o8` and `0xG` are invalid numbers.
+    ///
+    /// Otherwise the `0` is a number on its own and the letter is an
+    /// identifier, so that `0x` reads as `0 * x` with implicit multiplication.
+    fn radix_prefix_ahead(&self) -> bool {
+        let full_width = self.full_width;
+        let mut ahead = self.expression.clone().map(|c| normalize(c, full_width));
+        if !matches!(
+            ahead.next().map(|c| c.to_ascii_lowercase()),
+            Some('x' | 'b' | 'o')
+        ) {
+            return false;
+        }
+        ahead.next().is_some_and(is_xid_continue)
+    }
+
+    /// Parses a hexadecimal, binary or octal integer literal such as `0xFF`,
+    /// `0b1010` or `0o17`, after its leading `0` has been consumed.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Token::Number)` - The value of the literal
+    /// * `None` - If the literal has a digit outside its radix or is too
+    ///   large for `Decimal`; the literal is recorded as invalid
+    fn parse_radix_number(&mut self) -> Option<Token> {
+        let marker = self.next_char()?;
+        let radix = match marker.to_ascii_lowercase() {
+            'x' => 16,
+            'b' => 2,
+            _ => 8,
+        };
+        // 连续的标识符字符都属于该字面量，这样 0b102 整体报错而不是拆成 0b10 和 2
+        let mut digits = String::new();
+        while let Some(next) = self.next_char_if(|c| is_xid_continue(*c)) {
+            digits.push(next);
+        }
+        let value = i128::from_str_radix(&digits, radix)
+            .ok()
+            .and_then(|n| Decimal::try_from_i128_with_scale(n, 0).ok());
+        match value {
+            Some(n) => Some(Token::Number(n)),
+            None => {
+                self.invalid_number = Some(format!("0{marker}{digits}"));
+                None
+            }
+        }
     }
 
     /// Returns the next token from the expression with configurable behavior for special characters.
@@ -278,6 +543,8 @@ impl<'a> Tokenizer<'a> {
     ///
     /// * `include_comma` - If true, returns Token::Comma for commas; otherwise skips them
     /// * `include_right_func_paren` - If true, returns Token::RightFuncParen for '>'; otherwise skips it
+    /// * `group_function_args` - If true, collects a function call with its arguments into one
+    ///   `Token::Function`; otherwise returns a `Token::Function` without arguments for its name
     ///
     /// # Returns
     ///
@@ -287,69 +554,250 @@ impl<'a> Tokenizer<'a> {
         &mut self,
         include_comma: bool,
         include_right_func_paren: bool,
+        group_function_args: bool,
     ) -> Option<Token> {
         if self.end {
             return None;
         }
-        let option = self.expression.next();
-        match option {
-            None => {
-                self.end = true;
-                Some(Token::EOF)
+        // 空白以及被跳过的逗号、右尖括号通过循环跳过，避免递归
+        loop {
+            let option = self.next_char();
+            return match option {
+                None => {
+                    self.end = true;
+                    Some(Token::EOF)
+                }
+                Some(space) if space.is_whitespace() => continue,
+                Some('0') if self.radix_prefix_ahead() => self.parse_radix_number(),
+                // 只接受 ASCII 数字，其他数字字符（例如中文数字）可用作标识符
+                Some(num) if num.is_ascii_digit() => {
+                    let mut number = String::from(num);
+                    while let Some(next) = self.next_char_if(|c| c.is_ascii_digit()) {
+                        number.push(next)
+                    }
+                    // 小数部分
+                    if let Some(point) = self.next_char_if(|c| *c == '.') {
+                        number.push(point);
+                        while let Some(next) = self.next_char_if(|c| c.is_ascii_digit()) {
+                            number.push(next)
+                        }
+                    }
+                    // 超出 Decimal 范围或精度（28 位有效数字）的字面量保留原文，
+                    // 由求值时的数值后端转换，例如大数模式可以精确表示
+                    match (Decimal::from_str_exact(&number), self.imaginary_suffix()) {
+                        (Ok(n), true) => Some(Token::Imaginary(n)),
+                        (Ok(n), false) => Some(Token::Number(n)),
+                        (Err(_), false) => Some(Token::BigNumber(number)),
+                        (Err(_), true) => {
+                            self.invalid_number = Some(number);
+                            None
+                        }
+                    }
+                }
+                Some(word) if is_xid_start(word) || word == '_' => {
+                    let words = self.collect_identifier(word)?;
+                    if self.chinese_numerals && words.chars().all(chinese::is_chinese_numeral_char)
+                    {
+                        return match chinese::parse_chinese(&words) {
+                            Some(n) => Some(Token::Number(n)),
+                            None => {
+                                self.invalid_number = Some(words);
+                                None
+                            }
+                        };
+                    }
+                    if self.judge_cell_part(&words) {
+                        return self.parse_cell(words);
+                    }
+                    // 函数名与 < 之间允许有空白
+                    while self.next_char_if(|c| c.is_whitespace()).is_some() {}
+
+                    if self.judge_function_part() {
+                        if !group_function_args {
+                            return Some(Token::Function {
+                                function_prefix: words,
+                                args: Vec::new(),
+                            });
+                        }
+                        // consume '<'
+                        // self.stepping_expression();
+                        Some(self.parse_function(words))
+                    } else {
+                        Some(Token::Variable(words))
+                    }
+                }
+                Some('+') => Some(Token::Add),
+                Some('-') => Some(Token::Sub),
+                Some('*') => Some(Token::Mul),
+                Some('/') => match self.next_char_if(|c| *c == '/') {
+                    Some(_) => Some(Token::DoubleSlash),
+                    None => Some(Token::Div),
+                },
+                Some('%') => Some(Token::Percent),
+                Some('!') => Some(Token::Exclamation),
+                Some('^') => Some(Token::Caret),
+                Some('(') => Some(Token::LeftSmallParen),
+                Some(')') => Some(Token::RightSmallParen),
+                Some('[') => Some(Token::LeftMidParen),
+                Some(']') => Some(Token::RightMidParen),
+                Some('{') => Some(Token::LeftBigParen),
+                Some('}') => Some(Token::RightBigParen),
+                Some(',') if !include_comma => continue,
+                Some(',') => Some(Token::Comma),
+                Some(';') => Some(Token::Semicolon),
+                Some('>') if !include_right_func_paren => continue,
+                Some('>') => Some(Token::RightFuncParen),
+                Some('<') => Some(Token::LeftFuncParen),
+                Some('"') => self.parse_text(),
+                Some(c) => {
+                    self.unexpected_char = Some(c);
+                    None
+                }
+            };
+        }
+    }
+}
+
+/// A function call whose parameters are being collected by `parse_function`.
+struct FunctionFrame {
+    function_prefix: String,
+    args: Vec<Vec<Token>>,
+    current_param: Vec<Token>,
+    angle: i32,   // < 计数
+    paren: i32,   // ( 计数
+    bracket: i32, // [ 计数
+    brace: i32,   // { 计数
+}
+
+impl FunctionFrame {
+    fn new(function_prefix: String) -> Self {
+        Self {
+            function_prefix,
+            args: Vec::new(),
+            current_param: Vec::new(),
+            angle: 0,
+            paren: 0,
+            bracket: 0,
+            brace: 0,
+        }
+    }
+
+    /// 将当前收集的 tokens 添加到参数列表中
+    fn add_current_tokens_to_args(&mut self) {
+        if self.current_param.is_empty() {
+            return;
+        }
+        // 将当前参数添加到参数列表中
+        self.args.push(std::mem::take(&mut self.current_param));
+    }
+
+    /// Adds the next token to the call.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the token finished the call, either by closing it or by
+    /// reaching the end of the expression
+    fn push(&mut self, token: Option<Token>) -> bool {
+        match token {
+            Some(Token::Comma) => {
+                if self.paren > 0 || self.bracket > 0 || self.brace > 0 {
+                    // 在括号内的逗号作为表达式的一部分
+                    self.current_param.push(Token::Comma);
+                } else if self.angle <= 1 {
+                    // 函数参数分隔符，例如 abs<2,0> 中的逗号
+                    // 这里不应该将逗号添加到 current_param 中
+                    // 而是应该将当前收集的 tokens 添加到参数列表中，并清空 current_param
+                    self.add_current_tokens_to_args();
+                } else {
+                    // 更深层嵌套的逗号，作为表达式的一部分
+                    self.current_param.push(Token::Comma);
+                }
             }
-            Some(space) if space.is_whitespace() => {
-                while let Some(_) = self.expression.next_if(|c| c.is_whitespace()) {}
-                self.next_token_internal(include_comma, include_right_func_paren)
+
+            Some(Token::LeftFuncParen) => {
+                self.angle += 1;
+                if self.angle > 1 {
+                    // 嵌套的左尖括号
+                    self.current_param.push(Token::LeftFuncParen);
+                }
             }
-            Some(num) if num.is_numeric() => {
-                let mut number = String::from(num);
-                while let Some(next) = self.expression.next_if(|c| c.is_numeric()) {
-                    number.push(next)
+
+            Some(Token::RightFuncParen) => {
+                self.angle -= 1;
+                if self.angle == 0 && self.paren == 0 && self.bracket == 0 && self.brace == 0 {
+                    // 函数结束
+                    self.add_current_tokens_to_args();
+                    return true;
+                } else if self.angle > 0 {
+                    // 嵌套的右尖括号
+                    self.current_param.push(Token::RightFuncParen);
                 }
-                Some(Token::Number(number.parse().unwrap()))
             }
-            Some(word) if word.is_ascii_alphabetic() => {
-                let words = self.collect_alphabetic_chars(word);
 
-                if self.judge_function_part() {
-                    // consume '<'
-                   // self.stepping_expression();
-                    Some(self.parse_function(words))
-                } else {
-                    Some(Token::Variable(words))
+            Some(Token::EOF) | None => {
+                self.current_param.push(Token::EOF);
+                // 文件结束或无更多 token
+                self.add_current_tokens_to_args();
+                return true;
+            }
+
+            Some(Token::LeftSmallParen) => {
+                self.paren += 1;
+                self.current_param.push(Token::LeftSmallParen);
+            }
+
+            Some(Token::RightSmallParen) => {
+                // 特殊处理：测试用例中有不匹配的右括号
+                if self.paren > 0 {
+                    self.paren -= 1;
+                    // 只有当不是最外层的右括号时才添加
+                    if self.paren > 0 || self.angle > 0 || self.bracket > 0 || self.brace > 0 {
+                        self.current_param.push(Token::RightSmallParen);
+                    }
                 }
+                // 如果 paren 已经是 0，忽略多余的右括号
             }
-            Some('+') => Some(Token::Add),
-            Some('-') => Some(Token::Sub),
-            Some('*') => Some(Token::Mul),
-            Some('/') => Some(Token::Div),
-            Some('^') => Some(Token::Caret),
-            Some('(') => Some(Token::LeftSmallParen),
-            Some(')') => Some(Token::RightSmallParen),
-            Some('[') => Some(Token::LeftMidParen),
-            Some(']') => Some(Token::RightMidParen),
-            Some('{') => Some(Token::LeftBigParen),
-            Some('}') => Some(Token::RightBigParen),
-            Some(',') => {
-                if include_comma {
-                    Some(Token::Comma)
-                } else {
-                    self.next_token_internal(include_comma, include_right_func_paren)
+
+            Some(Token::LeftMidParen) => {
+                self.bracket += 1;
+                self.current_param.push(Token::LeftMidParen);
+            }
+
+            Some(Token::RightMidParen) => {
+                if self.bracket > 0 {
+                    self.bracket -= 1;
+                    self.current_param.push(Token::RightMidParen);
                 }
+                // 忽略多余的右中括号
             }
-            Some('>') => {
-                if include_right_func_paren {
-                    Some(Token::RightFuncParen)
-                } else {
-                    self.next_token_internal(include_comma, include_right_func_paren)
+
+            Some(Token::LeftBigParen) => {
+                self.brace += 1;
+                self.current_param.push(Token::LeftBigParen);
+            }
+
+            Some(Token::RightBigParen) => {
+                if self.brace > 0 {
+                    self.brace -= 1;
+                    self.current_param.push(Token::RightBigParen);
                 }
+                // 忽略多余的右大括号
             }
-            Some('<') => Some(Token::LeftFuncParen),
-            Some(c) => {
-                println!("{c}");
-                None
+
+            Some(t) => {
+                // 其他 token 直接添加到当前列表
+                self.current_param.push(t);
             }
         }
+        debug!("this is  angle:{}", self.angle);
+        false
+    }
+
+    fn into_token(self) -> Token {
+        Token::Function {
+            function_prefix: self.function_prefix,
+            args: self.args,
+        }
     }
 }
 
@@ -373,7 +821,7 @@ impl<'a> Iterator for Tokenizer<'a> {
     /// * `Some(Token)` - The next token in the expression
     /// * `None` - If the end of the expression has been reached
     fn next(&mut self) -> Option<Self::Item> {
-        self.next_token_internal(false, false)
+        self.next_token_internal(false, false, true)
     }
 }
 
@@ -381,6 +829,7 @@ impl<'a> Iterator for Tokenizer<'a> {
 mod tests {
     use super::*;
     use Token::*;
+    use log::info;
     use rust_decimal::dec;
 
     /// Tests the creation of a new Tokenizer instance.
@@ -390,7 +839,7 @@ mod tests {
     #[test]
     fn test_new() {
         let tokenizer = Tokenizer::new("1 + 2");
-        assert_eq!(tokenizer.end, false);
+        assert!(!tokenizer.end);
         assert_eq!(tokenizer.unexpected_char, None);
         let v: Vec<_> = tokenizer.collect();
         assert_eq!(v, vec![Number(dec!(1)), Add, Number(dec!(2)), EOF]);
@@ -464,8 +913,8 @@ mod tests {
         let tokenizer =
             Tokenizer::new("1 + 2 * nvl < abs < 1 + 2 * 3 + [ ( 1+ 3 ) / 2 ] ) , 0 > , 0 >");
         let v: Vec<_> = tokenizer.clone().collect();
-        debug!("{:?}",v.get(4));
-        info!("{:?}",serde_json::to_string(&v).unwrap());
+        debug!("{:?}", v.get(4));
+        info!("{:?}", serde_json::to_string(&v).unwrap());
         assert_eq!(
             v,
             vec![
@@ -507,6 +956,334 @@ mod tests {
         );
     }
 
+    /// Tests tokenization of decimal literals and unexpected characters.
+    ///
+    /// Verifies that a fractional part is kept on numbers and that the
+    /// tokenizer stops and records the first character it does not know.
+    #[test]
+    fn test_next_decimal_and_unexpected_char() {
+        let mut tokenizer = Tokenizer::new("1.25 * 3 $ 4");
+        let v: Vec<_> = tokenizer.by_ref().collect();
+        assert_eq!(v, vec![Number(dec!(1.25)), Mul, Number(dec!(3))]);
+        assert_eq!(tokenizer.get_unexpected_char(), Some('$'));
+    }
+
+    /// Tests that a literal too large or too precise for `Decimal` keeps its
+    /// text for the numeric backend, while such an imaginary literal stops
+    /// the tokenizer instead of being rounded.
+    #[test]
+    fn test_next_invalid_number() {
+        let v: Vec<_> = Tokenizer::new("1 + 123456789012345678901234567890").collect();
+        assert_eq!(
+            v,
+            vec![
+                Number(dec!(1)),
+                Add,
+                BigNumber("123456789012345678901234567890".to_string()),
+                EOF
+            ]
+        );
+        let tiny = "0.0000000000000000000000000000000001";
+        let v: Vec<_> = Tokenizer::new(tiny).collect();
+        assert_eq!(v, vec![BigNumber(tiny.to_string()), EOF]);
+        let mut tokenizer = Tokenizer::new("123456789012345678901234567890i").complex_numbers(true);
+        assert_eq!(tokenizer.by_ref().count(), 0);
+        assert_eq!(
+            tokenizer.get_invalid_number(),
+            Some("123456789012345678901234567890")
+        );
+    }
+
+    /// Tests that imaginary literals are only read when enabled and that an
+    /// `i` starting a word stays part of the word.
+    #[test]
+    fn test_next_imaginary() {
+        let v: Vec<_> = Tokenizer::new("2.5i * 2in").complex_numbers(true).collect();
+        assert_eq!(
+            v,
+            vec![
+                Imaginary(dec!(2.5)),
+                Mul,
+                Number(dec!(2)),
+                Variable("in".to_string()),
+                EOF
+            ]
+        );
+        let v: Vec<_> = Tokenizer::new("4i").collect();
+        assert_eq!(v, vec![Number(dec!(4)), Variable("i".to_string()), EOF]);
+    }
+
+    /// Tests that semicolons separate the rows of a matrix literal, also in
+    /// their full-width form.
+    #[test]
+    fn test_next_semicolon() {
+        let expected = vec![
+            LeftMidParen,
+            Number(dec!(1)),
+            Semicolon,
+            Number(dec!(2)),
+            RightMidParen,
+            EOF,
+        ];
+        let v: Vec<_> = Tokenizer::new("[1; 2]").collect();
+        assert_eq!(v, expected);
+        let v: Vec<_> = Tokenizer::new("【1；2】").full_width(true).collect();
+        assert_eq!(v, expected);
+    }
+
+    /// Tests hexadecimal, binary and octal literals and invalid ones.
+    #[test]
+    fn test_next_radix_number() {
+        let v: Vec<_> = Tokenizer::new("0xFF + 0b1010 * 0o17 - 0Xff").collect();
+        assert_eq!(
+            v,
+            vec![
+                Number(dec!(255)),
+                Add,
+                Number(dec!(10)),
+                Mul,
+                Number(dec!(15)),
+                Sub,
+                Number(dec!(255)),
+                EOF
+            ]
+        );
+        // 前缀字母后不是标识符字符时，0 单独成为数字，字母留给标识符
+        let v: Vec<_> = Tokenizer::new("0x + 0o").collect();
+        assert_eq!(
+            v,
+            vec![
+                Number(dec!(0)),
+                Variable("x".to_string()),
+                Add,
+                Number(dec!(0)),
+                Variable("o".to_string()),
+                EOF
+            ]
+        );
+        for invalid in [
+            "0o8",
+            "0xG",
+            "0b2",
+            "0b102",
+            "0o78",
+            "0xFG",
+            "0x1_0",
+            "0xFFFFFFFFFFFFFFFFFFFFFFFFF",
+        ] {
+            let mut tokenizer = Tokenizer::new(invalid);
+            assert_eq!(tokenizer.by_ref().count(), 0, "{invalid}");
+            assert_eq!(tokenizer.get_invalid_number(), Some(invalid));
+        }
+    }
+
+    /// Tests tokenization of string literals.
+    ///
+    /// Verifies that the characters between quotes are kept as they are,
+    /// including commas inside function arguments, and that an unclosed
+    /// string is reported.
+    #[test]
+    fn test_next_text() {
+        let tokenizer = Tokenizer::new(r#"round<x, 2, "HALF, EVEN">"#);
+        let v: Vec<_> = tokenizer.collect();
+        assert_eq!(
+            v,
+            vec![
+                Function {
+                    function_prefix: "round".to_string(),
+                    args: vec![
+                        vec![Variable("x".to_string())],
+                        vec![Number(dec!(2))],
+                        vec![Text("HALF, EVEN".to_string())]
+                    ]
+                },
+                EOF
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new(r#"1 + "abc"#);
+        let v: Vec<_> = tokenizer.by_ref().collect();
+        assert_eq!(v, vec![Number(dec!(1)), Add]);
+        assert_eq!(tokenizer.get_unexpected_char(), Some('"'));
+    }
+
+    /// Tests tokenization of cell references and ranges.
+    ///
+    /// Verifies that with cell references on, uppercase letters followed by
+    /// a row number become cell references, that `:` joins two references
+    /// into a range, and that the references also work inside function
+    /// arguments. Columns past `XFD` and references with cell references off
+    /// stay identifiers.
+    #[test]
+    fn test_next_cell_and_range() {
+        let tokenizer = Tokenizer::new("A1 + sum<B2:C10, AA3> * x").cell_references(true);
+        let v: Vec<_> = tokenizer.collect();
+        assert_eq!(
+            v,
+            vec![
+                Cell(CellRef::new(1, 1)),
+                Add,
+                Function {
+                    function_prefix: "sum".to_string(),
+                    args: vec![
+                        vec![Range(CellRef::new(2, 2), CellRef::new(3, 10))],
+                        vec![Cell(CellRef::new(27, 3))]
+                    ]
+                },
+                Mul,
+                Variable("x".to_string()),
+                EOF
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new("A1:2").cell_references(true);
+        assert_eq!(tokenizer.by_ref().collect::<Vec<_>>(), vec![]);
+        assert_eq!(tokenizer.get_unexpected_char(), Some(':'));
+
+        let v: Vec<_> = Tokenizer::new("ZZZ1 + XFD1")
+            .cell_references(true)
+            .collect();
+        assert_eq!(
+            v,
+            vec![
+                Variable("ZZZ1".to_string()),
+                Add,
+                Cell(CellRef::new(16_384, 1)),
+                EOF
+            ]
+        );
+        let v: Vec<_> = Tokenizer::new("VAT1 * A1:B2").collect();
+        assert_eq!(v[0], Variable("VAT1".to_string()));
+        assert_eq!(v[2], Variable("A1".to_string()));
+    }
+
+    /// Tests tokenization of Unicode identifiers.
+    ///
+    /// Verifies that Chinese variable and function names are kept unaltered,
+    /// that digits and `_` may follow the first character, that Chinese
+    /// numerals are not mistaken for digits, and that identifiers which look
+    /// like a long column name are not split into a cell reference.
+    #[test]
+    fn test_next_unicode_identifiers() {
+        let tokenizer = Tokenizer::new("单价 * 数量 + 求和<_x1, 五> - ABCD1");
+        let v: Vec<_> = tokenizer.collect();
+        assert_eq!(
+            v,
+            vec![
+                Variable("单价".to_string()),
+                Mul,
+                Variable("数量".to_string()),
+                Add,
+                Function {
+                    function_prefix: "求和".to_string(),
+                    args: vec![
+                        vec![Variable("_x1".to_string())],
+                        vec![Variable("五".to_string())]
+                    ]
+                },
+                Sub,
+                Variable("ABCD1".to_string()),
+                EOF
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new("1 + ²");
+        assert_eq!(
+            tokenizer.by_ref().collect::<Vec<_>>(),
+            vec![Number(dec!(1)), Add]
+        );
+        assert_eq!(tokenizer.get_unexpected_char(), Some('²'));
+    }
+
+    /// Tests normalisation of full-width input.
+    ///
+    /// Verifies that full-width digits and punctuation are read as ASCII only
+    /// when normalisation is on, and that string literals keep their
+    /// full-width characters.
+    #[test]
+    fn test_next_full_width() {
+        let tokenizer = Tokenizer::new("（１．５＋x）÷“（备注）”").full_width(true);
+        let v: Vec<_> = tokenizer.collect();
+        assert_eq!(
+            v,
+            vec![
+                LeftSmallParen,
+                Number(dec!(1.5)),
+                Add,
+                Variable("x".to_string()),
+                RightSmallParen,
+                Div,
+                Text("（备注）".to_string()),
+                EOF
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new("１");
+        assert_eq!(tokenizer.by_ref().collect::<Vec<_>>(), vec![]);
+        assert_eq!(tokenizer.get_unexpected_char(), Some('１'));
+    }
+
+    /// Tests tokenization of the remainder, integer division and factorial
+    /// operators.
+    ///
+    /// Verifies that two slashes form one operator, while slashes separated
+    /// by whitespace stay two divisions.
+    #[test]
+    fn test_next_remainder_and_factorial() {
+        let v: Vec<_> = Tokenizer::new("7 // 2 % n! / / 1").collect();
+        assert_eq!(
+            v,
+            vec![
+                Number(dec!(7)),
+                DoubleSlash,
+                Number(dec!(2)),
+                Percent,
+                Variable("n".to_string()),
+                Exclamation,
+                Div,
+                Div,
+                Number(dec!(1)),
+                EOF
+            ]
+        );
+    }
+
+    /// Tests Chinese numeral literals.
+    ///
+    /// Verifies that words made only of numeral characters are read as
+    /// numbers when the option is on, including inside function arguments,
+    /// that other words stay variables, and that a malformed numeral is
+    /// reported as an invalid number.
+    #[test]
+    fn test_next_chinese_numerals() {
+        let tokenizer = Tokenizer::new("两万零五 + 求和<三点五, 十> * 五金").chinese_numerals(true);
+        let v: Vec<_> = tokenizer.collect();
+        assert_eq!(
+            v,
+            vec![
+                Number(dec!(20005)),
+                Add,
+                Function {
+                    function_prefix: "求和".to_string(),
+                    args: vec![vec![Number(dec!(3.5))], vec![Number(dec!(10))]]
+                },
+                Mul,
+                Variable("五金".to_string()),
+                EOF
+            ]
+        );
+
+        let v: Vec<_> = Tokenizer::new("十").collect();
+        assert_eq!(v, vec![Variable("十".to_string()), EOF]);
+
+        let mut tokenizer = Tokenizer::new("1 + 十点").chinese_numerals(true);
+        assert_eq!(
+            tokenizer.by_ref().collect::<Vec<_>>(),
+            vec![Number(dec!(1)), Add]
+        );
+        assert_eq!(tokenizer.get_invalid_number(), Some("十点"));
+    }
+
     /// Tests tokenization of a simple function expression.
     ///
     /// Verifies that the tokenizer correctly handles a simple function call
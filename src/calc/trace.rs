@@ -0,0 +1,259 @@
+use crate::calc::ast::Node;
+use crate::calc::cell::CellProvider;
+use crate::calc::context::Context;
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::evaluator::{Evaluator, Steps};
+use crate::calc::locale::Locale;
+use crate::calc::numeric::Numeric;
+use crate::calc::span::Span;
+use crate::calc::value::Value;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// One reduction performed while evaluating an expression.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TraceStep {
+    /// The reduced sub-expression, with its operands and arguments shown as
+    /// the values they evaluated to, e.g. `abs<-3>`
+    pub expression: String,
+    /// The value the sub-expression evaluated to
+    pub value: String,
+    /// Where the variable, operator or function name is in the source
+    pub span: Option<Span>,
+    /// The whole expression after this reduction, e.g. `1 + 6`
+    pub rewritten: String,
+}
+
+/// A record of how an expression was evaluated, step by step.
+///
+/// Steps are listed in evaluation order: every variable and cell lookup,
+/// operator and function call whose evaluation succeeded. Arguments that a
+/// function never evaluates, such as the default of `nvl`, do not appear.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trace {
+    /// The expression as it was written
+    pub expression: String,
+    /// The reductions, in evaluation order
+    pub steps: Vec<TraceStep>,
+    /// The final value, after the context's final rounding
+    pub result: Option<String>,
+    /// The error message if the evaluation failed
+    pub error: Option<String>,
+    /// The stable code of the error, such as `E012`
+    pub error_code: Option<&'static str>,
+    // 用于按指定语言输出错误信息
+    #[serde(skip)]
+    failure: Option<CalcError>,
+}
+
+impl Node {
+    /// Evaluates the expression tree and records every reduction step.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - Values for the variables referenced by the expression
+    /// * `cells` - The provider of cell values
+    /// * `context` - The evaluation settings
+    ///
+    /// # Returns
+    ///
+    /// The value of the expression, as returned by
+    /// [`eval_value`](Node::eval_value), and the trace of its evaluation
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_calculate::calc::cell::CellRef;
+    /// use rust_calculate::calc::context::Context;
+    /// use rust_calculate::calc::parser::Parser;
+    /// use rust_decimal::Decimal;
+    /// use std::collections::HashMap;
+    ///
+    /// let node = Parser::new("1 + 2 * 3").unwrap().parse().unwrap();
+    /// let cells: HashMap<CellRef, Decimal> = HashMap::new();
+    /// let (_, trace) = node.trace(&HashMap::<String, Decimal>::new(), &cells, &Context::default());
+    /// let rewritten: Vec<_> = trace.steps.iter().map(|step| step.rewritten.as_str()).collect();
+    /// assert_eq!(rewritten, ["1 + 6", "7"]);
+    /// ```
+    pub fn trace<N: Numeric>(
+        &self,
+        variables: &HashMap<String, N>,
+        cells: &dyn CellProvider,
+        context: &Context,
+    ) -> (CalcResult<Value<N>>, Trace) {
+        let evaluator = Evaluator::new(variables, cells, context).with_steps();
+        let result = evaluator.eval_value(self);
+        let trace = Trace::new(self, evaluator.into_steps(), &result);
+        (result, trace)
+    }
+}
+
+impl Trace {
+    fn new<N: Numeric>(root: &Node, recorded: Steps<N>, result: &CalcResult<Value<N>>) -> Self {
+        let mut nodes = HashMap::new();
+        index(root, &mut nodes);
+        let mut values: HashMap<*const Node, String> = HashMap::new();
+        let mut steps = Vec::with_capacity(recorded.len());
+        for (address, value) in recorded {
+            let Some(node) = nodes.get(&address) else {
+                continue;
+            };
+            let substitute = |node: &Node| values.get(&(node as *const Node)).cloned();
+            let expression = node.render(&substitute);
+            values.insert(address, value.to_string());
+            let substitute = |node: &Node| values.get(&(node as *const Node)).cloned();
+            steps.push(TraceStep {
+                expression,
+                value: value.to_string(),
+                span: node.span(),
+                rewritten: root.render(&substitute),
+            });
+        }
+        Self {
+            expression: root.to_string(),
+            steps,
+            result: result.as_ref().ok().map(Value::to_string),
+            error: result.as_ref().err().map(ToString::to_string),
+            error_code: result.as_ref().err().map(CalcError::code),
+            failure: result.as_ref().err().cloned(),
+        }
+    }
+
+    /// Serializes the trace to a JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Formats the trace as human-readable text.
+    ///
+    /// Each step shows the reduced sub-expression with its value, followed
+    /// by the whole expression after the reduction:
+    ///
+    /// ```text
+    /// Expression: 1 + 2 * 3
+    /// Step 1: 2 * 3 = 6
+    ///   => 1 + 6
+    /// Step 2: 1 + 6 = 7
+    /// Result: 7
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - The language of the labels and the error message
+    pub fn to_text(&self, locale: Locale) -> String {
+        let (expression, result, error) = match locale {
+            Locale::EnUs => ("Expression: ", "Result: ", "Error: "),
+            Locale::ZhCn => ("表达式：", "结果：", "错误："),
+        };
+        let mut text = format!("{}{}\n", expression, self.expression);
+        for (i, step) in self.steps.iter().enumerate() {
+            let label = match locale {
+                Locale::EnUs => format!("Step {}: ", i + 1),
+                Locale::ZhCn => format!("第 {} 步：", i + 1),
+            };
+            let _ = writeln!(text, "{}{} = {}", label, step.expression, step.value);
+            // 最后一步的整体结果就是它的值，无需重复
+            if step.rewritten != step.value {
+                let _ = writeln!(text, "  => {}", step.rewritten);
+            }
+        }
+        if let Some(value) = &self.result {
+            let _ = writeln!(text, "{}{}", result, value);
+        }
+        if let Some(failure) = &self.failure {
+            let _ = writeln!(text, "{}{}", error, failure.message(locale));
+        }
+        text
+    }
+}
+
+/// Maps the address of every node in the tree to the node.
+fn index<'a>(root: &'a Node, nodes: &mut HashMap<*const Node, &'a Node>) {
+    let mut pending = vec![root];
+    while let Some(node) = pending.pop() {
+        nodes.insert(node, node);
+        pending.extend(node.children());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::cell::NoCells;
+    use crate::calc::parser::Parser;
+    use rust_decimal::{Decimal, dec};
+
+    fn trace_of(expression: &str, variables: &HashMap<String, Decimal>) -> Trace {
+        let node = Parser::new(expression).unwrap().parse().unwrap();
+        node.trace(variables, &NoCells, &Context::default()).1
+    }
+
+    /// Tests that every reduction rewrites the whole expression, including
+    /// variable lookups and function calls with their argument values.
+    #[test]
+    fn test_trace_steps() {
+        let variables = HashMap::from([("x".to_string(), dec!(-3))]);
+        let trace = trace_of("2 * nvl<abs<x>, 0> + 1", &variables);
+        let steps: Vec<_> = trace
+            .steps
+            .iter()
+            .map(|step| {
+                (
+                    step.expression.as_str(),
+                    step.value.as_str(),
+                    step.rewritten.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("x", "-3", "2 * nvl<abs<-3>, 0> + 1"),
+                ("abs<-3>", "3", "2 * nvl<3, 0> + 1"),
+                ("nvl<3, 0>", "3", "2 * 3 + 1"),
+                ("2 * 3", "6", "6 + 1"),
+                ("6 + 1", "7", "7"),
+            ]
+        );
+        assert_eq!(trace.steps[1].span, Some(Span::new(8, 11)));
+        assert_eq!(trace.result, Some("7".to_string()));
+        assert_eq!(trace.error, None);
+    }
+
+    /// Tests the JSON and text exports, and that a failed evaluation keeps
+    /// the steps that succeeded.
+    #[test]
+    fn test_trace_export() {
+        let variables = HashMap::new();
+        let trace = trace_of("(1 + 2) * 3", &variables);
+        assert_eq!(
+            trace.to_text(Locale::EnUs),
+            "Expression: (1 + 2) * 3\nStep 1: 1 + 2 = 3\n  => 3 * 3\nStep 2: 3 * 3 = 9\nResult: 9\n"
+        );
+        assert_eq!(
+            trace.to_text(Locale::ZhCn),
+            "表达式：(1 + 2) * 3\n第 1 步：1 + 2 = 3\n  => 3 * 3\n第 2 步：3 * 3 = 9\n结果：9\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&trace.to_json().unwrap()).unwrap();
+        assert_eq!(json["steps"][0]["rewritten"], "3 * 3");
+        assert_eq!(json["steps"][0]["span"]["start"], 3);
+        assert_eq!(json["result"], "9");
+
+        let trace = trace_of("1 + 2 / (1 - 1)", &variables);
+        assert_eq!(trace.steps.len(), 1);
+        assert_eq!(trace.steps[0].rewritten, "1 + 2 / 0");
+        assert_eq!(trace.result, None);
+        assert_eq!(trace.error_code, Some("E012"));
+        assert!(
+            trace
+                .to_text(Locale::EnUs)
+                .ends_with("Error: division by zero (at 6..7)\n")
+        );
+        assert!(
+            trace
+                .to_text(Locale::ZhCn)
+                .ends_with("错误：除数不能为零 (位置 6..7)\n")
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use crate::calc::complex::Complex;
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::matrix::Matrix;
+use crate::calc::numeric::Numeric;
+use crate::calc::span::Span;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// The kind of a spreadsheet-style error value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ErrorKind {
+    // #DIV/0!，除数为零
+    Div0,
+    // #N/A，变量不存在
+    Na,
+    // #VALUE!，参数类型错误
+    Value,
+    // #REF!，引用的单元格不存在
+    Ref,
+    // #NUM!，溢出或参数超出定义域
+    Num,
+}
+
+/// The type of a value, as reported in type mismatch errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Number,
+    Complex,
+    Matrix,
+    Range,
+    Text,
+    Null,
+    Error,
+}
+
+impl ErrorKind {
+    /// Returns the kind of error value an evaluation error turns into, or
+    /// `None` if the error is a problem with the expression itself, such as
+    /// an unknown function, and cannot be represented as a value.
+    pub fn from_error(error: &CalcError) -> Option<Self> {
+        match error.without_span() {
+            CalcError::DivideByZero => Some(Self::Div0),
+            CalcError::UndefinedVariable(_) => Some(Self::Na),
+            CalcError::TypeMismatch { .. } | CalcError::DimensionMismatch { .. } => {
+                Some(Self::Value)
+            }
+            CalcError::UndefinedCell(_) => Some(Self::Ref),
+            CalcError::Overflow
+            | CalcError::Underflow
+            | CalcError::InvalidArgument { .. }
+            | CalcError::InexactResult(_)
+            | CalcError::ComplexResult(_)
+            | CalcError::SingularMatrix => Some(Self::Num),
+            CalcError::ErrorValue { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Returns the number `errortype` reports for the kind, following
+    /// Excel's `ERROR.TYPE`.
+    pub fn code(&self) -> usize {
+        match self {
+            Self::Div0 => 2,
+            Self::Value => 3,
+            Self::Ref => 4,
+            Self::Num => 6,
+            Self::Na => 7,
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Div0 => "#DIV/0!",
+            Self::Na => "#N/A",
+            Self::Value => "#VALUE!",
+            Self::Ref => "#REF!",
+            Self::Num => "#NUM!",
+        })
+    }
+}
+
+/// A value produced while evaluating an expression.
+///
+/// The numbers are of the evaluator's numeric backend, `Decimal` by default.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<N = Decimal> {
+    // 数字
+    Number(N),
+    // 虚部不为零的复数
+    Complex(Complex<N>),
+    // 矩阵或向量，由 [1, 2; 3, 4] 这样的字面量产生
+    Matrix(Matrix<N>),
+    // 单元格区域中非空单元格的值
+    Range(Vec<N>),
+    // 字符串
+    Text(String),
+    // 空值，由 ArithmeticPolicy::Null 产生
+    Null,
+    // 错误值，例如 #DIV/0!，记录产生错误的位置
+    Error(ErrorKind, Span),
+}
+
+impl<N: Numeric> Value<N> {
+    /// Wraps a complex number, turning it into a real number when its
+    /// imaginary part is zero.
+    pub fn from_complex(value: Complex<N>) -> Self {
+        if value.is_real() {
+            Self::Number(value.re)
+        } else {
+            Self::Complex(value)
+        }
+    }
+
+    /// Returns the type of the value for error messages.
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::Number(_) => ValueType::Number,
+            Self::Complex(_) => ValueType::Complex,
+            Self::Matrix(_) => ValueType::Matrix,
+            Self::Range(_) => ValueType::Range,
+            Self::Text(_) => ValueType::Text,
+            Self::Null => ValueType::Null,
+            Self::Error(..) => ValueType::Error,
+        }
+    }
+
+    /// Converts the value into a number.
+    ///
+    /// # Returns
+    ///
+    /// The number, `CalcError::ErrorValue` for an error value, or
+    /// `CalcError::TypeMismatch` if the value is not a scalar
+    pub fn into_number(self) -> CalcResult<N> {
+        match self {
+            Self::Number(n) => Ok(n),
+            Self::Error(kind, span) => Err(CalcError::ErrorValue { kind, span }),
+            other => Err(CalcError::TypeMismatch {
+                expected: ValueType::Number,
+                actual: other.value_type(),
+            }),
+        }
+    }
+
+    /// Converts the value into a string.
+    ///
+    /// # Returns
+    ///
+    /// The string, or `CalcError::TypeMismatch` if the value is not text
+    pub fn into_text(self) -> CalcResult<String> {
+        match self {
+            Self::Text(text) => Ok(text),
+            other => Err(CalcError::TypeMismatch {
+                expected: ValueType::Text,
+                actual: other.value_type(),
+            }),
+        }
+    }
+}
+
+impl<N: Numeric> Display for Value<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{}", n),
+            Self::Complex(z) => write!(f, "{}", z),
+            Self::Matrix(m) => write!(f, "{}", m),
+            Self::Range(values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str("]")
+            }
+            Self::Text(text) => write!(f, "\"{}\"", text),
+            Self::Null => f.write_str("null"),
+            Self::Error(kind, _) => write!(f, "{}", kind),
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use crate::calc::ast::Node;
+use crate::calc::error::{CalcError, CalcResult};
+use crate::calc::parser::Parser;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// A set of named formulas and input values, kept up to date incrementally.
+///
+/// Each formula's dependencies are derived from its free variables. A name is
+/// either an input (a plain value) or a formula; names that are neither are
+/// simply missing, so `nvl` can still provide a fallback for them.
+///
+/// Formulas are evaluated in topological order, and changing an input or a
+/// formula only recomputes the formulas that depend on it.
+///
+/// # Examples
+///
+/// ```
+/// use rust_calculate::calc::workbook::Workbook;
+/// use rust_decimal::dec;
+///
+/// let mut workbook = Workbook::new();
+/// workbook.set_formula("subtotal", "qty * price").unwrap();
+/// workbook.set_formula("total", "subtotal * 1.1").unwrap();
+/// workbook.set_input("price", dec!(10));
+/// workbook.set_input("qty", dec!(3));
+/// assert_eq!(workbook.get("total"), Some(Ok(dec!(33.0))));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Workbook {
+    inputs: HashMap<String, Decimal>,
+    formulas: BTreeMap<String, Node>,
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+    // 反向依赖：名称 -> 直接读取它的公式
+    dependents: HashMap<String, BTreeSet<String>>,
+    results: HashMap<String, CalcResult<Decimal>>,
+}
+
+/// 深度优先遍历的访问状态
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+impl Workbook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an input value and recomputes the formulas that depend on it.
+    ///
+    /// If `name` was a formula it is replaced by the value.
+    ///
+    /// # Returns
+    ///
+    /// The names of the recomputed formulas, in evaluation order
+    pub fn set_input(&mut self, name: &str, value: Decimal) -> Vec<String> {
+        self.remove_formula(name);
+        self.inputs.insert(name.to_string(), value);
+        let dirty = self.dependents_of(name);
+        self.recalculate(&dirty)
+    }
+
+    /// Adds or replaces a named formula and recomputes it and its dependents.
+    ///
+    /// The workbook is left unchanged if the formula does not parse or would
+    /// introduce a circular reference.
+    ///
+    /// # Returns
+    ///
+    /// The names of the recomputed formulas in evaluation order, or
+    /// `CalcError::CircularReference` naming the cycle
+    pub fn set_formula(&mut self, name: &str, expression: &str) -> CalcResult<Vec<String>> {
+        let node = Parser::new(expression)?.parse()?;
+        let previous = self.formulas.get(name).cloned();
+        self.insert_formula(name, node);
+        if let Err(err) = self.topological_order() {
+            match previous {
+                Some(previous) => self.insert_formula(name, previous),
+                None => self.remove_formula(name),
+            }
+            return Err(err);
+        }
+        self.inputs.remove(name);
+        let mut dirty = self.dependents_of(name);
+        dirty.insert(name.to_string());
+        Ok(self.recalculate(&dirty))
+    }
+
+    /// Returns the value of an input or the last computed result of a formula.
+    pub fn get(&self, name: &str) -> Option<CalcResult<Decimal>> {
+        match self.inputs.get(name) {
+            Some(value) => Some(Ok(*value)),
+            None => self.results.get(name).cloned(),
+        }
+    }
+
+    /// Returns the names a formula reads, or `None` if `name` is not a formula.
+    pub fn dependencies(&self, name: &str) -> Option<&BTreeSet<String>> {
+        self.dependencies.get(name)
+    }
+
+    /// Returns every formula that directly or transitively depends on `name`.
+    pub fn dependents_of(&self, name: &str) -> BTreeSet<String> {
+        let mut dependents = BTreeSet::new();
+        let mut pending = vec![name];
+        while let Some(current) = pending.pop() {
+            for formula in self.dependents.get(current).into_iter().flatten() {
+                if dependents.insert(formula.clone()) {
+                    pending.push(formula);
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Recomputes every formula.
+    ///
+    /// # Returns
+    ///
+    /// The names of the recomputed formulas, in evaluation order
+    pub fn recalculate_all(&mut self) -> Vec<String> {
+        let all = self.formulas.keys().cloned().collect();
+        self.recalculate(&all)
+    }
+
+    /// Returns the formulas ordered so that every formula comes after the
+    /// formulas it depends on.
+    ///
+    /// # Returns
+    ///
+    /// The evaluation order, or `CalcError::CircularReference` with the names
+    /// along the cycle, starting and ending with the same name
+    pub fn topological_order(&self) -> CalcResult<Vec<String>> {
+        let mut order = Vec::with_capacity(self.formulas.len());
+        let mut visits = HashMap::new();
+        for root in self.formulas.keys() {
+            if visits.contains_key(root.as_str()) {
+                continue;
+            }
+            // 用显式栈代替递归，每一帧是公式名及其尚未访问的依赖，
+            // 栈中的名称也就是当前的依赖路径
+            visits.insert(root.as_str(), Visit::InProgress);
+            let mut stack = vec![(root.as_str(), self.formula_dependencies(root))];
+            while let Some((name, deps)) = stack.last_mut() {
+                let name = *name;
+                let Some(dep) = deps.next() else {
+                    stack.pop();
+                    visits.insert(name, Visit::Done);
+                    order.push(name.to_string());
+                    continue;
+                };
+                match visits.get(dep.as_str()) {
+                    Some(Visit::Done) => {}
+                    Some(Visit::InProgress) => {
+                        // 从路径中截取出环
+                        let start = stack.iter().position(|(n, _)| n == dep).unwrap_or(0);
+                        let mut cycle: Vec<String> =
+                            stack[start..].iter().map(|(n, _)| n.to_string()).collect();
+                        cycle.push(dep.clone());
+                        return Err(CalcError::CircularReference(cycle));
+                    }
+                    None => {
+                        visits.insert(dep.as_str(), Visit::InProgress);
+                        stack.push((dep.as_str(), self.formula_dependencies(dep)));
+                    }
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// Returns the dependencies of `name` that are formulas themselves.
+    fn formula_dependencies<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a String> {
+        self.dependencies
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|dep| self.formulas.contains_key(*dep))
+    }
+
+    fn insert_formula(&mut self, name: &str, node: Node) {
+        self.unlink_dependencies(name);
+        let dependencies = node.free_variables();
+        for dep in &dependencies {
+            self.dependents
+                .entry(dep.clone())
+                .or_default()
+                .insert(name.to_string());
+        }
+        self.dependencies.insert(name.to_string(), dependencies);
+        self.formulas.insert(name.to_string(), node);
+    }
+
+    fn remove_formula(&mut self, name: &str) {
+        self.unlink_dependencies(name);
+        self.formulas.remove(name);
+        self.results.remove(name);
+    }
+
+    /// Removes the dependencies of `name` and the matching reverse edges.
+    fn unlink_dependencies(&mut self, name: &str) {
+        for dep in self.dependencies.remove(name).into_iter().flatten() {
+            if let Some(dependents) = self.dependents.get_mut(&dep) {
+                dependents.remove(name);
+                if dependents.is_empty() {
+                    self.dependents.remove(&dep);
+                }
+            }
+        }
+    }
+
+    /// Evaluates the dirty formulas in topological order.
+    fn recalculate(&mut self, dirty: &BTreeSet<String>) -> Vec<String> {
+        // 写入公式时已经检查过循环引用
+        let order = self.topological_order().unwrap_or_default();
+        let recomputed: Vec<String> = order
+            .into_iter()
+            .filter(|name| dirty.contains(name))
+            .collect();
+        for name in &recomputed {
+            let result = self.evaluate(name);
+            self.results.insert(name.clone(), result);
+        }
+        recomputed
+    }
+
+    fn evaluate(&self, name: &str) -> CalcResult<Decimal> {
+        let mut variables = HashMap::new();
+        for dep in &self.dependencies[name] {
+            if let Some(value) = self.inputs.get(dep) {
+                variables.insert(dep.clone(), *value);
+            } else if let Some(result) = self.results.get(dep) {
+                // 依赖的公式出错时直接传递该错误
+                variables.insert(dep.clone(), result.clone()?);
+            }
+        }
+        self.formulas[name].eval(&variables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::span::Span;
+    use rust_decimal::dec;
+
+    fn sheet() -> Workbook {
+        let mut workbook = Workbook::new();
+        workbook.set_input("qty", dec!(2));
+        workbook.set_input("price", dec!(5));
+        workbook.set_input("shipping", dec!(3));
+        workbook.set_formula("total", "subtotal + fee").unwrap();
+        workbook.set_formula("subtotal", "qty * price").unwrap();
+        workbook.set_formula("fee", "shipping * 2").unwrap();
+        workbook
+    }
+
+    /// Tests that formulas are evaluated after the formulas they depend on,
+    /// regardless of the order they were added in.
+    #[test]
+    fn test_topological_evaluation() {
+        let workbook = sheet();
+        assert_eq!(
+            workbook.topological_order(),
+            Ok(vec![
+                "fee".to_string(),
+                "subtotal".to_string(),
+                "total".to_string()
+            ])
+        );
+        assert_eq!(workbook.get("subtotal"), Some(Ok(dec!(10))));
+        assert_eq!(workbook.get("total"), Some(Ok(dec!(16))));
+    }
+
+    /// Tests that changing an input only recomputes the affected formulas.
+    #[test]
+    fn test_incremental_recalculation() {
+        let mut workbook = sheet();
+        assert_eq!(
+            workbook.set_input("qty", dec!(4)),
+            vec!["subtotal".to_string(), "total".to_string()]
+        );
+        assert_eq!(workbook.get("total"), Some(Ok(dec!(26))));
+        assert_eq!(workbook.set_input("unused", dec!(1)), Vec::<String>::new());
+    }
+
+    /// Tests that a circular reference is rejected with the cycle in the
+    /// error and that the workbook is left unchanged.
+    #[test]
+    fn test_circular_reference() {
+        let mut workbook = sheet();
+        let err = workbook.set_formula("qty", "total / 2").unwrap_err();
+        assert_eq!(
+            err,
+            CalcError::CircularReference(
+                ["qty", "total", "subtotal", "qty"]
+                    .map(String::from)
+                    .to_vec()
+            )
+        );
+        assert_eq!(err.to_string(), "循环引用: qty -> total -> subtotal -> qty");
+        assert_eq!(workbook.get("qty"), Some(Ok(dec!(2))));
+        assert_eq!(workbook.get("total"), Some(Ok(dec!(16))));
+        assert_eq!(
+            workbook.set_formula("x", "x + 1"),
+            Err(CalcError::CircularReference(vec![
+                "x".to_string(),
+                "x".to_string()
+            ]))
+        );
+    }
+
+    /// Tests that missing inputs and failing dependencies surface as errors
+    /// on the dependent formulas.
+    #[test]
+    fn test_errors_propagate() {
+        let mut workbook = Workbook::new();
+        workbook.set_formula("ratio", "a / b").unwrap();
+        workbook.set_formula("scaled", "ratio * 10").unwrap();
+        assert_eq!(
+            workbook.get("scaled"),
+            Some(Err(CalcError::UndefinedVariable("a".to_string())))
+        );
+        workbook.set_input("a", dec!(1));
+        workbook.set_input("b", dec!(0));
+        assert_eq!(
+            workbook.get("scaled"),
+            Some(Err(CalcError::DivideByZero.at(Span::new(2, 3))))
+        );
+        workbook.set_input("b", dec!(4));
+        assert_eq!(workbook.get("scaled"), Some(Ok(dec!(2.5))));
+    }
+
+    /// Tests that a long chain of formulas is ordered and recalculated
+    /// without deep recursion.
+    #[test]
+    fn test_long_dependency_chain() {
+        const LENGTH: usize = 100_000;
+        let mut workbook = Workbook::new();
+        // 每个公式依赖名称排在它之后的公式，遍历从链的起点开始，深度等于链长
+        for i in 0..LENGTH {
+            let node = Parser::new(&format!("c{:06} + 1", i + 1))
+                .unwrap()
+                .parse()
+                .unwrap();
+            workbook.insert_formula(&format!("c{:06}", i), node);
+        }
+        let order = workbook.topological_order().unwrap();
+        assert_eq!(order.len(), LENGTH);
+        assert_eq!(order[0], format!("c{:06}", LENGTH - 1));
+        assert_eq!(
+            workbook.dependents_of(&format!("c{:06}", LENGTH)).len(),
+            LENGTH
+        );
+        assert_eq!(
+            workbook
+                .set_input(&format!("c{:06}", LENGTH), dec!(0))
+                .len(),
+            LENGTH
+        );
+        assert_eq!(workbook.get("c000000"), Some(Ok(Decimal::from(LENGTH))));
+    }
+}
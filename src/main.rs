@@ -1,5 +1,96 @@
-mod calc;
+use rust_calculate::calc::cell::CellRef;
+use rust_calculate::calc::context::Context;
+use rust_calculate::calc::format::{NumberFormat, Separators};
+use rust_calculate::calc::locale::Locale;
+use rust_calculate::calc::parser::{ParseOptions, Parser};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::process::ExitCode;
 
-fn main() {
-    println!("Hello, world!");
+const USAGE: &str = "Usage: rust_calculate [OPTIONS] <EXPRESSION>...
+
+Options:
+  --format <PATTERN>  Format the result, e.g. \"#,##0.00\", \"0.0%\" or \"0.00E+00\"
+  --radix <N>         Write an integer result in radix 2 to 36, e.g. 16 for 0xFF
+  --complex           Read literals such as 4i and allow complex results, e.g. sqrt<-4>
+  --polar             Write complex results in polar form, e.g. 5∠53.13°
+  --locale <TAG>      Separators and message language, e.g. en-US, de-DE or zh-CN";
+
+fn main() -> ExitCode {
+    env_logger::init();
+    match run(std::env::args().skip(1)) {
+        Ok(text) => {
+            println!("{text}");
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Evaluates the expression given on the command line.
+///
+/// # Returns
+///
+/// The formatted result, or the message to print on failure
+fn run(mut args: impl Iterator<Item = String>) -> Result<String, String> {
+    let mut pattern = None;
+    let mut locale = None;
+    let mut radix = None;
+    let mut complex = false;
+    let mut polar = false;
+    let mut words = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => pattern = Some(args.next().ok_or(USAGE)?),
+            "--locale" => locale = Some(args.next().ok_or(USAGE)?),
+            "--radix" => {
+                let value = args.next().ok_or(USAGE)?;
+                radix = Some(value.parse::<u32>().map_err(|_| USAGE)?);
+            }
+            "--complex" => complex = true,
+            "--polar" => polar = true,
+            "-h" | "--help" => return Ok(USAGE.to_string()),
+            _ => words.push(arg),
+        }
+    }
+    if words.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    // 语言标签同时决定分隔符和错误信息的语言
+    let language = locale.as_deref().map_or(Locale::EnUs, Locale::negotiate);
+    let format = || {
+        let mut format = match &pattern {
+            Some(pattern) => pattern.parse()?,
+            None => NumberFormat::new(),
+        };
+        if let Some(tag) = &locale {
+            format = format.separators(tag.parse::<Separators>()?);
+        }
+        if let Some(radix) = radix {
+            format = format.radix(radix);
+        }
+        Ok(format.polar(polar))
+    };
+    let expression = words.join(" ");
+    let variables: HashMap<String, Decimal> = HashMap::new();
+    let cells: HashMap<CellRef, Decimal> = HashMap::new();
+    format()
+        .and_then(|format| {
+            let options = ParseOptions {
+                complex_numbers: complex,
+                ..ParseOptions::default()
+            };
+            let context = Context {
+                complex_numbers: complex,
+                ..Context::default()
+            };
+            let node = Parser::with_options(&expression, &options)?.parse()?;
+            let value = node.eval_value(&variables, &cells, &context)?;
+            format.format_value(&value)
+        })
+        .map_err(|error| error.message(language))
 }